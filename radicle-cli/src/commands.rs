@@ -8,12 +8,16 @@ pub mod rad_checkout;
 pub mod rad_clone;
 #[path = "commands/comment.rs"]
 pub mod rad_comment;
+#[path = "commands/config.rs"]
+pub mod rad_config;
 #[path = "commands/delegate.rs"]
 pub mod rad_delegate;
 #[path = "commands/edit.rs"]
 pub mod rad_edit;
 #[path = "commands/fetch.rs"]
 pub mod rad_fetch;
+#[path = "commands/fork.rs"]
+pub mod rad_fork;
 #[path = "commands/help.rs"]
 pub mod rad_help;
 #[path = "commands/id.rs"]
@@ -36,12 +40,16 @@ pub mod rad_patch;
 pub mod rad_path;
 #[path = "commands/push.rs"]
 pub mod rad_push;
+#[path = "commands/release.rs"]
+pub mod rad_release;
 #[path = "commands/review.rs"]
 pub mod rad_review;
 #[path = "commands/rm.rs"]
 pub mod rad_rm;
 #[path = "commands/self.rs"]
 pub mod rad_self;
+#[path = "commands/storage.rs"]
+pub mod rad_storage;
 #[path = "commands/tag.rs"]
 pub mod rad_tag;
 #[path = "commands/track.rs"]
@@ -24,9 +24,16 @@ Usage
     via the standard input stream if `--stdin` is used. Using one of these
     methods disables the passphrase prompt.
 
+    With `--profile`, the named profile is created if it doesn't exist, and
+    becomes the active profile. Each profile has its own key and node. A new
+    profile has its own storage, unless `--share-storage` is used, in which case
+    it shares the storage of the default profile.
+
 Options
 
     --stdin                 Read passphrase from stdin (default: false)
+    --profile <name>        Authenticate with the named profile
+    --share-storage         Share the storage of the default profile
     --help                  Print help
 "#,
 };
@@ -34,6 +41,8 @@ Options
 #[derive(Debug)]
 pub struct Options {
     pub stdin: bool,
+    pub profile: Option<String>,
+    pub share_storage: bool,
 }
 
 impl Args for Options {
@@ -41,6 +50,8 @@ impl Args for Options {
         use lexopt::prelude::*;
 
         let mut stdin = false;
+        let mut profile = None;
+        let mut share_storage = false;
         let mut parser = lexopt::Parser::from_args(args);
 
         while let Some(arg) = parser.next()? {
@@ -48,6 +59,12 @@ impl Args for Options {
                 Long("stdin") => {
                     stdin = true;
                 }
+                Long("profile") => {
+                    profile = Some(term::args::string(&parser.value()?));
+                }
+                Long("share-storage") => {
+                    share_storage = true;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -55,18 +72,43 @@ impl Args for Options {
             }
         }
 
-        Ok((Options { stdin }, vec![]))
+        Ok((
+            Options {
+                stdin,
+                profile,
+                share_storage,
+            },
+            vec![],
+        ))
     }
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    if let Some(name) = &options.profile {
+        let home = profile::create(name, options.share_storage)?;
+
+        return match Profile::load_from(home.clone()) {
+            Ok(profile) => {
+                profile::set_active(name)?;
+                authenticate(&profile, options)
+            }
+            Err(_) => {
+                let name = name.clone();
+                init(home, options)?;
+                profile::set_active(&name)?;
+
+                term::success!("Switched to profile {}", term::format::highlight(name));
+                Ok(())
+            }
+        };
+    }
     match ctx.profile() {
         Ok(profile) => authenticate(&profile, options),
-        Err(_) => init(options),
+        Err(_) => init(profile::home()?, options),
     }
 }
 
-pub fn init(options: Options) -> anyhow::Result<()> {
+pub fn init(home: profile::Home, options: Options) -> anyhow::Result<()> {
     term::headline(format!(
         "Initializing your {} 🌱 identity",
         term::format::highlight("radicle")
@@ -84,7 +126,6 @@ pub fn init(options: Options) -> anyhow::Result<()> {
         anyhow::bail!("Error retrieving git version; please check your installation");
     }
 
-    let home = profile::home()?;
     let passphrase = if options.stdin {
         term::passphrase_stdin()
     } else {
@@ -16,6 +16,7 @@ use radicle::prelude::*;
 use radicle::rad;
 use radicle::storage;
 use radicle::storage::git::Storage;
+use radicle::storage::{FetchLimits, WriteStorage as _};
 
 use crate::commands::rad_checkout as checkout;
 use crate::commands::rad_fetch as fetch;
@@ -35,9 +36,16 @@ Usage
 
 Options
 
-    --no-announce   Do not announce our new refs to the network
-    --no-confirm    Don't ask for confirmation during clone
-    --help          Print help
+    --no-announce       Do not announce our new refs to the network
+    --no-confirm        Don't ask for confirmation during clone
+    --depth <n>         Only fetch the last <n> commits of each branch
+    --filter <filter>   Omit objects matching the filter, eg. `blob:none` or
+                        `blob:limit=<bytes>`; the checkout is a partial clone
+    --help              Print help
+
+    The identity and collaborative objects of the project are always fetched
+    in full. Limits set with `--depth` and `--filter` also apply to further
+    fetches of the project.
 
 "#,
 };
@@ -48,6 +56,7 @@ pub struct Options {
     #[allow(dead_code)]
     interactive: Interactive,
     announce: bool,
+    limits: FetchLimits,
 }
 
 impl Args for Options {
@@ -58,6 +67,7 @@ impl Args for Options {
         let mut id: Option<Id> = None;
         let mut interactive = Interactive::Yes;
         let mut announce = true;
+        let mut limits = FetchLimits::default();
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -70,6 +80,12 @@ impl Args for Options {
                 Long("announce") => {
                     announce = true;
                 }
+                Long("depth") => {
+                    limits.depth = Some(term::args::parse_value("depth", parser.value()?)?);
+                }
+                Long("filter") => {
+                    limits.filter = Some(term::args::parse_value("filter", parser.value()?)?);
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -92,6 +108,7 @@ impl Args for Options {
                 id,
                 interactive,
                 announce,
+                limits,
             },
             vec![],
         ))
@@ -108,6 +125,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         &profile.storage,
         &mut node,
         options.announce,
+        &options.limits,
     )?;
     let delegates = doc
         .delegates
@@ -166,9 +184,20 @@ pub fn clone<G: Signer>(
     storage: &Storage,
     node: &mut Node,
     announce: bool,
+    limits: &FetchLimits,
 ) -> Result<(raw::Repository, Doc<Verified>, Project), CloneError> {
     let me = *signer.public_key();
 
+    if !limits.is_empty() {
+        // Limits are set before the first fetch, so that the node applies them.
+        let repo = match storage.repository(id) {
+            Ok(repo) => repo,
+            Err(e) if e.is_not_found() => storage.create(id)?,
+            Err(e) => return Err(e.into()),
+        };
+        repo.set_fetch_limits(limits)?;
+    }
+
     // Track.
     if node.track_repo(id, Scope::default())? {
         term::success!(
@@ -0,0 +1,128 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+use radicle::profile;
+use serde_json as json;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "config",
+    description: "Manage the profile configuration",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad config [show]
+    rad config get <key>
+    rad config set <key> <value>
+    rad config unset <key>
+
+    Keys are dotted paths into the configuration, eg. `node.trackingPolicy`
+    or `cli.color`. Values are parsed as JSON, and otherwise taken as strings.
+    Unsetting a key resets it to its default value.
+
+Options
+
+    --help    Print help
+"#,
+};
+
+pub enum Operation {
+    Show,
+    Get { key: String },
+    Set { key: String, value: String },
+    Unset { key: String },
+}
+
+#[derive(Default)]
+pub enum OperationName {
+    #[default]
+    Show,
+    Get,
+    Set,
+    Unset,
+}
+
+pub struct Options {
+    op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut key: Option<String> = None;
+        let mut value: Option<String> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "show" => op = Some(OperationName::Show),
+                    "get" => op = Some(OperationName::Get),
+                    "set" => op = Some(OperationName::Set),
+                    "unset" => op = Some(OperationName::Unset),
+
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val) if key.is_none() && !matches!(op, Some(OperationName::Show)) => {
+                    key = Some(val.to_string_lossy().into_owned());
+                }
+                Value(val) if value.is_none() && matches!(op, Some(OperationName::Set)) => {
+                    value = Some(val.to_string_lossy().into_owned());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.unwrap_or_default() {
+            OperationName::Show => Operation::Show,
+            OperationName::Get => Operation::Get {
+                key: key.ok_or_else(|| anyhow!("a key must be provided"))?,
+            },
+            OperationName::Set => Operation::Set {
+                key: key.ok_or_else(|| anyhow!("a key must be provided"))?,
+                value: value.ok_or_else(|| anyhow!("a value must be provided"))?,
+            },
+            OperationName::Unset => Operation::Unset {
+                key: key.ok_or_else(|| anyhow!("a key must be provided"))?,
+            },
+        };
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, _ctx: impl term::Context) -> anyhow::Result<()> {
+    let home = profile::home()?;
+    let path = home.config();
+    let mut config = profile::Config::load(&path)?;
+
+    match options.op {
+        Operation::Show => {
+            println!("{}", json::to_string_pretty(&config)?);
+        }
+        Operation::Get { key } => match config.get(&key)? {
+            json::Value::Null => {}
+            json::Value::String(s) => println!("{s}"),
+            value => println!("{}", json::to_string_pretty(&value)?),
+        },
+        Operation::Set { key, value } => {
+            let value =
+                json::from_str(&value).unwrap_or_else(|_| json::Value::String(value.clone()));
+
+            config.set(&key, value)?;
+            config.write(&path)?;
+        }
+        Operation::Unset { key } => {
+            config.set(&key, json::Value::Null)?;
+            config.write(&path)?;
+        }
+    }
+    Ok(())
+}
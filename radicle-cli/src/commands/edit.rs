@@ -2,6 +2,9 @@ use std::ffi::OsString;
 
 use anyhow::{anyhow, Context as _};
 
+use radicle::cob::Timestamp;
+use radicle::git::fmt::refspec::PatternString;
+use radicle::identity::doc::{is_canonical_pattern, PayloadId};
 use radicle::identity::Id;
 use radicle::storage::{ReadStorage, WriteRepository};
 
@@ -20,8 +23,20 @@ Usage
     Edits the identity document pointed to by the ID. If it isn't specified,
     the current project is edited.
 
+    Archiving a project makes it read-only: nodes stop accepting issues,
+    patches and pushes from anyone but the delegates.
+
+    Canonical ref patterns, eg. `refs/heads/release/*` or `refs/tags/*`, declare
+    branches and tags, besides the default branch, whose target is agreed upon
+    by a quorum of delegates.
+
 Options
 
+    --archive           Archive the project, instead of editing the document
+    --unarchive         Unarchive the project, instead of editing the document
+    --canonical <ref>   Add a canonical ref pattern, instead of editing the document
+    --no-canonical <ref>
+                        Remove a canonical ref pattern, instead of editing the document
     --help              Print help
 "#,
 };
@@ -29,6 +44,8 @@ Options
 #[derive(Default, Debug, Eq, PartialEq)]
 pub struct Options {
     pub id: Option<Id>,
+    pub archive: Option<bool>,
+    pub canonical: Vec<(PatternString, bool)>,
 }
 
 impl Args for Options {
@@ -37,9 +54,30 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut id: Option<Id> = None;
+        let mut archive: Option<bool> = None;
+        let mut canonical = Vec::new();
 
         while let Some(arg) = parser.next()? {
             match arg {
+                Long("archive") => {
+                    archive = Some(true);
+                }
+                Long("unarchive") => {
+                    archive = Some(false);
+                }
+                Long(flag @ ("canonical" | "no-canonical")) => {
+                    let add = flag == "canonical";
+                    let pattern = parser.value()?;
+                    let pattern = PatternString::try_from(pattern.to_string_lossy().as_ref())
+                        .map_err(|e| anyhow!("invalid ref pattern {:?}: {e}", pattern))?;
+
+                    if !is_canonical_pattern(&pattern) {
+                        anyhow::bail!(
+                            "invalid ref pattern '{pattern}': must be under `refs/heads/` or `refs/tags/`"
+                        );
+                    }
+                    canonical.push((pattern, add));
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -50,7 +88,14 @@ impl Args for Options {
             }
         }
 
-        Ok((Options { id }, vec![]))
+        Ok((
+            Options {
+                id,
+                archive,
+                canonical,
+            },
+            vec![],
+        ))
     }
 }
 
@@ -70,21 +115,46 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     let repo = storage.repository(id)?;
 
-    let payload = serde_json::to_string_pretty(&project.payload)?;
-    match term::Editor::new().extension("json").edit(payload) {
-        Ok(Some(updated_payload)) => {
-            project.payload = serde_json::from_str(&updated_payload)?;
-            project.sign(&signer).and_then(|(_, sig)| {
-                project.update(
-                    signer.public_key(),
-                    "Update payload",
-                    &[(signer.public_key(), sig)],
-                    repo.raw(),
-                )
-            })?;
+    let msg = if let Some(archive) = options.archive {
+        let mut proj = project.project()?;
+        if archive {
+            proj.set_archived(Some(Timestamp::now().as_secs()));
+        } else {
+            proj.set_archived(None);
         }
-        _ => return Err(anyhow!("Operation aborted!")),
-    }
+        project.payload.insert(PayloadId::project(), proj.into());
+
+        if archive {
+            "Archive project"
+        } else {
+            "Unarchive project"
+        }
+    } else if !options.canonical.is_empty() {
+        for (pattern, add) in options.canonical {
+            project.canonical_refs.retain(|p| p != &pattern);
+            if add {
+                project.canonical_refs.push(pattern);
+            }
+        }
+        "Update canonical refs"
+    } else {
+        let payload = serde_json::to_string_pretty(&project.payload)?;
+        match term::Editor::new().extension("json").edit(payload) {
+            Ok(Some(updated_payload)) => {
+                project.payload = serde_json::from_str(&updated_payload)?;
+            }
+            _ => return Err(anyhow!("Operation aborted!")),
+        }
+        "Update payload"
+    };
+    project.sign(&signer).and_then(|(_, sig)| {
+        project.update(
+            signer.public_key(),
+            msg,
+            &[(signer.public_key(), sig)],
+            repo.raw(),
+        )
+    })?;
 
     term::success!("Update successful!");
 
@@ -0,0 +1,92 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle::identity::Id;
+use radicle::rad;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "fork",
+    description: "Create a new project from an existing one",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad fork <rid> [<option>...]
+
+    Creates a new project, with its own identity, from an existing project
+    in storage. You become the sole delegate of the new project, and the
+    upstream project is recorded in its identity document.
+
+    To contribute to a project without creating a new one, use `rad clone`
+    instead.
+
+Options
+
+    --name <name>       Name of the new project (default: the upstream name)
+    --help              Print help
+"#,
+};
+
+pub struct Options {
+    upstream: Id,
+    name: Option<String>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut upstream: Option<Id> = None;
+        let mut name: Option<String> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("name") => {
+                    name = Some(term::args::string(&parser.value()?));
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if upstream.is_none() => {
+                    upstream = Some(term::args::rid(&val)?);
+                }
+                _ => return Err(anyhow::anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                upstream: upstream
+                    .ok_or_else(|| anyhow!("an `rid` must be provided; see `rad fork --help`"))?,
+                name,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let spinner = term::spinner(format!("Forking {}...", options.upstream));
+    let (id, doc, _) = rad::hard_fork(options.upstream, options.name, &signer, &profile.storage)?;
+    spinner.finish();
+
+    term::success!(
+        "Project {} forked from {} as {}",
+        term::format::highlight(doc.project()?.name()),
+        term::format::dim(options.upstream),
+        term::format::highlight(id)
+    );
+    term::tip!(
+        "To create a working copy, run {}",
+        term::format::secondary(format!("`rad checkout {id}`"))
+    );
+
+    Ok(())
+}
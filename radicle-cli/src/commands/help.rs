@@ -17,8 +17,10 @@ const COMMANDS: &[Help] = &[
     rad_auth::HELP,
     rad_checkout::HELP,
     rad_clone::HELP,
+    rad_config::HELP,
     rad_edit::HELP,
     rad_fetch::HELP,
+    rad_fork::HELP,
     rad_help::HELP,
     rad_id::HELP,
     rad_init::HELP,
@@ -30,9 +32,11 @@ const COMMANDS: &[Help] = &[
     rad_patch::HELP,
     rad_path::HELP,
     rad_push::HELP,
+    rad_release::HELP,
     rad_review::HELP,
     rad_rm::HELP,
     rad_self::HELP,
+    rad_storage::HELP,
     rad_tag::HELP,
     rad_track::HELP,
     rad_unassign::HELP,
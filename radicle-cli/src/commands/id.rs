@@ -2,6 +2,7 @@ use std::{ffi::OsString, str::FromStr as _};
 
 use anyhow::{anyhow, Context as _};
 use radicle::cob::identity::{self, Proposal, Proposals, Revision, RevisionId};
+use radicle::cob::store;
 use radicle::git::Oid;
 use radicle::identity::Identity;
 use radicle::prelude::{Did, Doc};
@@ -28,6 +29,8 @@ Usage
     rad id (show|rebase) <id> [--rev <revision id>]
     rad id show <id> [--rev <revision id>] [--revisions]
     rad id (accept|reject|close|commit) [--rev <revision id>] [--no-confirm]
+    rad id log
+    rad id diff <commit> <commit>
 
 Options
         --help                 Print help
@@ -93,6 +96,11 @@ pub enum Operation {
     Close {
         id: Rev,
     },
+    Log,
+    Diff {
+        old: Rev,
+        new: Rev,
+    },
 }
 
 #[derive(Default, PartialEq, Eq)]
@@ -107,6 +115,8 @@ pub enum OperationName {
     List,
     Commit,
     Close,
+    Log,
+    Diff,
 }
 
 pub struct Options {
@@ -128,6 +138,7 @@ impl Args for Options {
         let mut threshold: Option<usize> = None;
         let mut interactive = Interactive::Yes;
         let mut show_revisions = false;
+        let mut revs: Vec<Rev> = Vec::new();
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -153,6 +164,8 @@ impl Args for Options {
                     "r" | "reject" => op = Some(OperationName::Reject),
                     "commit" => op = Some(OperationName::Commit),
                     "close" => op = Some(OperationName::Close),
+                    "log" => op = Some(OperationName::Log),
+                    "diff" => op = Some(OperationName::Diff),
 
                     unknown => anyhow::bail!("unknown operation '{}'", unknown),
                 },
@@ -173,6 +186,9 @@ impl Args for Options {
                 Long("revisions") => {
                     show_revisions = true;
                 }
+                Value(val) if op == Some(OperationName::Diff) => {
+                    revs.push(Rev::from(string(&val)));
+                }
                 Value(val) if op.is_some() => {
                     let val = string(&val);
                     id = Some(Rev::from(val));
@@ -223,6 +239,11 @@ impl Args for Options {
             OperationName::Close => Operation::Close {
                 id: id.ok_or_else(|| anyhow!("a proposal id must be provided"))?,
             },
+            OperationName::Log => Operation::Log,
+            OperationName::Diff => match <[Rev; 2]>::try_from(revs) {
+                Ok([old, new]) => Operation::Diff { old, new },
+                Err(_) => anyhow::bail!("exactly two identity commits must be provided"),
+            },
         };
         Ok((Options { op, interactive }, vec![]))
     }
@@ -377,7 +398,14 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             let mut timestamped = Vec::new();
             let mut no_latest = Vec::new();
             for result in proposals.all()? {
-                let (id, proposal, _) = result?;
+                let (id, proposal, _) = match result {
+                    Ok(r) => r,
+                    Err(err @ (store::Error::Archived | store::Error::Policy(..))) => {
+                        term::warning(&format!("Skipping rejected proposal: {err}"));
+                        continue;
+                    }
+                    Err(err) => return Err(err.into()),
+                };
                 match proposal.latest() {
                     None => no_latest.push((id, proposal)),
                     Some((_, revision)) => {
@@ -428,6 +456,45 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 print(&proposal, &previous, None)?;
             }
         }
+        Operation::Log => {
+            let mut t = term::Table::new(term::table::TableOptions::default());
+            let versions =
+                Identity::versions(previous.head, &repo)?.collect::<Result<Vec<_>, _>>()?;
+
+            for version in versions.into_iter().rev() {
+                let verified = if version.is_verified() {
+                    term::format::badge_positive("verified")
+                } else {
+                    term::format::badge_negative("unverified")
+                };
+                let mut signers = version.at.sigs.keys().collect::<Vec<_>>();
+                signers.sort();
+
+                t.push([
+                    term::format::yellow(version.at.commit.to_string()),
+                    term::format::dim(format!("r{}", version.revision)),
+                    term::format::italic(format!(
+                        "{}/{} signature(s)",
+                        version.quorum, version.threshold
+                    )),
+                    verified,
+                    term::format::tertiary(
+                        signers
+                            .into_iter()
+                            .map(term::format::node)
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                ]);
+            }
+            t.print();
+        }
+        Operation::Diff { old, new } => {
+            let old = Doc::<Verified>::load_at(old.resolve_commit(&repo.backend)?, &repo)?;
+            let new = Doc::<Verified>::load_at(new.resolve_commit(&repo.backend)?, &repo)?;
+
+            print!("{}", term::proposal::doc_diff(&old.doc, &new.doc)?);
+        }
         Operation::Show {
             id,
             rev,
@@ -8,6 +8,7 @@ use anyhow::{anyhow, bail, Context as _};
 
 use radicle::crypto::ssh;
 use radicle::git::RefString;
+use radicle::identity::Visibility;
 use radicle::node::tracking::Scope;
 use radicle::node::{Handle, NodeId};
 
@@ -35,6 +36,10 @@ Options
     -u, --set-upstream       Setup the upstream of the default branch
         --setup-signing      Setup the radicle key as a signing key for this repository
         --announce           Announce the new project to the network
+        --private            Make the project private: it is never announced, and is
+                             only served to delegates and allowed nodes
+        --allow <did>        Allow the given node to fetch the private project (may be
+                             specified multiple times)
         --no-confirm         Don't ask for confirmation during setup
     -v, --verbose            Verbose mode
         --help               Print help
@@ -51,6 +56,7 @@ pub struct Options {
     pub setup_signing: bool,
     pub set_upstream: bool,
     pub announce: bool,
+    pub visibility: Visibility,
     pub verbose: bool,
     pub track: bool,
 }
@@ -69,6 +75,8 @@ impl Args for Options {
         let mut set_upstream = false;
         let mut setup_signing = false;
         let mut announce = false;
+        let mut private = false;
+        let mut allow = Vec::new();
         let mut track = true;
         let mut verbose = false;
 
@@ -115,6 +123,12 @@ impl Args for Options {
                 Long("announce") => {
                     announce = true;
                 }
+                Long("private") => {
+                    private = true;
+                }
+                Long("allow") => {
+                    allow.push(term::args::did(&parser.value()?)?);
+                }
                 Long("no-confirm") => {
                     interactive = Interactive::No;
                 }
@@ -134,6 +148,14 @@ impl Args for Options {
             }
         }
 
+        let visibility = if private {
+            Visibility::private(allow)
+        } else if !allow.is_empty() {
+            bail!("`--allow` can only be used with `--private`");
+        } else {
+            Visibility::Public
+        };
+
         Ok((
             Options {
                 path,
@@ -144,6 +166,7 @@ impl Args for Options {
                 set_upstream,
                 setup_signing,
                 announce,
+                visibility,
                 track,
                 verbose,
             },
@@ -207,11 +230,12 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
     let mut node = radicle::Node::new(profile.socket());
     let mut spinner = term::spinner("Initializing...");
 
-    match radicle::rad::init(
+    match radicle::rad::init_with_visibility(
         &repo,
         &name,
         &description,
         branch,
+        options.visibility,
         &signer,
         &profile.storage,
     ) {
@@ -274,7 +298,12 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
             );
             term::indented(term::format::secondary("rad ."));
 
-            if !options.announce {
+            if !doc.visibility.is_public() {
+                term::blank();
+                term::info!(
+                    "Your project is private: it will only be served to its delegates and allowed nodes."
+                );
+            } else if !options.announce {
                 term::blank();
                 term::info!("To publish your project to the network, run:");
                 term::indented(term::format::secondary("rad push"));
@@ -1,5 +1,6 @@
 #![allow(clippy::or_fun_call)]
 use std::ffi::OsString;
+use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str::FromStr;
@@ -8,10 +9,13 @@ use anyhow::{anyhow, Context as _};
 use chrono::prelude::*;
 use json_color::{Color, Colorizer};
 
+use radicle::cob::Timestamp;
 use radicle::crypto::Unverified;
 use radicle::identity::Untrusted;
-use radicle::identity::{Doc, Id};
-use radicle::storage::{ReadRepository, ReadStorage};
+use radicle::identity::{Did, Doc, Id};
+use radicle::storage::git::divergence;
+use radicle::storage::refs::SignedRefs;
+use radicle::storage::{ReadRepository, ReadStorage, WriteRepository};
 
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
@@ -32,11 +36,16 @@ Usage
 
 Options
 
-    --id        Return the repository identifier (RID)
-    --payload   Inspect the repository's identity payload
-    --refs      Inspect the repository's refs on the local device (requires `tree`)
-    --history   Show the history of the repository identity document
-    --help      Print help
+    --id            Return the repository identifier (RID)
+    --payload       Inspect the repository's identity payload
+    --project       Show the project name, description, default branch and metadata
+    --refs          Inspect the repository's refs on the local device (requires `tree`)
+    --history       Show the history of the repository identity document
+    --canonical     Show the canonical refs, and how each delegate's refs diverge
+    --divergence    Show how the delegates' default branches diverge, and what
+                    prevents them from reaching a quorum
+    --sigrefs       Show the history of each remote's signed refs
+    --help          Print help
 "#,
 };
 
@@ -44,7 +53,11 @@ Options
 pub enum Target {
     Refs,
     Payload,
+    Project,
     History,
+    Canonical,
+    Divergence,
+    Sigrefs,
     #[default]
     Id,
 }
@@ -74,9 +87,21 @@ impl Args for Options {
                 Long("payload") => {
                     target = Target::Payload;
                 }
+                Long("project") => {
+                    target = Target::Project;
+                }
                 Long("history") => {
                     target = Target::History;
                 }
+                Long("canonical") => {
+                    target = Target::Canonical;
+                }
+                Long("divergence") => {
+                    target = Target::Divergence;
+                }
+                Long("sigrefs") => {
+                    target = Target::Sigrefs;
+                }
                 Long("id") => {
                     target = Target::Id;
                 }
@@ -114,6 +139,16 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     if options.target == Target::Id {
         term::info!("{}", term::format::highlight(id.urn()));
+
+        if let Some(archived) = ctx.profile().ok().and_then(|profile| {
+            let repo = profile.storage.repository(id).ok()?;
+            repo.identity().ok()?.doc.archived()
+        }) {
+            term::warning(&format!(
+                "This project was archived {}",
+                term::format::timestamp(&Timestamp::from(archived))
+            ));
+        }
         return Ok(());
     }
 
@@ -142,10 +177,50 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 colorizer().colorize_json_str(&serde_json::to_string_pretty(&project.payload)?)?
             );
         }
+        Target::Project => {
+            let proj = project.project()?;
+            let metadata = proj.metadata();
+            let mut table = term::Table::new(term::table::TableOptions::default());
+            let mut row = |key: &str, value: String| {
+                table.push([term::format::bold(key.to_owned()), term::Paint::new(value)]);
+            };
+
+            row("name", proj.name().to_owned());
+            row("description", proj.description().to_owned());
+            row("default branch", proj.default_branch().to_string());
+            if let Some(homepage) = &metadata.homepage {
+                row("homepage", homepage.clone());
+            }
+            if let Some(license) = &metadata.license {
+                row("license", license.clone());
+            }
+            if !metadata.keywords.is_empty() {
+                row("keywords", metadata.keywords.join(", "));
+            }
+            if let Some(logo) = &metadata.logo {
+                row("logo", logo.clone());
+            }
+            row("issues", metadata.issue_policy.to_string());
+            row("patches", metadata.patch_policy.to_string());
+            for mirror in &metadata.mirrors {
+                row("mirror", mirror.clone());
+            }
+            if let Some(archived) = metadata.archived {
+                row(
+                    "archived",
+                    term::format::timestamp(&Timestamp::from(archived)),
+                );
+            }
+            for (key, value) in proj.unknown() {
+                row(key, value.to_string());
+            }
+            table.print();
+        }
         Target::History => {
             let repo = storage.repository(id)?;
             let head = Doc::<Untrusted>::head(signer.public_key(), &repo)?;
             let history = repo.revwalk(head)?;
+            let mut out = String::new();
 
             for oid in history {
                 let oid = oid?.into();
@@ -166,35 +241,128 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 .with_timezone(&timezone)
                 .to_rfc2822();
 
-                println!(
+                writeln!(
+                    out,
                     "{} {}",
                     term::format::yellow("commit"),
                     term::format::yellow(oid),
-                );
+                )?;
                 if let Ok(parent) = tip.parent_id(0) {
-                    println!("parent {parent}");
+                    writeln!(out, "parent {parent}")?;
                 }
-                println!("blob   {}", blob.id());
-                println!("date   {time}");
-                println!();
+                writeln!(out, "blob   {}", blob.id())?;
+                writeln!(out, "date   {time}")?;
+                writeln!(out)?;
 
                 if let Some(msg) = tip.message() {
                     for line in msg.lines() {
                         if line.is_empty() {
-                            println!();
+                            writeln!(out)?;
                         } else {
-                            term::indented(term::format::dim(line));
+                            writeln!(out, "{}{}", term::TAB, term::format::dim(line))?;
                         }
                     }
-                    term::blank();
+                    writeln!(out)?;
                 }
 
                 let json =
                     colorizer().colorize_json_str(&serde_json::to_string_pretty(&content)?)?;
                 for line in json.lines() {
-                    println!(" {line}");
+                    writeln!(out, " {line}")?;
+                }
+                writeln!(out)?;
+            }
+            term::pager::page(&out)?;
+        }
+        Target::Canonical => {
+            let repo = storage.repository(id)?;
+
+            for r in repo.canonical_refs()? {
+                match r.target {
+                    Some(target) => println!("{} {}", term::format::highlight(&r.name), target),
+                    None => println!(
+                        "{} {}",
+                        term::format::highlight(&r.name),
+                        term::format::negative("(no quorum)")
+                    ),
+                }
+                for (remote, head) in &r.heads {
+                    let divergence = match r.target {
+                        Some(target) if target != *head && !r.is_tag() => {
+                            let (ahead, behind) = repo.raw().graph_ahead_behind(**head, *target)?;
+                            format!("+{ahead} -{behind}")
+                        }
+                        _ => String::new(),
+                    };
+                    term::indented(format!(
+                        "{} {head} {}",
+                        term::format::tertiary(Did::from(*remote)),
+                        term::format::dim(divergence)
+                    ));
+                }
+            }
+        }
+        Target::Divergence => {
+            let repo = storage.repository(id)?;
+            let report = divergence::report(&repo)?;
+
+            match report.canonical {
+                Some(head) => println!("{} {}", term::format::highlight(&report.branch), head),
+                None => println!(
+                    "{} {}",
+                    term::format::highlight(&report.branch),
+                    term::format::negative("(no canonical head)")
+                ),
+            }
+            for delegate in &report.delegates {
+                let did = Did::from(delegate.remote);
+                let Some(head) = delegate.head else {
+                    term::indented(format!(
+                        "{} {}",
+                        term::format::tertiary(did),
+                        term::format::negative("(missing)")
+                    ));
+                    continue;
+                };
+                let canonical = delegate
+                    .canonical
+                    .map(|c| format!("{c} canonical"))
+                    .unwrap_or_default();
+
+                term::indented(format!(
+                    "{} {head} {}",
+                    term::format::tertiary(did),
+                    term::format::dim(canonical)
+                ));
+                for (other, ahead_behind) in &delegate.others {
+                    term::indented(term::format::dim(format!(
+                        "  {ahead_behind} {}",
+                        Did::from(*other)
+                    )));
+                }
+            }
+            if let Some(blocking) = report.blocking {
+                term::warning(&format!(
+                    "No quorum on {} (threshold {}): {blocking}",
+                    report.branch, report.threshold
+                ));
+            }
+        }
+        Target::Sigrefs => {
+            let repo = storage.repository(id)?;
+
+            for remote in repo.remote_ids()? {
+                let remote = remote?;
+
+                println!("{}", term::format::tertiary(Did::from(remote)));
+                for entry in SignedRefs::history(&remote, &repo)? {
+                    term::indented(format!(
+                        "{} {} {}",
+                        term::format::yellow(entry.at),
+                        term::format::timestamp(&Timestamp::from(entry.timestamp as u64)),
+                        term::format::dim(format!("({} ref(s))", entry.sigrefs.len()))
+                    ));
                 }
-                println!();
             }
         }
         Target::Id => {
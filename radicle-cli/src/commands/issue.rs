@@ -298,7 +298,14 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
             let mut t = term::Table::new(term::table::TableOptions::default());
             for result in issues.all()? {
-                let (id, issue, _) = result?;
+                let (id, issue, _) = match result {
+                    Ok(r) => r,
+                    Err(err @ (cob::store::Error::Archived | cob::store::Error::Policy(..))) => {
+                        term::warning(&format!("Skipping rejected issue: {err}"));
+                        continue;
+                    }
+                    Err(err) => return Err(err.into()),
+                };
                 let assigned: Vec<_> = issue.assigned().collect();
 
                 if Some(true) == assignee.map(|a| !assigned.contains(&Did::from(a))) {
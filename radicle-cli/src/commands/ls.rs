@@ -52,8 +52,13 @@ pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         let Ok((_, head)) = repo.head() else { return };
         let Ok(proj) = repo.project_of(profile.id()) else { return };
         let head = term::format::oid(head);
+        let name = if proj.archived().is_some() {
+            format!("{} (archived)", proj.name())
+        } else {
+            proj.name().to_owned()
+        };
         table.push([
-            term::format::bold(proj.name().to_owned()),
+            term::format::bold(name),
             term::format::tertiary(id.urn()),
             term::format::secondary(head),
             term::format::italic(proj.description().to_owned()),
@@ -23,6 +23,7 @@ Usage
     rad node status
     rad node (start|stop)
     rad node connect <nid> <addr>
+    rad node events
     rad node routing
     rad node tracking [--repos|--nodes]
 
@@ -40,6 +41,7 @@ pub struct Options {
 
 pub enum Operation {
     Connect { nid: NodeId, addr: Address },
+    Events,
     Routing,
     Start,
     Status,
@@ -57,6 +59,7 @@ pub enum TrackingMode {
 #[derive(Default)]
 pub enum OperationName {
     Connect,
+    Events,
     Routing,
     Start,
     #[default]
@@ -82,6 +85,7 @@ impl Args for Options {
                 }
                 Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
                     "connect" => op = Some(OperationName::Connect),
+                    "events" => op = Some(OperationName::Events),
                     "routing" => op = Some(OperationName::Routing),
                     "start" => op = Some(OperationName::Start),
                     "status" => op = Some(OperationName::Status),
@@ -118,6 +122,7 @@ impl Args for Options {
                 nid: nid.ok_or_else(|| anyhow!("a NID must be provided"))?,
                 addr: addr.ok_or_else(|| anyhow!("an address must be provided"))?,
             },
+            OperationName::Events => Operation::Events,
             OperationName::Routing => Operation::Routing,
             OperationName::Start => Operation::Start,
             OperationName::Status => Operation::Status,
@@ -138,6 +143,10 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             let mut node = Node::new(profile.socket());
             control::connect(&mut node, nid, addr)?
         }
+        Operation::Events => {
+            let node = Node::new(profile.socket());
+            control::events(&node)?;
+        }
         Operation::Routing => {
             let store =
                 radicle::node::routing::Table::reader(profile.home.node().join(ROUTING_DB_FILE))?;
@@ -145,8 +154,8 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         }
         Operation::Start => control::start()?,
         Operation::Status => {
-            let node = Node::new(profile.socket());
-            control::status(&node);
+            let mut node = Node::new(profile.socket());
+            control::status(&mut node)?;
         }
         Operation::Stop => {
             let node = Node::new(profile.socket());
@@ -35,10 +35,33 @@ pub fn connect(node: &mut Node, nid: NodeId, addr: Address) -> anyhow::Result<()
     Ok(())
 }
 
-pub fn status(node: &Node) {
-    if node.is_running() {
-        term::success!("The node is {}", term::format::positive("running"));
-    } else {
+pub fn events(node: &Node) -> anyhow::Result<()> {
+    for event in node.subscribe()? {
+        println!("{}", serde_json::to_string(&event?)?);
+    }
+    Ok(())
+}
+
+pub fn status(node: &mut Node) -> anyhow::Result<()> {
+    if !node.is_running() {
         term::info!("The node is {}", term::format::negative("stopped"));
+        return Ok(());
     }
+    term::success!("The node is {}", term::format::positive("running"));
+
+    let rejections = node.rejections()?;
+    if !rejections.is_empty() {
+        term::blank();
+        term::info!("Fetches rejected for exceeding a storage quota:");
+
+        for r in rejections {
+            term::info!(
+                "{} from {}: {}",
+                term::format::tertiary(r.rid),
+                term::format::node(&r.from),
+                term::format::dim(r.reason)
+            );
+        }
+    }
+    Ok(())
 }
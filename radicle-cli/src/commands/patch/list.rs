@@ -63,7 +63,7 @@ fn print(
     let mut author_info = term::Line::spaced([
         term::format::positive("●").into(),
         term::format::default("opened by").into(),
-        term::format::tertiary(patch.author().id()).into(),
+        term::format::tertiary(term::format::did(patch.author().id(), repository)).into(),
     ]);
 
     if you {
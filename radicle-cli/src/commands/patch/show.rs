@@ -58,7 +58,7 @@ pub fn run(
     ]);
     attrs.push([
         term::format::tertiary("Author".to_owned()),
-        term::format::default(patch.author().id().to_string()),
+        term::format::default(term::format::did(patch.author().id(), storage)),
     ]);
     attrs.push([
         term::format::tertiary("Status".to_owned()),
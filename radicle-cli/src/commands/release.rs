@@ -0,0 +1,220 @@
+use std::ffi::OsString;
+
+use anyhow::{anyhow, Context as _};
+
+use radicle::git::{Oid, RefString};
+use radicle::identity::{Did, Id};
+use radicle::release;
+use radicle::storage::{ReadRepository, ReadStorage, WriteRepository};
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "release",
+    description: "Manage project releases",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad release create <name> [--target <oid>] [--notes <text>] [<option>...]
+    rad release list [<option>...]
+    rad release verify <name> [<option>...]
+
+    A release is a tag signed by the project delegates. It becomes canonical
+    once enough delegates to reach the identity threshold sign the same target.
+
+    The `create` command signs a release of the given target, or of the
+    canonical head of the default branch if no target is given. To co-sign a
+    release created by another delegate, run `create` with the same name and
+    target.
+
+Options
+
+    --repo <rid>        The repository to operate on (default: cwd)
+    --target <oid>      The object to release (create only)
+    --notes <text>      Release notes (create only)
+    --help              Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+enum OperationName {
+    Create,
+    List,
+    Verify,
+}
+
+#[derive(Debug)]
+enum Operation {
+    Create {
+        name: RefString,
+        target: Option<Oid>,
+        notes: String,
+    },
+    List,
+    Verify {
+        name: RefString,
+    },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    op: Operation,
+    rid: Option<Id>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut name: Option<RefString> = None;
+        let mut target: Option<Oid> = None;
+        let mut notes = String::new();
+        let mut rid: Option<Id> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("repo") => {
+                    rid = Some(term::args::rid(&parser.value()?)?);
+                }
+                Long("target") if op == Some(OperationName::Create) => {
+                    target = Some(term::args::parse_value("target", parser.value()?)?);
+                }
+                Long("notes") if op == Some(OperationName::Create) => {
+                    notes = term::args::string(&parser.value()?);
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "create" => op = Some(OperationName::Create),
+                    "list" => op = Some(OperationName::List),
+                    "verify" => op = Some(OperationName::Verify),
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val) if op != Some(OperationName::List) && name.is_none() => {
+                    let val = val.to_string_lossy();
+                    let val = RefString::try_from(val.as_ref())
+                        .map_err(|_| anyhow!("invalid release name '{}'", val))?;
+
+                    name = Some(val);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::Create => Operation::Create {
+                name: name.ok_or_else(|| anyhow!("a release name must be provided"))?,
+                target,
+                notes,
+            },
+            OperationName::List => Operation::List,
+            OperationName::Verify => Operation::Verify {
+                name: name.ok_or_else(|| anyhow!("a release name must be provided"))?,
+            },
+        };
+
+        Ok((Options { op, rid }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let rid = match options.rid {
+        Some(rid) => rid,
+        None => {
+            let (_, rid) = radicle::rad::cwd()
+                .context("Current directory is not a radicle project; use `--repo`")?;
+            rid
+        }
+    };
+    let repo = profile.storage.repository(rid)?;
+
+    match options.op {
+        Operation::Create {
+            name,
+            target,
+            notes,
+        } => {
+            let signer = term::signer(&profile)?;
+            let target = match target {
+                Some(target) => target,
+                None => repo.head()?.1,
+            };
+            release::create(&repo, &name, target, &notes, &signer)?;
+            repo.sign_refs(&signer)?;
+
+            term::success!(
+                "Signed release {} of {}",
+                term::format::highlight(&name),
+                term::format::secondary(target)
+            );
+            if let Some(release) = release::get(&repo, &name)? {
+                if release.canonical {
+                    release::set_canonical(&repo)?;
+                    term::success!("Release {} is canonical", term::format::highlight(&name));
+                } else {
+                    term::info!(
+                        "The release needs more delegate signatures to become canonical ({}/{})",
+                        release.signatures.len(),
+                        release.threshold
+                    );
+                }
+            }
+        }
+        Operation::List => {
+            let mut table = term::Table::default();
+
+            for r in release::list(&repo)? {
+                table.push([
+                    term::format::bold(r.name.to_string()),
+                    term::format::secondary(term::format::oid(r.target)),
+                    term::format::dim(format!("{} signature(s)", r.signatures.len())),
+                    if r.canonical {
+                        term::format::positive("canonical".to_owned())
+                    } else {
+                        term::format::yellow("pending".to_owned())
+                    },
+                ]);
+            }
+            table.print();
+        }
+        Operation::Verify { name } => {
+            let delegates = repo.delegates()?;
+
+            for delegate in delegates.iter() {
+                match release::signed(&repo, delegate.as_key(), &name) {
+                    Ok(Some(signed)) => term::success!(
+                        "{} signed {}",
+                        term::format::tertiary(Did::from(*delegate.as_key())),
+                        term::format::secondary(signed.target)
+                    ),
+                    Ok(None) => term::info!(
+                        "{} did not sign this release",
+                        term::format::tertiary(Did::from(*delegate.as_key()))
+                    ),
+                    Err(e) => term::error(format!("{}: {e}", Did::from(*delegate.as_key()))),
+                }
+            }
+
+            let release = release::get(&repo, &name)?
+                .ok_or_else(|| anyhow!("release '{name}' was not found"))?;
+            if !release.canonical {
+                anyhow::bail!(
+                    "release '{name}' of {} is not canonical: not enough delegate signatures",
+                    release.target
+                );
+            }
+            term::success!(
+                "Release {} of {} is canonical",
+                term::format::highlight(&name),
+                term::format::secondary(release.target)
+            );
+        }
+    }
+    Ok(())
+}
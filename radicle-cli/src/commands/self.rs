@@ -1,7 +1,14 @@
 use std::ffi::OsString;
 
+use anyhow::Context as _;
+
 use radicle::crypto::ssh;
-use radicle::Profile;
+use radicle::crypto::ssh::keystore::MemorySigner;
+use radicle::git::Oid;
+use radicle::identity::{Person, Rotation};
+use radicle::profile::env::RAD_PASSPHRASE;
+use radicle::storage::{ReadRepository, ReadStorage, WriteRepository};
+use radicle::{profile, rad, Profile};
 
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
@@ -15,6 +22,26 @@ pub const HELP: Help = Help {
 Usage
 
     rad self [<option>...]
+    rad self --profile <name>
+    rad self rotate
+    rad self publish --name <name> [--email <email>] [--avatar <oid>] [--link <profile>...]
+
+    The `rotate` command replaces your key with a newly generated one. Your old
+    key signs a statement binding it to the new key, which is added to the identity
+    of every repository you are a delegate of. Where the identity requires more than
+    one signature, an identity proposal accepted by your old key is opened instead,
+    for the other delegates to accept. Your references are copied to the
+    new key's namespace, and your old key is kept under `keys/rotated/`. If a
+    rotation is interrupted, running it again resumes it with the same new key.
+
+    The `publish` command signs a person document with your display name and other
+    details, and publishes it in every repository you have a namespace in, so that
+    others see your name next to your DID. Keys of your other profiles can be linked
+    to the document with `--link`: they co-sign it, showing they belong to you.
+
+    The `--profile` option switches to another profile, created with
+    `rad auth --profile <name>`. The `RAD_PROFILE` environment variable takes
+    precedence over the profile switched to.
 
 Options
 
@@ -22,7 +49,15 @@ Options
     --did                Show your DID
     --ssh-key            Show your public key in OpenSSH format
     --ssh-fingerprint    Show your public key fingerprint in OpenSSH format
+    --profile <name>     Switch to the given profile
     --help               Show help
+
+Publish options
+
+    --name <name>        Your display name
+    --email <email>      Your email address
+    --avatar <oid>       Git blob hash of your avatar image
+    --link <profile>     Link the key of another of your profiles
 "#,
 };
 
@@ -35,9 +70,23 @@ enum Show {
     All,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum OperationName {
+    Rotate,
+    Publish,
+}
+
+#[derive(Debug)]
+enum Operation {
+    Show(Show),
+    Rotate,
+    Publish(Person, Vec<String>),
+    Switch(String),
+}
+
 #[derive(Debug)]
 pub struct Options {
-    show: Show,
+    op: Operation,
 }
 
 impl Args for Options {
@@ -46,6 +95,12 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut show: Option<Show> = None;
+        let mut op: Option<OperationName> = None;
+        let mut name: Option<String> = None;
+        let mut email: Option<String> = None;
+        let mut avatar: Option<Oid> = None;
+        let mut links: Vec<String> = Vec::new();
+        let mut switch: Option<String> = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -61,26 +116,67 @@ impl Args for Options {
                 Long("ssh-fingerprint") if show.is_none() => {
                     show = Some(Show::SshFingerprint);
                 }
+                Long("profile") if show.is_none() && op.is_none() => {
+                    switch = Some(term::args::string(&parser.value()?));
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
+                Long("name") if op == Some(OperationName::Publish) => {
+                    name = Some(term::args::string(&parser.value()?));
+                }
+                Long("email") if op == Some(OperationName::Publish) => {
+                    email = Some(term::args::string(&parser.value()?));
+                }
+                Long("avatar") if op == Some(OperationName::Publish) => {
+                    avatar = Some(term::args::parse_value("avatar", parser.value()?)?);
+                }
+                Long("link") if op == Some(OperationName::Publish) => {
+                    links.push(term::args::string(&parser.value()?));
+                }
+                Value(val) if show.is_none() && op.is_none() && switch.is_none() => {
+                    match val.to_string_lossy().as_ref() {
+                        "rotate" => op = Some(OperationName::Rotate),
+                        "publish" => op = Some(OperationName::Publish),
+                        unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                    }
+                }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
 
-        Ok((
-            Options {
-                show: show.unwrap_or(Show::All),
+        let op = match op {
+            Some(OperationName::Rotate) => Operation::Rotate,
+            Some(OperationName::Publish) => {
+                let name =
+                    name.ok_or_else(|| anyhow::anyhow!("a name must be specified with `--name`"))?;
+                let mut person = Person::new(name)?;
+                person.email = email;
+                person.avatar = avatar;
+                person.validate()?;
+
+                Operation::Publish(person, links)
+            }
+            None => match switch {
+                Some(name) => Operation::Switch(name),
+                None => Operation::Show(show.unwrap_or(Show::All)),
             },
-            vec![],
-        ))
+        };
+
+        Ok((Options { op }, vec![]))
     }
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let show = match options.op {
+        Operation::Show(show) => show,
+        Operation::Switch(name) => return switch(&name),
+        Operation::Rotate => return rotate(&ctx.profile()?),
+        Operation::Publish(person, links) => return publish(&ctx.profile()?, person, &links),
+    };
     let profile = ctx.profile()?;
 
-    match options.show {
+    match show {
         Show::NodeId => {
             term::print(profile.id());
         }
@@ -99,9 +195,43 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn switch(name: &str) -> anyhow::Result<()> {
+    if let Err(e) = profile::set_active(name) {
+        if let profile::Error::NotFound(_) = e {
+            let profiles = profile::profiles()?.join(", ");
+            anyhow::bail!(
+                "profile '{name}' not found (available: {profiles}); \
+                to create it, run `rad auth --profile {name}`"
+            );
+        }
+        return Err(e.into());
+    }
+    let profile = Profile::load_from(profile::Home::new(profile::path(name)?)?)?;
+
+    term::success!(
+        "Switched to profile {} ({})",
+        term::format::highlight(name),
+        term::format::tertiary(profile.did())
+    );
+    if let Ok(active) = profile::active() {
+        if active != name {
+            term::warning(&format!(
+                "The active profile is overridden by `{}`, set to '{active}'",
+                profile::env::RAD_PROFILE
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn all(profile: &Profile) -> anyhow::Result<()> {
     let mut table = term::Table::default();
 
+    table.push([
+        term::format::style("Profile").to_string(),
+        term::format::tertiary(profile::active()?).to_string(),
+    ]);
+
     let did = profile.did();
     table.push([
         term::format::style("DID").to_string(),
@@ -148,3 +278,98 @@ fn all(profile: &Profile) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn rotate(profile: &Profile) -> anyhow::Result<()> {
+    let old = *profile.id();
+    let signer = profile.signer()?;
+    let passphrase =
+        term::passphrase_confirm("Enter a passphrase for your new key:", RAD_PASSPHRASE)?;
+
+    // The new key only replaces the current one once every repository was migrated, so
+    // that an interrupted rotation can be resumed by running it again.
+    let spinner = term::spinner("Creating your new Ed25519 keypair...");
+    let new = profile
+        .keystore
+        .prepare_rotation("radicle", passphrase.clone())?;
+    let new_signer = MemorySigner::load(&profile.keystore.pending(), passphrase.clone()).context(
+        "failed to load your new key; when resuming a rotation, use the same passphrase",
+    )?;
+    let rotation = Rotation::new(&signer, new);
+    spinner.finish();
+
+    for rid in profile.storage.repositories()? {
+        let repo = profile.storage.repository(rid)?;
+        if repo.remote(&old).is_err() {
+            continue;
+        }
+        let spinner = term::spinner(format!("Migrating {rid}..."));
+        let rotated = rad::rotate(&repo, rotation, &signer, &new_signer)
+            .with_context(|| format!("failed to rotate your key in {rid}"))?;
+        spinner.finish();
+
+        match rotated {
+            rad::Rotated::Migrated => {}
+            rad::Rotated::Updated(_) => {
+                if let Err(e) = repo.set_identity_head() {
+                    term::warning(&format!("Could not set the identity head of {rid}: {e}"));
+                }
+            }
+            rad::Rotated::Proposed(id) => {
+                term::info!(
+                    "Other delegates of {rid} have to accept identity proposal {} \
+                     for your new key to replace the current one",
+                    term::format::highlight(id)
+                );
+            }
+        }
+    }
+    profile.keystore.rotate()?;
+
+    if super::rad_auth::register(&Profile::load()?, passphrase).is_err() {
+        term::warning("Could not add your new key to ssh-agent; run `rad auth` to add it");
+    }
+    term::success!(
+        "Your key was rotated. Your new Radicle ID is {}",
+        term::format::highlight(radicle::identity::Did::from(new))
+    );
+
+    Ok(())
+}
+
+fn publish(profile: &Profile, mut person: Person, links: &[String]) -> anyhow::Result<()> {
+    let signer = profile.signer()?;
+    let mut linked = Vec::new();
+    for name in links {
+        let home = profile::Home::new(profile::path(name)?)?;
+        let other =
+            Profile::load_from(home).with_context(|| format!("failed to load profile '{name}'"))?;
+
+        person.keys.push(other.did());
+        linked.push(other);
+    }
+    person.validate()?;
+
+    // Linked keys sign the final document, with every key in it.
+    let mut cosignatures = Vec::new();
+    for other in &linked {
+        let (_, sig) = person.sign(&other.signer()?)?;
+        cosignatures.push((*other.id(), sig));
+    }
+    let mut published = 0;
+
+    for rid in profile.storage.repositories()? {
+        let repo = profile.storage.repository(rid)?;
+        if repo.remote(profile.id()).is_err() {
+            continue;
+        }
+        person.publish(&signer, &cosignatures, repo.raw())?;
+        repo.sign_refs(&signer)?;
+        published += 1;
+    }
+    term::success!(
+        "Published person document for {} to {published} repositories",
+        term::format::highlight(&person.name)
+    );
+
+    Ok(())
+}
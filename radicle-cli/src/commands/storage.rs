@@ -0,0 +1,123 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle::identity::Id;
+use radicle::storage::git::verify;
+use radicle::storage::ReadStorage;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "storage",
+    description: "Manage your local storage",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad storage verify [<rid>] [<option>...]
+
+    The `verify` command checks that every remote's signed refs match the refs
+    present in storage, that identity branches are signed by enough delegates,
+    that collaborative object changes are validly signed, and that no objects
+    are missing. Without an `rid`, all repositories in storage are verified.
+
+Options
+
+    --verbose, -v       Also show dangling objects
+    --help              Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+enum OperationName {
+    Verify,
+}
+
+#[derive(Debug)]
+enum Operation {
+    Verify { rid: Option<Id> },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    op: Operation,
+    verbose: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut rid: Option<Id> = None;
+        let mut verbose = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("verbose") | Short('v') => {
+                    verbose = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "verify" => op = Some(OperationName::Verify),
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val) if op == Some(OperationName::Verify) && rid.is_none() => {
+                    rid = Some(term::args::rid(&val)?);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op {
+            Some(OperationName::Verify) => Operation::Verify { rid },
+            None => anyhow::bail!("an operation must be provided; see `rad storage --help`"),
+        };
+
+        Ok((Options { op, verbose }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = &profile.storage;
+
+    match options.op {
+        Operation::Verify { rid } => {
+            let report = if let Some(rid) = rid {
+                let repo = storage.repository(rid)?;
+                verify::VerifyReport {
+                    repositories: vec![verify::repository(&repo)?],
+                }
+            } else {
+                verify::run(storage)?
+            };
+
+            for repo in &report.repositories {
+                if repo.is_ok() {
+                    term::success!("{} verified", term::format::highlight(repo.rid));
+                } else {
+                    for problem in &repo.problems {
+                        term::error(format!("{}: {problem}", repo.rid));
+                    }
+                }
+                if options.verbose {
+                    for oid in &repo.dangling {
+                        term::info!("{}: dangling object {}", repo.rid, term::format::dim(oid));
+                    }
+                }
+            }
+
+            let failed = report.failed().count();
+            if failed > 0 {
+                anyhow::bail!("verification failed for {failed} repository(s)");
+            }
+        }
+    }
+    Ok(())
+}
@@ -2,8 +2,9 @@ use std::ffi::OsString;
 
 use anyhow::anyhow;
 
+use radicle::node;
 use radicle::node::tracking::{Alias, Scope};
-use radicle::node::{Handle, NodeId};
+use radicle::node::{tracking, Handle, NodeId};
 use radicle::{prelude::*, Node};
 
 use crate::terminal as term;
@@ -17,7 +18,7 @@ pub const HELP: Help = Help {
 Usage
 
     rad track <did> [--[no-]fetch] [--alias <name>]
-    rad track <rid> [--[no-]fetch] [--scope <scope>]
+    rad track <rid> [--[no-]fetch] [--scope <scope>] [--[no-]large-objects]
 
     The `track` command takes either a DID or an RID. Based on the argument, it will
     either update the tracking policy of a node (DID), or a repository (RID).
@@ -27,10 +28,14 @@ Usage
     On the other hand, with `trusted`, only the repository delegates will be tracked,
     plus any remote that is explicitly tracked via `rad track <nid>`.
 
+    Large objects of a repository, eg. binary assets stored with `git-rad-lfs`, aren't
+    stored by default. Use `--large-objects` to store and seed all of them.
+
 Options
 
     --alias <name>         Associate an alias to a tracked node
     --fetch                Fetch refs after tracking
+    --large-objects        Store the large objects of a repository
     --scope <scope>        Node (remote) tracking scope for a repository
     --verbose, -v          Verbose output
     --help                 Print help
@@ -39,8 +44,15 @@ Options
 
 #[derive(Debug)]
 pub enum Operation {
-    TrackNode { nid: NodeId, alias: Option<Alias> },
-    TrackRepo { rid: Id, scope: Scope },
+    TrackNode {
+        nid: NodeId,
+        alias: Option<Alias>,
+    },
+    TrackRepo {
+        rid: Id,
+        scope: Scope,
+        large_objects: Option<bool>,
+    },
 }
 
 #[derive(Debug)]
@@ -66,6 +78,7 @@ impl Args for Options {
                         op = Some(Operation::TrackRepo {
                             rid,
                             scope: Scope::default(),
+                            large_objects: None,
                         });
                     } else if let Ok(did) = term::args::did(val) {
                         op = Some(Operation::TrackNode {
@@ -94,6 +107,12 @@ impl Args for Options {
                         .ok_or_else(|| anyhow!("scope specified is not UTF-8"))?
                         .parse()?;
                 }
+                (Long("large-objects"), Some(Operation::TrackRepo { large_objects, .. })) => {
+                    *large_objects = Some(true);
+                }
+                (Long("no-large-objects"), Some(Operation::TrackRepo { large_objects, .. })) => {
+                    *large_objects = Some(false);
+                }
                 (Long("no-fetch"), _) => fetch = false,
                 (Long("verbose") | Short('v'), _) => verbose = true,
                 (Long("help"), _) => {
@@ -122,7 +141,32 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     match options.op {
         Operation::TrackNode { nid, alias } => track_node(nid, alias, &mut node),
-        Operation::TrackRepo { rid, scope } => track_repo(rid, scope, &mut node),
+        Operation::TrackRepo {
+            rid,
+            scope,
+            large_objects,
+        } => {
+            track_repo(rid, scope, &mut node)?;
+
+            if let Some(large_objects) = large_objects {
+                let mut store = tracking::store::Config::open(
+                    profile.home.node().join(node::TRACKING_DB_FILE),
+                )?;
+                let policy = if large_objects {
+                    tracking::Policy::Track
+                } else {
+                    tracking::Policy::Block
+                };
+                store.set_large_objects_policy(&rid, policy)?;
+
+                term::success!(
+                    "Large objects of {} will {}be stored",
+                    term::format::tertiary(rid),
+                    if large_objects { "" } else { "not " }
+                );
+            }
+            Ok(())
+        }
     }?;
 
     if options.fetch {
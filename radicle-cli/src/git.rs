@@ -44,6 +44,12 @@ impl Rev {
         let object = repo.revparse_single(self.as_str())?;
         Ok(ObjectId::from(object.id()))
     }
+
+    /// Resolve the revision to a commit.
+    pub fn resolve_commit(&self, repo: &git2::Repository) -> Result<git::Oid, git2::Error> {
+        let commit = repo.revparse_single(self.as_str())?.peel_to_commit()?;
+        Ok(commit.id().into())
+    }
 }
 
 impl Display for Rev {
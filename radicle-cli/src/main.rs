@@ -1,7 +1,9 @@
 use std::ffi::OsString;
-use std::{io::ErrorKind, iter, process};
+use std::{env, io::ErrorKind, iter, process};
 
 use anyhow::anyhow;
+use radicle::profile;
+use radicle::profile::config::Color;
 
 use radicle_cli::commands::*;
 use radicle_cli::terminal as term;
@@ -19,6 +21,9 @@ enum Command {
 }
 
 fn main() {
+    if let Err(err) = configure() {
+        term::warning(&format!("rad: failed to load configuration: {err}"));
+    }
     match parse_args().map_err(Some).and_then(run) {
         Ok(_) => process::exit(0),
         Err(err) => {
@@ -30,6 +35,28 @@ fn main() {
     }
 }
 
+/// Apply the CLI settings of the profile configuration. Environment variables take precedence.
+fn configure() -> anyhow::Result<()> {
+    let config = profile::Config::load(&profile::home()?.config())?.cli;
+
+    if let Some(editor) = config.editor {
+        if env::var_os("VISUAL").is_none() && env::var_os("EDITOR").is_none() {
+            env::set_var("EDITOR", editor);
+        }
+    }
+    if let Some(pager) = config.pager {
+        if env::var_os("PAGER").is_none() {
+            env::set_var("PAGER", pager);
+        }
+    }
+    match config.color {
+        Color::Auto => {}
+        Color::Always => term::Paint::force(true),
+        Color::Never => term::Paint::force(false),
+    }
+    Ok(())
+}
+
 fn parse_args() -> anyhow::Result<Command> {
     use lexopt::prelude::*;
 
@@ -142,6 +169,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "config" => {
+            term::run_command_args::<rad_config::Options, _>(
+                rad_config::HELP,
+                "Config",
+                rad_config::run,
+                args.to_vec(),
+            );
+        }
         "delegate" => {
             term::run_command_args::<rad_delegate::Options, _>(
                 rad_delegate::HELP,
@@ -166,6 +201,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "fork" => {
+            term::run_command_args::<rad_fork::Options, _>(
+                rad_fork::HELP,
+                "Fork",
+                rad_fork::run,
+                args.to_vec(),
+            );
+        }
         "help" => {
             term::run_command_args::<rad_help::Options, _>(
                 rad_help::HELP,
@@ -254,6 +297,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "release" => {
+            term::run_command_args::<rad_release::Options, _>(
+                rad_release::HELP,
+                "Release",
+                rad_release::run,
+                args.to_vec(),
+            );
+        }
         "review" => {
             term::run_command_args::<rad_review::Options, _>(
                 rad_review::HELP,
@@ -278,6 +329,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "storage" => {
+            term::run_command_args::<rad_storage::Options, _>(
+                rad_storage::HELP,
+                "Storage",
+                rad_storage::run,
+                args.to_vec(),
+            );
+        }
         "tag" => {
             term::run_command_args::<rad_tag::Options, _>(
                 rad_tag::HELP,
@@ -4,8 +4,10 @@ pub use radicle_term::format::*;
 pub use radicle_term::{style, Paint};
 
 use radicle::cob::{ObjectId, Timestamp};
+use radicle::identity::{Did, Person};
 use radicle::node::NodeId;
 use radicle::profile::Profile;
+use radicle::storage::ReadRepository;
 
 use crate::terminal as term;
 
@@ -18,6 +20,15 @@ pub fn node(node: &NodeId) -> String {
     format!("{start}…{end}")
 }
 
+/// Format a DID along with the display name from its person document, if published
+/// in the given repository, eg. `did:key:z6Mk… (Alice)`.
+pub fn did<R: ReadRepository>(did: &Did, repo: &R) -> String {
+    match Person::load(did, repo) {
+        Ok(at) => format!("{did} ({})", at.person.name),
+        Err(_) => did.to_string(),
+    }
+}
+
 /// Format a git Oid.
 pub fn oid(oid: impl Into<radicle::git::Oid>) -> String {
     format!("{:.7}", oid.into())
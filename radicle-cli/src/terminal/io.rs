@@ -41,8 +41,9 @@ pub mod proposal {
 
     use radicle::{
         cob::identity::{self, Proposal},
+        crypto::Verified,
         git::Oid,
-        identity::Identity,
+        identity::{Doc, Identity},
     };
 
     use super::*;
@@ -86,11 +87,16 @@ pub mod proposal {
     }
 
     pub fn diff(proposal: &identity::Revision, previous: &Identity<Oid>) -> anyhow::Result<String> {
+        doc_diff(&previous.doc, &proposal.proposed)
+    }
+
+    /// Diff two identity documents.
+    pub fn doc_diff(old: &Doc<Verified>, new: &Doc<Verified>) -> anyhow::Result<String> {
         use similar::{ChangeTag, TextDiff};
 
-        let new = serde_json::to_string_pretty(&proposal.proposed)?;
-        let previous = serde_json::to_string_pretty(&previous.doc)?;
-        let diff = TextDiff::from_lines(&previous, &new);
+        let new = serde_json::to_string_pretty(new)?;
+        let old = serde_json::to_string_pretty(old)?;
+        let diff = TextDiff::from_lines(&old, &new);
         let mut buf = String::new();
         for change in diff.iter_all_changes() {
             match change.tag() {
@@ -11,7 +11,7 @@ use git_ext::Oid;
 use git_trailers::OwnedTrailer;
 use nonempty::NonEmpty;
 
-use crate::history::entry::Timestamp;
+use crate::history::entry::{HybridClock, Timestamp};
 use crate::signatures;
 use crate::{
     change::{self, store, Change},
@@ -21,6 +21,7 @@ use crate::{
 };
 
 const MANIFEST_BLOB_NAME: &str = "manifest";
+const CLOCK_BLOB_NAME: &str = "clock";
 
 pub mod error {
     use std::str::Utf8Error;
@@ -78,6 +79,8 @@ pub mod error {
         Utf8(#[from] FromUtf8Error),
         #[error(transparent)]
         Trailer(#[from] TrailerError),
+        #[error("the 'clock' found at '{0}' was invalid")]
+        InvalidClock(Oid),
     }
 }
 
@@ -104,13 +107,16 @@ impl change::Storage for git2::Repository {
             tips,
             message,
             contents,
+            mut clock,
         } = spec;
         let manifest = store::Manifest {
             typename,
             history_type,
         };
+        let (author, timestamp) = author(self)?;
+        let clock = clock.tick(timestamp.into());
 
-        let revision = write_manifest(self, &manifest, &contents)?;
+        let revision = write_manifest(self, &manifest, &contents, &clock)?;
         let tree = self.find_tree(revision)?;
         let signature = {
             let sig = signer.sign(revision.as_bytes());
@@ -118,7 +124,15 @@ impl change::Storage for git2::Repository {
             ExtendedSignature::new(*key, sig)
         };
 
-        let (id, timestamp) = write_commit(self, resource, tips, message, signature.clone(), tree)?;
+        let id = write_commit(
+            self,
+            resource,
+            tips,
+            message,
+            signature.clone(),
+            tree,
+            author,
+        )?;
         Ok(Change {
             id,
             revision: revision.into(),
@@ -127,6 +141,7 @@ impl change::Storage for git2::Repository {
             manifest,
             contents,
             timestamp,
+            clock: Some(clock),
         })
     }
 
@@ -147,6 +162,7 @@ impl change::Storage for git2::Repository {
         let tree = self.find_tree(commit.tree())?;
         let manifest = load_manifest(self, &tree)?;
         let contents = load_contents(self, &tree)?;
+        let clock = load_clock(self, &tree)?;
 
         Ok(Change {
             id,
@@ -156,6 +172,7 @@ impl change::Storage for git2::Repository {
             manifest,
             contents,
             timestamp,
+            clock,
         })
     }
 }
@@ -194,6 +211,22 @@ fn load_manifest(
     })
 }
 
+fn load_clock(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+) -> Result<Option<HybridClock>, error::Load> {
+    let Some(entry) = tree.get_name(CLOCK_BLOB_NAME) else {
+        return Ok(None);
+    };
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    let clock = std::str::from_utf8(blob.content())
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .ok_or_else(|| error::Load::InvalidClock(tree.id().into()))?;
+
+    Ok(Some(clock))
+}
+
 fn load_contents(
     repo: &git2::Repository,
     tree: &git2::Tree,
@@ -220,6 +253,27 @@ fn load_contents(
     NonEmpty::collect(ops.into_values()).ok_or_else(|| error::Load::NoChange(tree.id().into()))
 }
 
+/// Get the author of a new change, and its timestamp.
+fn author(repo: &git2::Repository) -> Result<(commit::Author, Timestamp), error::Create> {
+    let author = repo.signature()?;
+    let timestamp = author.when().seconds();
+    let author = commit::Author::try_from(&author)?;
+
+    #[cfg(debug_assertions)]
+    let (author, timestamp) = if let Ok(s) = std::env::var(crate::git::RAD_COMMIT_TIME) {
+        let timestamp = s.trim().parse::<i64>().unwrap();
+        let author = commit::Author {
+            time: git_commit::author::Time::new(timestamp, 0),
+            ..author
+        };
+        (author, timestamp)
+    } else {
+        (author, timestamp)
+    };
+
+    Ok((author, timestamp as Timestamp))
+}
+
 fn write_commit<O>(
     repo: &git2::Repository,
     resource: O,
@@ -227,7 +281,8 @@ fn write_commit<O>(
     message: String,
     signature: ExtendedSignature,
     tree: git2::Tree,
-) -> Result<(Oid, Timestamp), error::Create>
+    author: commit::Author,
+) -> Result<Oid, error::Create>
 where
     O: AsRef<git2::Oid>,
 {
@@ -239,8 +294,6 @@ where
         .collect::<Vec<_>>();
 
     let trailers: Vec<OwnedTrailer> = vec![trailers::ResourceCommitTrailer::from(resource).into()];
-    let author = repo.signature()?;
-    let timestamp = author.when().seconds();
 
     let mut headers = commit::Headers::new();
     headers.push(
@@ -250,19 +303,6 @@ where
             .map_err(signatures::error::Signatures::from)?
             .as_str(),
     );
-    let author = commit::Author::try_from(&author)?;
-
-    #[cfg(debug_assertions)]
-    let (author, timestamp) = if let Ok(s) = std::env::var(crate::git::RAD_COMMIT_TIME) {
-        let timestamp = s.trim().parse::<i64>().unwrap();
-        let author = commit::Author {
-            time: git_commit::author::Time::new(timestamp, 0),
-            ..author
-        };
-        (author, timestamp)
-    } else {
-        (author, timestamp)
-    };
 
     let oid = Commit::new(
         tree.id(),
@@ -275,13 +315,14 @@ where
     )
     .write(repo)?;
 
-    Ok((Oid::from(oid), timestamp as u64))
+    Ok(Oid::from(oid))
 }
 
 fn write_manifest(
     repo: &git2::Repository,
     manifest: &store::Manifest,
     contents: &NonEmpty<Vec<u8>>,
+    clock: &HybridClock,
 ) -> Result<git2::Oid, git2::Error> {
     let mut tb = repo.treebuilder(None)?;
     // SAFETY: we're serializing to an in memory buffer so the only source of
@@ -294,6 +335,9 @@ fn write_manifest(
         git2::FileMode::Blob.into(),
     )?;
 
+    let clock_oid = repo.blob(clock.to_string().as_bytes())?;
+    tb.insert(CLOCK_BLOB_NAME, clock_oid, git2::FileMode::Blob.into())?;
+
     for (ix, op) in contents.iter().enumerate() {
         let oid = repo.blob(op.as_ref())?;
         tb.insert(&ix.to_string(), oid, git2::FileMode::Blob.into())?;
@@ -9,7 +9,7 @@ use nonempty::NonEmpty;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    history::{Contents, Timestamp},
+    history::{Contents, HybridClock, Timestamp},
     signatures, TypeName,
 };
 
@@ -48,6 +48,9 @@ pub struct Template<Id> {
     pub tips: Vec<Id>,
     pub message: String,
     pub contents: NonEmpty<Vec<u8>>,
+    /// Hybrid clock of the tips. The clock of the new change is derived from it and the
+    /// change timestamp.
+    pub clock: HybridClock,
 }
 
 #[derive(Clone, Debug)]
@@ -69,6 +72,8 @@ pub struct Change<Resource, Id, Signature> {
     pub contents: Contents,
     /// Timestamp of change.
     pub timestamp: Timestamp,
+    /// Hybrid clock of change. Changes created before the clock was recorded don't have one.
+    pub clock: Option<HybridClock>,
 }
 
 impl<Resource, Id, S> fmt::Display for Change<Resource, Id, S>
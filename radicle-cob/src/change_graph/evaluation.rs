@@ -36,17 +36,18 @@ pub fn evaluate(root: Oid, graph: &Dag<Oid, Change>, rng: fastrand::Rng) -> hist
                 ControlFlow::Break(entries)
             }
             Ok(entry) => {
-                // Get parent commits and calculate this node's clock based on theirs.
-                let clock = graph[&c.oid]
-                    .dependencies
-                    .iter()
-                    .map(|e| entries[&EntryId::from(*e)].clock())
-                    .max()
-                    .unwrap_or_default() // When there are no operations, the clock is zero.
-                    + 1;
+                // Get parent commits and calculate this node's clocks based on theirs.
+                // When there are no parents, the clock starts from zero.
+                let entry = EntryWithClock::new(
+                    entry,
+                    graph[&c.oid]
+                        .dependencies
+                        .iter()
+                        .map(|e| &entries[&EntryId::from(*e)]),
+                );
                 log::trace!("change '{}' accepted", c.change.id());
 
-                entries.insert(*entry.id(), EntryWithClock { entry, clock });
+                entries.insert(*entry.id(), entry);
 
                 ControlFlow::Continue(entries)
             }
@@ -72,6 +73,7 @@ fn evaluate_change(
         child_commits.iter().cloned(),
         change.contents().clone(),
         change.timestamp,
+        change.clock,
     ))
 }
 
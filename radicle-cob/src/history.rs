@@ -15,11 +15,12 @@ use radicle_dag::Dag;
 use crate::pruning_fold;
 
 pub mod entry;
-pub use entry::{Clock, Contents, Entry, EntryId, EntryWithClock, Timestamp};
+pub use entry::{Clock, Contents, Entry, EntryId, EntryWithClock, HybridClock, Timestamp};
 
 /// The DAG of changes making up the history of a collaborative object.
 #[derive(Clone, Debug)]
 pub struct History {
+    root: EntryId,
     graph: Dag<EntryId, EntryWithClock>,
 }
 
@@ -44,6 +45,7 @@ impl History {
         resource: Oid,
         contents: Contents,
         timestamp: Timestamp,
+        recorded: Option<HybridClock>,
     ) -> Self
     where
         Id: Into<EntryId>,
@@ -56,6 +58,7 @@ impl History {
             children: vec![],
             contents,
             timestamp,
+            recorded,
         };
         let mut entries = HashMap::new();
         entries.insert(id, EntryWithClock::root(root_entry));
@@ -85,6 +88,37 @@ impl History {
             .unwrap_or_default()
     }
 
+    /// Get the current value of the hybrid clock.
+    /// This is the maximum value of all tips.
+    pub fn hybrid(&self) -> HybridClock {
+        self.graph
+            .tips()
+            .map(|(_, node)| node.hybrid)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Get an entry of the history, along with its clocks.
+    pub fn entry(&self, id: &EntryId) -> Option<&EntryWithClock> {
+        self.graph.get(id).map(|node| &node.value)
+    }
+
+    /// The entries the given entry depends on.
+    pub fn dependencies(&self, id: &EntryId) -> impl Iterator<Item = &EntryId> {
+        self.graph
+            .get(id)
+            .into_iter()
+            .flat_map(|node| node.dependencies.iter())
+    }
+
+    /// The entries depending on the given entry.
+    pub fn dependents(&self, id: &EntryId) -> impl Iterator<Item = &EntryId> {
+        self.graph
+            .get(id)
+            .into_iter()
+            .flat_map(|node| node.dependents.iter())
+    }
+
     /// Get the current history timestamp.
     /// This is the latest timestamp of any tip.
     pub fn timestamp(&self) -> Timestamp {
@@ -114,6 +148,12 @@ impl History {
         pruning_fold::pruning_fold(init, items, f)
     }
 
+    /// Get the root entry of the history, ie. the entry that created the object.
+    pub fn root(&self) -> &EntryWithClock {
+        // The root is added on construction, and entries are never removed.
+        &self.graph[&self.root].value
+    }
+
     pub fn tips(&self) -> BTreeSet<Oid> {
         self.graph
             .tips()
@@ -128,6 +168,7 @@ impl History {
         new_resource: Oid,
         new_contents: Contents,
         new_timestamp: Timestamp,
+        new_clock: Option<HybridClock>,
     ) where
         Id: Into<EntryId>,
     {
@@ -140,14 +181,12 @@ impl History {
             std::iter::empty::<git2::Oid>(),
             new_contents,
             new_timestamp,
+            new_clock,
         );
-        self.graph.node(
-            new_id,
-            EntryWithClock {
-                entry: new_entry,
-                clock: self.clock() + 1,
-            },
-        );
+        let new_entry =
+            EntryWithClock::new(new_entry, self.graph.tips().map(|(_, node)| &node.value));
+
+        self.graph.node(new_id, new_entry);
         for tip in tips {
             self.graph.dependency(new_id, (*tip).into());
         }
@@ -171,5 +210,5 @@ fn create_dag<'a>(root: &'a EntryId, entries: &'a HashMap<EntryId, EntryWithCloc
             to_process.push(child.clone());
         }
     }
-    History { graph }
+    History { root: *root, graph }
 }
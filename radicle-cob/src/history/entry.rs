@@ -7,6 +7,7 @@ use std::str::FromStr;
 
 use git_ext::Oid;
 use nonempty::NonEmpty;
+use radicle_crdt::clock;
 use radicle_crypto::PublicKey;
 use serde::{Deserialize, Serialize};
 
@@ -22,6 +23,9 @@ pub type Clock = u64;
 /// Local time in seconds since epoch.
 pub type Timestamp = u64;
 
+/// Hybrid logical clock used to order changes by time, consistently with causality.
+pub type HybridClock = clock::Hybrid;
+
 /// A unique identifier for a history entry.
 #[derive(Clone, Copy, Debug, PartialEq, Hash, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct EntryId(Oid);
@@ -76,6 +80,8 @@ pub struct Entry {
     pub(super) contents: Contents,
     /// The entry timestamp, as seconds since epoch.
     pub(super) timestamp: Timestamp,
+    /// The hybrid clock recorded in the change, if any.
+    pub(super) recorded: Option<HybridClock>,
 }
 
 impl Entry {
@@ -86,6 +92,7 @@ impl Entry {
         children: ChildIds,
         contents: Contents,
         timestamp: Timestamp,
+        recorded: Option<HybridClock>,
     ) -> Self
     where
         Id1: Into<EntryId>,
@@ -99,6 +106,7 @@ impl Entry {
             children: children.into_iter().map(|id| id.into()).collect(),
             contents,
             timestamp,
+            recorded,
         }
     }
 
@@ -144,18 +152,57 @@ impl pruning_fold::GraphNode for Entry {
     }
 }
 
-/// Wraps an [`Entry`], adding a logical clock to it.
+/// Wraps an [`Entry`], adding a logical clock and a hybrid clock to it.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EntryWithClock {
     pub entry: Entry,
     pub clock: Clock,
+    pub hybrid: HybridClock,
 }
 
 impl EntryWithClock {
     pub fn root(entry: Entry) -> Self {
+        let hybrid = Self::hybrid_after(&entry, HybridClock::initial());
+
         Self {
             entry,
             clock: 1 as Clock, // The root entry has a clock value of `1`.
+            hybrid,
+        }
+    }
+
+    /// Create an entry given the clocks of the entries it depends on.
+    pub fn new<'a>(entry: Entry, parents: impl IntoIterator<Item = &'a EntryWithClock>) -> Self {
+        let (clock, hybrid) = parents.into_iter().map(|p| (p.clock, p.hybrid)).fold(
+            (Clock::default(), HybridClock::initial()),
+            |(c, h), (pc, ph)| (c.max(pc), h.max(ph)),
+        );
+        let hybrid = Self::hybrid_after(&entry, hybrid);
+
+        Self {
+            entry,
+            clock: clock + 1,
+            hybrid,
+        }
+    }
+
+    /// Compute the hybrid clock of an entry, given the greatest hybrid clock of its parents.
+    ///
+    /// The clock recorded in the change is used when it is greater than the parents' clock.
+    /// Otherwise, eg. for changes that were created before clocks were recorded, or with an
+    /// inconsistent clock, the parents' clock is advanced with the change timestamp.
+    ///
+    /// This only depends on the history, so that every peer orders it the same way. Clocks
+    /// too far ahead are refused when changes are received, see [`HybridClock::is_ahead`].
+    fn hybrid_after(entry: &Entry, mut parents: HybridClock) -> HybridClock {
+        let recorded = entry
+            .recorded
+            .unwrap_or_else(|| HybridClock::new(entry.timestamp, 0));
+
+        if recorded > parents {
+            recorded
+        } else {
+            parents.tick(recorded.physical())
         }
     }
 }
@@ -166,6 +213,12 @@ impl EntryWithClock {
         self.clock
     }
 
+    /// Get the hybrid clock value. This is always greater than the hybrid clock
+    /// of the entries this entry depends on.
+    pub fn hybrid(&self) -> HybridClock {
+        self.hybrid
+    }
+
     /// Iterator over the changes, including the clock.
     pub fn changes(&self) -> impl Iterator<Item = &[u8]> {
         self.contents.iter().map(|blob| blob.as_slice())
@@ -5,6 +5,7 @@
 
 use nonempty::NonEmpty;
 
+use crate::history::HybridClock;
 use crate::Store;
 
 use super::*;
@@ -29,6 +30,7 @@ impl Create {
             tips: Vec::new(),
             message: self.message.clone(),
             contents: self.contents.clone(),
+            clock: HybridClock::initial(),
         }
     }
 }
@@ -78,6 +80,7 @@ where
         resource,
         init_change.contents,
         init_change.timestamp,
+        init_change.clock,
     );
 
     Ok(CollaborativeObject {
@@ -87,6 +87,7 @@ where
             contents: changes,
             typename: typename.clone(),
             message,
+            clock: object.history.hybrid(),
         },
     )?;
 
@@ -100,6 +101,7 @@ where
         change.resource,
         change.contents,
         change.timestamp,
+        change.clock,
     );
 
     Ok(Updated {
@@ -6,9 +6,10 @@ use nonempty::nonempty;
 use qcheck::Arbitrary;
 use radicle_crypto::Signer;
 
+use crate::history::HybridClock;
 use crate::{
-    create, get, list, object, test::arbitrary::Invalid, update, Create, ObjectId, TypeName,
-    Update, Updated,
+    change, create, get, list, object, test::arbitrary::Invalid, update, Create, History, ObjectId,
+    TypeName, Update, Updated,
 };
 
 use super::test;
@@ -145,6 +146,100 @@ fn update_cob() {
     assert_eq!(object, expected);
 }
 
+#[test]
+fn recorded_clock() {
+    let storage = test::Storage::new();
+    let signer = gen::<MockSigner>(1);
+    let terry = test::Person::new(&storage, "terry", *signer.public_key()).unwrap();
+    let proj = test::Project::new(&storage, "discworld", *signer.public_key()).unwrap();
+    let proj = test::RemoteProject {
+        project: proj,
+        person: terry,
+    };
+    let typename = "xyz.rad.issue".parse::<TypeName>().unwrap();
+    let cob = create(
+        &storage,
+        &signer,
+        proj.project.content_id,
+        &proj.identifier(),
+        Create {
+            history_type: "test".to_string(),
+            contents: nonempty!(Vec::new()),
+            typename: typename.clone(),
+            message: "creating xyz.rad.issue".to_string(),
+        },
+    )
+    .unwrap();
+    let root = *cob.history().tips().iter().next().unwrap();
+
+    let Updated { object, head } = update(
+        &storage,
+        &signer,
+        proj.project.content_id,
+        &proj.identifier(),
+        Update {
+            changes: nonempty!(b"issue 1".to_vec()),
+            history_type: "test".to_string(),
+            object_id: *cob.id(),
+            typename: typename.clone(),
+            message: "commenting xyz.rad.issue".to_string(),
+        },
+    )
+    .unwrap();
+
+    let root = change::Storage::load(&storage, root)
+        .unwrap()
+        .clock
+        .unwrap();
+    let head = change::Storage::load(&storage, head)
+        .unwrap()
+        .clock
+        .unwrap();
+
+    assert!(head > root);
+    assert_eq!(cob.history().hybrid(), root);
+    assert_eq!(object.history().hybrid(), head);
+
+    let expected = get(&storage, &typename, object.id())
+        .unwrap()
+        .expect("BUG: cob was missing");
+    assert_eq!(expected.history().hybrid(), head);
+}
+
+#[test]
+fn recorded_clock_ahead() {
+    let signer = gen::<MockSigner>(1);
+    let resource =
+        git_ext::Oid::from(git2::Oid::hash_object(git2::ObjectType::Blob, b"identity").unwrap());
+    let root = git2::Oid::hash_object(git2::ObjectType::Blob, b"root").unwrap();
+    let child = git2::Oid::hash_object(git2::ObjectType::Blob, b"child").unwrap();
+    let ahead = HybridClock::new(u64::MAX / 2, 7);
+
+    // Clocks far ahead of the local clock are kept as they are, so that the ordering of a
+    // history doesn't depend on when it is computed.
+    let mut history = History::new_from_root(
+        root,
+        *signer.public_key(),
+        resource,
+        nonempty!(Vec::new()),
+        1,
+        Some(ahead),
+    );
+    assert_eq!(history.hybrid(), ahead);
+
+    // Changes are still ordered after the changes they depend on.
+    history.extend(
+        child,
+        *signer.public_key(),
+        resource,
+        nonempty!(Vec::new()),
+        2,
+        Some(HybridClock::new(2, 0)),
+    );
+    assert_eq!(history.hybrid(), HybridClock::new(u64::MAX / 2, 8));
+    assert_eq!(history.entry(&root.into()).unwrap().hybrid(), ahead);
+}
+
 #[test]
 fn traverse_cobs() {
     let storage = test::Storage::new();
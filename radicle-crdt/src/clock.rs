@@ -103,7 +103,9 @@ impl Bounded for Lamport {
 }
 
 /// Physical clock. Tracks real-time by the second.
-#[derive(Debug, Default, Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Serialize, Deserialize)]
+#[derive(
+    Debug, Default, Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Hash, Serialize, Deserialize,
+)]
 #[serde(transparent)]
 pub struct Physical {
     seconds: u64,
@@ -157,3 +159,165 @@ impl Bounded for Physical {
         }
     }
 }
+
+/// Hybrid logical clock.
+///
+/// Combines a [`Physical`] clock with a logical counter, such that the clock stays close to
+/// real-time, while being monotonic and consistent with causality, even when the physical
+/// clocks of the participants are skewed.
+#[derive(
+    Debug, Default, Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Hash, Serialize, Deserialize,
+)]
+pub struct Hybrid {
+    physical: Physical,
+    logical: u64,
+}
+
+impl Hybrid {
+    /// Maximum number of seconds a clock may be ahead of the local physical clock.
+    pub const MAX_DRIFT: u64 = 60 * 60;
+
+    pub fn new(physical: impl Into<Physical>, logical: u64) -> Self {
+        Self {
+            physical: physical.into(),
+            logical,
+        }
+    }
+
+    /// The initial value of the clock.
+    pub fn initial() -> Self {
+        Self::default()
+    }
+
+    /// Return the physical component of the clock.
+    pub fn physical(&self) -> Physical {
+        self.physical
+    }
+
+    /// Return the logical component of the clock.
+    pub fn logical(&self) -> u64 {
+        self.logical
+    }
+
+    /// Advance the clock given the current physical time, and return the new value.
+    /// Must be called before sending a message.
+    pub fn tick(&mut self, now: Physical) -> Self {
+        if now > self.physical {
+            self.physical = now;
+            self.logical = 0;
+        } else {
+            self.logical = self.logical.saturating_add(1);
+        }
+        *self
+    }
+
+    /// Check whether the physical component of the clock is more than [`Hybrid::MAX_DRIFT`]
+    /// seconds ahead of the given physical time. Such clocks are refused when received, so
+    /// that a single clock far in the future can't take over the ordering of every event
+    /// that follows.
+    pub fn is_ahead(&self, now: Physical) -> bool {
+        self.physical > now + Self::MAX_DRIFT
+    }
+
+    /// Merge the clock with another clock given the current physical time, and return
+    /// the new value. Must be called whenever a message is received.
+    pub fn merge(&mut self, other: Self, now: Physical) -> Self {
+        if other > *self {
+            *self = other;
+        }
+        self.tick(now)
+    }
+}
+
+impl fmt::Display for Hybrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.physical.as_secs(), self.logical)
+    }
+}
+
+/// Error decoding a hybrid clock.
+#[derive(Error, Debug)]
+pub enum HybridError {
+    #[error("invalid hybrid clock value")]
+    Invalid,
+}
+
+impl FromStr for Hybrid {
+    type Err = HybridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (physical, logical) = s.split_once('.').ok_or(HybridError::Invalid)?;
+        let physical = physical.parse::<u64>().map_err(|_| HybridError::Invalid)?;
+        let logical = logical.parse::<u64>().map_err(|_| HybridError::Invalid)?;
+
+        Ok(Self::new(physical, logical))
+    }
+}
+
+impl Bounded for Hybrid {
+    fn min_value() -> Self {
+        Self::new(Physical::min_value(), u64::min_value())
+    }
+
+    fn max_value() -> Self {
+        Self::new(Physical::max_value(), u64::max_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hybrid_tick() {
+        let mut clock = Hybrid::initial();
+
+        assert_eq!(clock.tick(Physical::new(10)), Hybrid::new(10, 0));
+        assert_eq!(clock.tick(Physical::new(10)), Hybrid::new(10, 1));
+        assert_eq!(clock.tick(Physical::new(12)), Hybrid::new(12, 0));
+        // Physical clock going backwards.
+        assert_eq!(clock.tick(Physical::new(8)), Hybrid::new(12, 1));
+    }
+
+    #[test]
+    fn test_hybrid_merge() {
+        let mut clock = Hybrid::new(10, 3);
+
+        // Remote clock is ahead of our physical clock.
+        assert_eq!(
+            clock.merge(Hybrid::new(20, 5), Physical::new(11)),
+            Hybrid::new(20, 6)
+        );
+        // Remote clock is behind.
+        assert_eq!(
+            clock.merge(Hybrid::new(15, 9), Physical::new(11)),
+            Hybrid::new(20, 7)
+        );
+        // Our physical clock is ahead of everything.
+        assert_eq!(
+            clock.merge(Hybrid::new(15, 9), Physical::new(30)),
+            Hybrid::new(30, 0)
+        );
+    }
+
+    #[test]
+    fn test_hybrid_is_ahead() {
+        let now = Physical::new(1000);
+
+        assert!(!Hybrid::new(1000, 3).is_ahead(now));
+        assert!(!Hybrid::new(1000 + Hybrid::MAX_DRIFT, 3).is_ahead(now));
+        assert!(Hybrid::new(1001 + Hybrid::MAX_DRIFT, 0).is_ahead(now));
+        assert!(Hybrid::new(u64::MAX / 2, 3).is_ahead(now));
+    }
+
+    #[test]
+    fn test_hybrid_encoding() {
+        let clock = Hybrid::new(1671125284, 42);
+        let encoded = clock.to_string();
+
+        assert_eq!(encoded, "1671125284.42");
+        assert_eq!(encoded.parse::<Hybrid>().unwrap(), clock);
+        assert!("1671125284".parse::<Hybrid>().is_err());
+        assert!("1671125284.x".parse::<Hybrid>().is_err());
+    }
+}
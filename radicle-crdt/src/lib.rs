@@ -8,7 +8,9 @@ pub mod gset;
 pub mod lwwmap;
 pub mod lwwreg;
 pub mod lwwset;
+pub mod mvreg;
 pub mod ord;
+pub mod orset;
 pub mod redactable;
 
 #[cfg(any(test, feature = "test"))]
@@ -16,13 +18,15 @@ pub mod test;
 
 ////////////////////////////////////////////////////////////////////////////////
 
-pub use clock::Lamport;
+pub use clock::{Hybrid, Lamport};
 pub use gmap::GMap;
 pub use gset::GSet;
 pub use lwwmap::LWWMap;
 pub use lwwreg::LWWReg;
 pub use lwwset::LWWSet;
+pub use mvreg::MVReg;
 pub use ord::{Max, Min};
+pub use orset::ORSet;
 pub use redactable::Redactable;
 
 ////////////////////////////////////////////////////////////////////////////////
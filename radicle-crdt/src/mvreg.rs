@@ -0,0 +1,139 @@
+use crate::clock;
+use crate::{GSet, Semilattice};
+
+/// Multi-Value Register.
+///
+/// Unlike [`crate::LWWReg`], concurrent assignments are not resolved: all of them are kept,
+/// and it is up to the user to pick or merge the values, eg. by presenting them in a UI.
+///
+/// Every assignment is identified by a unique tag, and overwrites the tags observed by
+/// the writer. Assignments that weren't observed are thus concurrent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MVReg<T, C = clock::Lamport> {
+    assigned: GSet<(C, T)>,
+    overwritten: GSet<C>,
+}
+
+impl<T: Ord, C: Ord> MVReg<T, C> {
+    pub fn new(value: T, tag: C) -> Self {
+        Self {
+            assigned: GSet::singleton((tag, value)),
+            overwritten: GSet::default(),
+        }
+    }
+
+    /// Assign a value, identified by a unique tag, overwriting the observed tags.
+    pub fn set(&mut self, value: T, tag: C, observed: impl IntoIterator<Item = C>) {
+        self.assigned.insert((tag, value));
+        self.overwritten.extend(observed);
+    }
+
+    /// Return the current values along with their tags.
+    pub fn iter(&self) -> impl Iterator<Item = (&C, &T)> {
+        self.assigned
+            .iter()
+            .filter(|(tag, _)| !self.overwritten.contains_key(tag))
+            .map(|(tag, value)| (tag, value))
+    }
+
+    /// Return the current values. There is more than one value if there were
+    /// concurrent assignments.
+    pub fn get(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Return the tags of the current values. These are the tags to overwrite
+    /// when assigning a new value.
+    pub fn tags(&self) -> impl Iterator<Item = &C> {
+        self.iter().map(|(t, _)| t)
+    }
+
+    /// Whether there are concurrent values that need to be resolved.
+    pub fn is_conflicted(&self) -> bool {
+        self.iter().nth(1).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+impl<T, C> Default for MVReg<T, C> {
+    fn default() -> Self {
+        Self {
+            assigned: GSet::default(),
+            overwritten: GSet::default(),
+        }
+    }
+}
+
+impl<T, C> Semilattice for MVReg<T, C>
+where
+    T: Ord,
+    C: Ord,
+{
+    fn merge(&mut self, other: Self) {
+        self.assigned.merge(other.assigned);
+        self.overwritten.merge(other.overwritten);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qcheck_macros::quickcheck;
+
+    fn reg(writes: Vec<(u8, u16)>) -> MVReg<u8, u16> {
+        let mut reg = MVReg::default();
+        for (v, t) in writes {
+            let observed = reg.tags().cloned().collect::<Vec<_>>();
+            reg.set(v, t, observed);
+        }
+        reg
+    }
+
+    #[quickcheck]
+    fn prop_semilattice(a: Vec<(u8, u16)>, b: Vec<(u8, u16)>, c: Vec<(u8, u16)>) {
+        let a = reg(a);
+        let b = reg(b);
+        let c = reg(c);
+
+        crate::test::assert_laws(&a, &b, &c);
+    }
+
+    #[test]
+    fn test_set_get() {
+        let mut reg = MVReg::new('a', 1);
+        assert_eq!(reg.get().collect::<Vec<_>>(), vec![&'a']);
+        assert!(!reg.is_conflicted());
+
+        reg.set('b', 2, [1]);
+        assert_eq!(reg.get().collect::<Vec<_>>(), vec![&'b']);
+
+        // Tag `2` wasn't observed, so both values are kept.
+        reg.set('c', 3, [1]);
+        assert_eq!(reg.get().collect::<Vec<_>>(), vec![&'b', &'c']);
+        assert!(reg.is_conflicted());
+
+        reg.set('d', 4, [2, 3]);
+        assert_eq!(reg.iter().collect::<Vec<_>>(), vec![(&4, &'d')]);
+    }
+
+    #[test]
+    fn test_concurrent() {
+        let mut alice = MVReg::new('a', 1);
+        let mut bob = alice.clone();
+
+        alice.set('b', 2, [1]);
+        bob.set('c', 3, [1]);
+
+        let mut merged = alice.join(bob);
+        assert_eq!(merged.get().collect::<Vec<_>>(), vec![&'b', &'c']);
+
+        // Resolve the conflict.
+        let observed = merged.tags().cloned().collect::<Vec<_>>();
+        merged.set('c', 4, observed);
+        assert_eq!(merged.get().collect::<Vec<_>>(), vec![&'c']);
+        assert!(!merged.is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::clock;
+use crate::{GMap, GSet, Semilattice};
+
+/// Observed-Remove Set.
+///
+/// Every insertion is identified by a unique tag, and a removal only removes the tags
+/// that were observed by the remover. Hence, if a value is added and removed concurrently,
+/// the "add" takes precedence over the "remove", regardless of the clocks involved.
+///
+/// It is up to the user to ensure tags are unique across all insertions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ORSet<T, C = clock::Lamport> {
+    added: GMap<T, GSet<C>>,
+    removed: GMap<T, GSet<C>>,
+}
+
+impl<T: Ord, C: Ord> ORSet<T, C> {
+    pub fn singleton(value: T, tag: C) -> Self {
+        Self {
+            added: GMap::singleton(value, GSet::singleton(tag)),
+            removed: GMap::default(),
+        }
+    }
+
+    /// Insert a value, identified by a unique tag.
+    pub fn insert(&mut self, value: T, tag: C) {
+        self.added.insert(value, GSet::singleton(tag));
+    }
+
+    /// Remove a value, given the tags under which it was observed.
+    ///
+    /// Tags that weren't observed, eg. from concurrent insertions, are not affected.
+    pub fn remove(&mut self, value: T, observed: impl IntoIterator<Item = C>) {
+        self.removed.insert(value, GSet::from_iter(observed));
+    }
+
+    /// Return the tags under which a value is currently present.
+    /// These are the tags to remove to remove the value.
+    pub fn tags<'a>(&'a self, value: &T) -> impl Iterator<Item = &'a C> + 'a {
+        let removed = self.removed.get(value);
+
+        self.added
+            .get(value)
+            .into_iter()
+            .flat_map(|tags| tags.iter())
+            .filter(move |tag| !removed.map_or(false, |r| r.contains_key(*tag)))
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.tags(value).next().is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.added
+            .keys()
+            .filter(|value| self.tags(value).next().is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+impl<T, C> Default for ORSet<T, C> {
+    fn default() -> Self {
+        Self {
+            added: GMap::default(),
+            removed: GMap::default(),
+        }
+    }
+}
+
+impl<T: Ord, C: Ord> FromIterator<(T, C)> for ORSet<T, C> {
+    fn from_iter<I: IntoIterator<Item = (T, C)>>(iter: I) -> Self {
+        let mut set = ORSet::default();
+        for (v, c) in iter.into_iter() {
+            set.insert(v, c);
+        }
+        set
+    }
+}
+
+impl<T: Ord, C: Ord> Extend<(T, C)> for ORSet<T, C> {
+    fn extend<I: IntoIterator<Item = (T, C)>>(&mut self, iter: I) {
+        for (v, c) in iter.into_iter() {
+            self.insert(v, c);
+        }
+    }
+}
+
+impl<T, C> Semilattice for ORSet<T, C>
+where
+    T: Ord,
+    C: Ord,
+{
+    fn merge(&mut self, other: Self) {
+        self.added.merge(other.added);
+        self.removed.merge(other.removed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn prop_semilattice(
+        a: Vec<(u8, u16)>,
+        b: Vec<(u8, u16)>,
+        c: Vec<(u8, u16)>,
+        mix: Vec<(u8, u16)>,
+        removals: Vec<u8>,
+    ) {
+        let mut a = ORSet::from_iter(a);
+        let mut b = ORSet::from_iter(b);
+        let mut c = ORSet::from_iter(c);
+
+        a.extend(mix.clone());
+        b.extend(mix);
+
+        for v in removals {
+            let tags = c.tags(&v).cloned().collect::<Vec<_>>();
+            c.remove(v, tags);
+        }
+        crate::test::assert_laws(&a, &b, &c);
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut set = ORSet::default();
+
+        set.insert('a', 0);
+        set.insert('b', 1);
+        set.insert('c', 2);
+
+        assert!(set.contains(&'a'));
+        assert!(set.contains(&'b'));
+        assert!(!set.contains(&'?'));
+
+        let values = set.iter().cloned().collect::<Vec<_>>();
+        assert_eq!(values, vec!['a', 'b', 'c']);
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = ORSet::default();
+
+        set.insert('a', 1);
+        set.insert('a', 2);
+        assert!(set.contains(&'a'));
+
+        set.remove('a', [1]);
+        assert!(set.contains(&'a')); // Tag `2` was not observed.
+
+        set.remove('a', [2]);
+        assert!(!set.contains(&'a'));
+        assert!(set.is_empty());
+
+        set.insert('a', 3);
+        assert!(set.contains(&'a'));
+        assert_eq!(set.tags(&'a').collect::<Vec<_>>(), vec![&3]);
+    }
+
+    #[test]
+    fn test_concurrent_add_wins() {
+        let mut alice = ORSet::<char, u8>::default();
+        alice.insert('a', 1);
+
+        let mut bob = alice.clone();
+        let observed = bob.tags(&'a').cloned().collect::<Vec<_>>();
+        bob.remove('a', observed);
+        assert!(!bob.contains(&'a'));
+
+        // Concurrently, Alice adds the same value again.
+        alice.insert('a', 2);
+
+        let merged = alice.clone().join(bob.clone());
+        assert!(merged.contains(&'a'));
+        assert_eq!(merged, bob.join(alice));
+    }
+}
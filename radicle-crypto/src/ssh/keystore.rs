@@ -23,6 +23,8 @@ pub enum Error {
     InvalidKeyType,
     #[error("keystore already initialized")]
     AlreadyInitialized,
+    #[error("keystore is not initialized")]
+    NotInitialized,
 }
 
 /// Stores keys on disk, in OpenSSH format.
@@ -86,6 +88,59 @@ impl Keystore {
         Ok(keypair.pk.into())
     }
 
+    /// The keystore holding the key pair being rotated to, under `rotating/`.
+    /// See [`Keystore::prepare_rotation`].
+    pub fn pending(&self) -> Keystore {
+        Keystore::new(&self.path.join("rotating"))
+    }
+
+    /// Generate the key pair to rotate to, without replacing the current key pair.
+    ///
+    /// If a pending key pair already exists, eg. because a previous rotation was interrupted,
+    /// it is kept. Returns the pending public key.
+    pub fn prepare_rotation(
+        &self,
+        comment: &str,
+        passphrase: impl Into<Passphrase>,
+    ) -> Result<PublicKey, Error> {
+        if self.public_key()?.is_none() {
+            return Err(Error::NotInitialized);
+        }
+        let pending = self.pending();
+
+        match pending.public_key()? {
+            Some(pk) => Ok(pk),
+            None => pending.init(comment, passphrase),
+        }
+    }
+
+    /// Replace the current key pair with the pending one, once everything signed with the
+    /// current key was migrated to it. See [`Keystore::prepare_rotation`].
+    ///
+    /// The current key pair is moved to `rotated/<public-key>/` under the keystore path,
+    /// so that it remains available. Returns the new public key.
+    pub fn rotate(&self) -> Result<PublicKey, Error> {
+        let old = self.public_key()?.ok_or(Error::NotInitialized)?;
+        let pending = self.pending();
+        let new = pending.public_key()?.ok_or(Error::NotInitialized)?;
+        let archive = self.path.join("rotated").join(old.to_human());
+
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&archive)?;
+
+        for file in ["radicle", "radicle.pub"] {
+            fs::rename(self.path.join(file), archive.join(file))?;
+        }
+        for file in ["radicle", "radicle.pub"] {
+            fs::rename(pending.path.join(file), self.path.join(file))?;
+        }
+        fs::remove_dir(&pending.path)?;
+
+        Ok(new)
+    }
+
     /// Load the public key from the store. Returns `None` if it wasn't found.
     pub fn public_key(&self) -> Result<Option<PublicKey>, Error> {
         let path = self.path.join("radicle.pub");
@@ -257,4 +312,39 @@ mod tests {
 
         assert_eq!(public, *signer.public_key());
     }
+
+    #[test]
+    fn test_rotate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = Keystore::new(&tmp.path());
+
+        store
+            .prepare_rotation("test", "hunter".to_owned())
+            .unwrap_err();
+
+        let old = store.init("test", "hunter".to_owned()).unwrap();
+        let new = store
+            .prepare_rotation("test", "hunter2".to_owned())
+            .unwrap();
+        assert_ne!(old, new);
+        // The current key is kept until the rotation is done.
+        assert_eq!(old, store.public_key().unwrap().unwrap());
+        // Preparing again keeps the pending key.
+        assert_eq!(
+            new,
+            store
+                .prepare_rotation("test", "hunter3".to_owned())
+                .unwrap()
+        );
+        assert!(MemorySigner::load(&store.pending(), "hunter2".to_owned().into()).is_ok());
+
+        assert_eq!(new, store.rotate().unwrap());
+        assert_eq!(new, store.public_key().unwrap().unwrap());
+        assert!(MemorySigner::load(&store, "hunter2".to_owned().into()).is_ok());
+        assert!(store.pending().public_key().unwrap().is_none());
+
+        let archive = Keystore::new(&tmp.path().join("rotated").join(old.to_human()));
+        assert_eq!(old, archive.public_key().unwrap().unwrap());
+        assert!(MemorySigner::load(&archive, "hunter".to_owned().into()).is_ok());
+    }
 }
@@ -0,0 +1,70 @@
+//! Benchmarks for DAG algorithms on large graphs.
+//!
+//! Run with `cargo bench -p radicle-dag`.
+use std::time::{Duration, Instant};
+
+use radicle_dag::Dag;
+
+/// Number of nodes in the benchmark graphs.
+const NODES: u64 = 100_000;
+
+/// Generate a random DAG where every node depends on up to three earlier nodes,
+/// mostly recent ones, similar to the history of a busy collaborative object.
+fn generate(rng: &fastrand::Rng) -> Dag<u64, ()> {
+    let mut dag = Dag::new();
+
+    dag.node(0, ());
+    for i in 1..NODES {
+        dag.node(i, ());
+
+        for _ in 0..rng.usize(1..=3) {
+            let dist = rng.u64(1..=i.min(16));
+            dag.dependency(i, i - dist);
+        }
+    }
+    dag
+}
+
+fn bench<T>(name: &str, iterations: u32, mut f: impl FnMut() -> T) {
+    let mut elapsed = Duration::ZERO;
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        std::hint::black_box(f());
+        elapsed += start.elapsed();
+    }
+    println!(
+        "{name:<32} {:>12.3?}/iter ({iterations} iterations)",
+        elapsed / iterations
+    );
+}
+
+fn main() {
+    let rng = fastrand::Rng::with_seed(7);
+    let dag = generate(&rng);
+    let pairs = (0..1000)
+        .map(|_| (rng.u64(0..NODES), rng.u64(0..NODES)))
+        .collect::<Vec<_>>();
+
+    println!("DAG with {} nodes", dag.len());
+
+    bench("ordered", 10, || dag.ordered());
+    bench("ancestors (tip)", 10, || dag.ancestors(&(NODES - 1)));
+    bench("is_ancestor (1000 pairs)", 1, || {
+        pairs.iter().filter(|(a, b)| dag.is_ancestor(a, b)).count()
+    });
+    bench("reachability", 10, || dag.reachability());
+
+    let reach = dag.reachability();
+    bench("reachability/is_ancestor (1000 pairs)", 10, || {
+        pairs
+            .iter()
+            .filter(|(a, b)| reach.is_ancestor(a, b))
+            .count()
+    });
+    bench("lowest_common_ancestors", 10, || {
+        dag.lowest_common_ancestors(&(NODES - 1), &(NODES / 2))
+    });
+    bench("subgraph (half)", 10, || dag.subgraph([NODES / 2]));
+    bench("prune (half)", 10, || dag.clone().prune(&(NODES / 2)));
+}
@@ -1,6 +1,7 @@
 use std::{
     borrow::Borrow,
-    collections::{HashMap, HashSet},
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
     fmt,
     hash::Hash,
     ops::{Deref, Index},
@@ -145,6 +146,167 @@ impl<K: Eq + Copy + Hash, V> Dag<K, V> {
         }
     }
 
+    /// Remove a node and all the nodes that depend on it, directly or transitively.
+    /// Returns the keys of the removed nodes.
+    pub fn prune(&mut self, key: &K) -> Vec<K> {
+        let mut removed = Vec::new();
+        let mut stack = vec![*key];
+
+        while let Some(key) = stack.pop() {
+            let Some(node) = self.graph.remove(&key) else {
+                continue;
+            };
+            self.tips.remove(&key);
+            self.roots.remove(&key);
+
+            for dependency in &node.dependencies {
+                if let Some(n) = self.graph.get_mut(dependency) {
+                    n.dependents.remove(&key);
+
+                    if n.dependents.is_empty() {
+                        self.tips.insert(*dependency);
+                    }
+                }
+            }
+            stack.extend(node.dependents);
+            removed.push(key);
+        }
+        removed
+    }
+
+    /// Return the sub-graph made of the given nodes and all of their ancestors.
+    /// Keys that are not in the graph are ignored.
+    pub fn subgraph(&self, keys: impl IntoIterator<Item = K>) -> Self
+    where
+        V: Clone,
+    {
+        let keys = keys
+            .into_iter()
+            .filter(|k| self.graph.contains_key(k))
+            .collect::<Vec<_>>();
+        let mut included = self.ancestors_of(keys.iter());
+        included.extend(keys);
+
+        let mut dag = Self::new();
+        for key in included.iter() {
+            let node = &self.graph[key];
+
+            if self.roots.contains(key) {
+                dag.roots.insert(*key);
+            }
+            if !node.dependents.iter().any(|d| included.contains(d)) {
+                dag.tips.insert(*key);
+            }
+            dag.graph.insert(
+                *key,
+                Node {
+                    value: node.value.clone(),
+                    dependencies: node.dependencies.clone(),
+                    dependents: node
+                        .dependents
+                        .iter()
+                        .filter(|d| included.contains(d))
+                        .copied()
+                        .collect(),
+                },
+            );
+        }
+        dag
+    }
+
+    /// Return all the ancestors of a node, ie. the nodes it depends on, directly or
+    /// transitively. The node itself is not included.
+    pub fn ancestors(&self, key: &K) -> HashSet<K> {
+        self.ancestors_of([key])
+    }
+
+    /// Check whether a node is an ancestor of another node, ie. whether `of` depends
+    /// on `ancestor`, directly or transitively.
+    ///
+    /// For repeated queries on large graphs, use [`Dag::reachability`].
+    pub fn is_ancestor(&self, ancestor: &K, of: &K) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![of];
+
+        while let Some(key) = stack.pop() {
+            let Some(node) = self.graph.get(key) else {
+                continue;
+            };
+            for dependency in &node.dependencies {
+                if dependency == ancestor {
+                    return true;
+                }
+                if visited.insert(*dependency) {
+                    stack.push(dependency);
+                }
+            }
+        }
+        false
+    }
+
+    /// Return the lowest common ancestors of two nodes, ie. the common ancestors
+    /// that are not ancestors of any other common ancestor. If one node is an ancestor
+    /// of the other, it is its own lowest common ancestor.
+    ///
+    /// There may be more than one lowest common ancestor, eg. in the case of criss-cross merges.
+    pub fn lowest_common_ancestors(&self, a: &K, b: &K) -> HashSet<K> {
+        if !self.graph.contains_key(a) || !self.graph.contains_key(b) {
+            return HashSet::new();
+        }
+        let mut a_ancestors = self.ancestors(a);
+        a_ancestors.insert(*a);
+
+        let mut b_ancestors = self.ancestors(b);
+        b_ancestors.insert(*b);
+
+        let common = a_ancestors
+            .intersection(&b_ancestors)
+            .copied()
+            .collect::<HashSet<_>>();
+        // Common ancestors that are themselves ancestors of other common ancestors
+        // are not the lowest.
+        let dominated = self.ancestors_of(common.iter());
+
+        common.difference(&dominated).copied().collect()
+    }
+
+    /// Return a reachability index over the graph, which speeds up repeated
+    /// ancestry queries.
+    pub fn reachability(&self) -> Reachability<'_, K, V> {
+        Reachability::new(self)
+    }
+
+    /// Return a deterministic topological ordering of the graph's nodes. When more
+    /// than one node can come next, the node with the smallest key is picked.
+    ///
+    /// Nodes that are part of a cycle are not included.
+    pub fn ordered(&self) -> Vec<K>
+    where
+        K: Ord,
+    {
+        let mut order = Vec::with_capacity(self.graph.len());
+        let mut degrees = self.degrees();
+        let mut queue = degrees
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(k, _)| Reverse(*k))
+            .collect::<BinaryHeap<_>>();
+
+        while let Some(Reverse(key)) = queue.pop() {
+            for dependent in &self.graph[&key].dependents {
+                if let Some(degree) = degrees.get_mut(dependent) {
+                    *degree -= 1;
+
+                    if *degree == 0 {
+                        queue.push(Reverse(*dependent));
+                    }
+                }
+            }
+            order.push(key);
+        }
+        order
+    }
+
     /// Return a topological ordering of the graph's nodes, using the given RNG.
     /// Graphs with more than one partial order will return an arbitrary topological ordering.
     ///
@@ -162,6 +324,41 @@ impl<K: Eq + Copy + Hash, V> Dag<K, V> {
         order
     }
 
+    /// Return the ancestors of the given nodes, not including the nodes themselves,
+    /// unless one node is the ancestor of another.
+    fn ancestors_of<'a>(&'a self, keys: impl IntoIterator<Item = &'a K>) -> HashSet<K> {
+        let mut ancestors = HashSet::new();
+        let mut stack = keys.into_iter().collect::<Vec<_>>();
+
+        while let Some(key) = stack.pop() {
+            let Some(node) = self.graph.get(key) else {
+                continue;
+            };
+            for dependency in &node.dependencies {
+                if self.graph.contains_key(dependency) && ancestors.insert(*dependency) {
+                    stack.push(dependency);
+                }
+            }
+        }
+        ancestors
+    }
+
+    /// Return the number of dependencies of each node, only counting dependencies
+    /// that are in the graph.
+    fn degrees(&self) -> HashMap<K, usize> {
+        self.graph
+            .iter()
+            .map(|(k, n)| {
+                let degree = n
+                    .dependencies
+                    .iter()
+                    .filter(|d| self.graph.contains_key(d))
+                    .count();
+                (*k, degree)
+            })
+            .collect()
+    }
+
     /// Add nodes recursively to the topological order, starting from the given node.
     fn visit(&self, key: &K, visited: &mut HashSet<K>, order: &mut Vec<K>) {
         if visited.contains(key) {
@@ -180,6 +377,236 @@ impl<K: Eq + Copy + Hash, V> Dag<K, V> {
     }
 }
 
+/// Number of randomized traversals used to label the nodes of a [`Reachability`] index.
+const TRAVERSALS: usize = 5;
+
+/// Labels of a node in a [`Reachability`] index.
+#[derive(Debug, Clone, Copy)]
+struct Labels {
+    /// Generation number.
+    generation: usize,
+    /// For each traversal, the position of the node when it was first visited.
+    pre: [usize; TRAVERSALS],
+    /// For each traversal, the interval `(low, post)`, where `post` is the position of the
+    /// node when it was last visited, and `low` is the lowest `post` of its ancestors.
+    intervals: [(usize, usize); TRAVERSALS],
+}
+
+impl Labels {
+    /// Whether the intervals of `other` are contained in ours. This is true of all our
+    /// ancestors, but may be true of other nodes.
+    fn may_reach(&self, other: &Self) -> bool {
+        self.intervals
+            .iter()
+            .zip(other.intervals.iter())
+            .all(|((low, post), (other_low, other_post))| low <= other_low && other_post <= post)
+    }
+
+    /// Whether `other` is below us in the spanning tree of one of the traversals. This is only
+    /// true of our ancestors, but not of all of them.
+    fn reaches(&self, other: &Self) -> bool {
+        (0..TRAVERSALS)
+            .any(|i| self.pre[i] <= other.pre[i] && other.intervals[i].1 <= self.intervals[i].1)
+    }
+}
+
+/// Reachability index over a [`Dag`].
+///
+/// Every node is assigned a *generation number*, which is one more than the highest
+/// generation of its dependencies, and is labeled by a few depth-first traversals of the
+/// graph, in the spirit of GRAIL[^1]:
+///
+/// * Nodes below a node in the spanning tree of one of the traversals are its ancestors.
+/// * Nodes whose intervals aren't contained in the intervals of a node aren't its ancestors.
+///
+/// Most queries are answered from the labels alone. The others search the graph, skipping
+/// the nodes that are too low in the graph, or whose labels rule them out.
+///
+/// [^1]: H. Yildirim, V. Chaoji, M. J. Zaki, "GRAIL: Scalable Reachability Index for Large
+/// Graphs", VLDB 2010.
+pub struct Reachability<'a, K: Eq + Hash, V> {
+    dag: &'a Dag<K, V>,
+    labels: HashMap<K, Labels>,
+}
+
+impl<'a, K: Eq + Copy + Hash, V> Reachability<'a, K, V> {
+    /// Build a reachability index over the given graph.
+    pub fn new(dag: &'a Dag<K, V>) -> Self {
+        let generations = Self::generations(dag);
+        // The traversals are randomized, but the index is deterministic.
+        let rng = fastrand::Rng::with_seed(TRAVERSALS as u64);
+        let traversals = (0..TRAVERSALS)
+            .map(|i| Self::traverse(dag, (i > 0).then_some(&rng)))
+            .collect::<Vec<_>>();
+
+        let labels = generations
+            .into_iter()
+            .map(|(key, generation)| {
+                let mut labels = Labels {
+                    generation,
+                    pre: [0; TRAVERSALS],
+                    intervals: [(0, 0); TRAVERSALS],
+                };
+                for (i, (pre, intervals)) in traversals.iter().enumerate() {
+                    labels.pre[i] = pre[&key];
+                    labels.intervals[i] = intervals[&key];
+                }
+                (key, labels)
+            })
+            .collect();
+
+        Self { dag, labels }
+    }
+
+    /// Get the generation number of a node. Root nodes have a generation of zero.
+    /// Returns `None` if the node isn't in the graph, or is part of a cycle.
+    pub fn generation(&self, key: &K) -> Option<usize> {
+        self.labels.get(key).map(|l| l.generation)
+    }
+
+    /// Check whether a node is an ancestor of another node.
+    /// See [`Dag::is_ancestor`].
+    pub fn is_ancestor(&self, ancestor: &K, of: &K) -> bool {
+        let (Some(target), Some(start)) = (self.labels.get(ancestor), self.labels.get(of)) else {
+            return false;
+        };
+        if target.generation >= start.generation || !start.may_reach(target) {
+            return false;
+        }
+        if start.reaches(target) {
+            return true;
+        }
+        let mut visited = HashSet::new();
+        let mut stack = vec![*of];
+        let mut next = Vec::new();
+
+        while let Some(key) = stack.pop() {
+            for dependency in &self.dag.graph[&key].dependencies {
+                if dependency == ancestor {
+                    return true;
+                }
+                let Some(labels) = self.labels.get(dependency) else {
+                    continue;
+                };
+                if labels.generation <= target.generation || !labels.may_reach(target) {
+                    continue;
+                }
+                if labels.reaches(target) {
+                    return true;
+                }
+                if visited.insert(*dependency) {
+                    next.push((labels.generation, *dependency));
+                }
+            }
+            // Visit the lowest dependencies first, since they are closest to the target.
+            next.sort_unstable_by_key(|(g, _)| Reverse(*g));
+            stack.extend(next.drain(..).map(|(_, k)| k));
+        }
+        false
+    }
+
+    /// Compute the generation numbers of the nodes. Nodes that are part of a cycle don't
+    /// have one.
+    fn generations(dag: &Dag<K, V>) -> HashMap<K, usize> {
+        let mut generations = HashMap::with_capacity(dag.len());
+        let mut degrees = dag.degrees();
+        let mut queue = degrees
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(k, _)| *k)
+            .collect::<VecDeque<_>>();
+
+        while let Some(key) = queue.pop_front() {
+            let node = &dag.graph[&key];
+            let generation = node
+                .dependencies
+                .iter()
+                .filter_map(|d| generations.get(d))
+                .max()
+                .map_or(0, |g| g + 1);
+
+            generations.insert(key, generation);
+
+            for dependent in &node.dependents {
+                if let Some(degree) = degrees.get_mut(dependent) {
+                    *degree -= 1;
+
+                    if *degree == 0 {
+                        queue.push_back(*dependent);
+                    }
+                }
+            }
+        }
+        generations
+    }
+
+    /// Traverse the graph depth-first, from the nodes without dependents to their
+    /// dependencies, in random order if an RNG is given. Returns the position of each node
+    /// when it is first visited, and its `(low, post)` interval.
+    fn traverse(
+        dag: &Dag<K, V>,
+        rng: Option<&fastrand::Rng>,
+    ) -> (HashMap<K, usize>, HashMap<K, (usize, usize)>) {
+        let mut pre = HashMap::with_capacity(dag.len());
+        let mut intervals: HashMap<K, (usize, usize)> = HashMap::with_capacity(dag.len());
+        let mut starts = dag
+            .graph
+            .iter()
+            .filter(|(_, n)| n.dependents.iter().all(|d| !dag.graph.contains_key(d)))
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        // Nodes that are part of a cycle may not be reachable from the nodes without
+        // dependents.
+        starts.extend(dag.graph.keys().copied());
+
+        let children = |key: &K| {
+            let mut children = dag.graph[key]
+                .dependencies
+                .iter()
+                .filter(|d| dag.graph.contains_key(d))
+                .copied()
+                .collect::<Vec<_>>();
+            if let Some(rng) = rng {
+                rng.shuffle(&mut children);
+            }
+            children
+        };
+        if let Some(rng) = rng {
+            rng.shuffle(&mut starts);
+        }
+
+        for start in starts {
+            if pre.contains_key(&start) {
+                continue;
+            }
+            pre.insert(start, pre.len());
+
+            let mut stack = vec![(start, children(&start), 0)];
+            while let Some((key, children_of, next)) = stack.last_mut() {
+                if let Some(child) = children_of.get(*next).copied() {
+                    *next += 1;
+
+                    if !pre.contains_key(&child) {
+                        pre.insert(child, pre.len());
+                        stack.push((child, children(&child), 0));
+                    }
+                    continue;
+                }
+                let post = intervals.len();
+                let low = children_of
+                    .iter()
+                    .filter_map(|c| intervals.get(c))
+                    .map(|(low, _)| *low)
+                    .fold(post, usize::min);
+
+                intervals.insert(*key, (low, post));
+                stack.pop();
+            }
+        }
+        (pre, intervals)
+    }
+}
+
 impl<K: Eq + Copy + Hash + fmt::Debug, V> Index<&K> for Dag<K, V> {
     type Output = Node<K, V>;
 
@@ -355,4 +782,199 @@ mod tests {
         }
         assert!(sorts.is_empty());
     }
+
+    #[test]
+    fn test_ancestors() {
+        let mut dag = Dag::new();
+
+        dag.node(0, ());
+        dag.node(1, ());
+        dag.node(2, ());
+        dag.node(3, ());
+        dag.node(4, ());
+
+        dag.dependency(1, 0);
+        dag.dependency(2, 0);
+        dag.dependency(3, 1);
+        dag.dependency(3, 2);
+
+        assert_eq!(dag.ancestors(&3), HashSet::from_iter([0, 1, 2]));
+        assert_eq!(dag.ancestors(&1), HashSet::from_iter([0]));
+        assert!(dag.ancestors(&0).is_empty());
+        assert!(dag.ancestors(&4).is_empty());
+
+        assert!(dag.is_ancestor(&0, &3));
+        assert!(dag.is_ancestor(&1, &3));
+        assert!(!dag.is_ancestor(&3, &0));
+        assert!(!dag.is_ancestor(&1, &2));
+        assert!(!dag.is_ancestor(&3, &3));
+        assert!(!dag.is_ancestor(&4, &3));
+
+        let reach = dag.reachability();
+        for a in 0..5 {
+            for b in 0..5 {
+                assert_eq!(
+                    reach.is_ancestor(&a, &b),
+                    dag.is_ancestor(&a, &b),
+                    "{a} {b}"
+                );
+                // Repeated query.
+                assert_eq!(
+                    reach.is_ancestor(&a, &b),
+                    dag.is_ancestor(&a, &b),
+                    "{a} {b}"
+                );
+            }
+        }
+        assert_eq!(reach.generation(&0), Some(0));
+        assert_eq!(reach.generation(&1), Some(1));
+        assert_eq!(reach.generation(&3), Some(2));
+        assert_eq!(reach.generation(&4), Some(0));
+        assert_eq!(reach.generation(&5), None);
+    }
+
+    #[test]
+    fn test_reachability_random() {
+        let rng = fastrand::Rng::with_seed(42);
+        let mut dag = Dag::new();
+
+        dag.node(0, ());
+        for i in 1..300 {
+            dag.node(i, ());
+
+            for _ in 0..rng.usize(0..=3) {
+                dag.dependency(i, rng.u64(0..i));
+            }
+        }
+        let reach = dag.reachability();
+
+        for a in 0..300 {
+            for b in 0..300 {
+                assert_eq!(
+                    reach.is_ancestor(&a, &b),
+                    dag.is_ancestor(&a, &b),
+                    "{a} {b}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_lowest_common_ancestors() {
+        let mut dag = Dag::new();
+
+        // Criss-cross merge.
+        //
+        //   0 <- 1 <- 3 <- 5
+        //    \      X
+        //     2 <- 4 <- 6
+        //
+        for i in 0..7 {
+            dag.node(i, ());
+        }
+        dag.dependency(1, 0);
+        dag.dependency(2, 0);
+        dag.dependency(3, 1);
+        dag.dependency(3, 2);
+        dag.dependency(4, 1);
+        dag.dependency(4, 2);
+        dag.dependency(5, 3);
+        dag.dependency(6, 4);
+
+        assert_eq!(
+            dag.lowest_common_ancestors(&5, &6),
+            HashSet::from_iter([1, 2])
+        );
+        assert_eq!(
+            dag.lowest_common_ancestors(&3, &4),
+            HashSet::from_iter([1, 2])
+        );
+        assert_eq!(dag.lowest_common_ancestors(&1, &2), HashSet::from_iter([0]));
+        assert_eq!(dag.lowest_common_ancestors(&1, &5), HashSet::from_iter([1]));
+        assert_eq!(dag.lowest_common_ancestors(&5, &5), HashSet::from_iter([5]));
+        assert!(dag.lowest_common_ancestors(&5, &7).is_empty());
+    }
+
+    #[test]
+    fn test_ordered() {
+        let mut dag = Dag::new();
+
+        dag.node(0, ());
+        dag.node(1, ());
+        dag.node(2, ());
+        dag.node(3, ());
+        dag.node(4, ());
+        dag.node(5, ());
+
+        dag.dependency(3, 2);
+        dag.dependency(1, 3);
+        dag.dependency(2, 5);
+        dag.dependency(0, 5);
+        dag.dependency(0, 4);
+        dag.dependency(1, 4);
+
+        assert_eq!(dag.ordered(), vec![4, 5, 0, 2, 3, 1]);
+        assert_eq!(dag.ordered(), dag.clone().ordered());
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut dag = Dag::new();
+
+        dag.node(0, ());
+        dag.node(1, ());
+        dag.node(2, ());
+        dag.node(3, ());
+
+        dag.dependency(1, 0);
+        dag.dependency(2, 0);
+        dag.dependency(3, 1);
+
+        let mut removed = dag.prune(&1);
+        removed.sort();
+
+        assert_eq!(removed, vec![1, 3]);
+        assert_eq!(dag.len(), 2);
+        assert!(dag.get(&1).is_none());
+        assert!(dag.get(&3).is_none());
+        assert!(dag[&0].dependents.contains(&2));
+        assert!(!dag[&0].dependents.contains(&1));
+        assert_eq!(dag.tips().map(|(k, _)| *k).collect::<Vec<_>>(), vec![2]);
+
+        dag.prune(&2);
+        assert_eq!(dag.tips().map(|(k, _)| *k).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(dag.roots().map(|(k, _)| *k).collect::<Vec<_>>(), vec![0]);
+
+        assert!(dag.prune(&2).is_empty());
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let mut dag = Dag::new();
+
+        dag.node(0, "a");
+        dag.node(1, "b");
+        dag.node(2, "c");
+        dag.node(3, "d");
+
+        dag.dependency(1, 0);
+        dag.dependency(2, 0);
+        dag.dependency(3, 1);
+
+        let sub = dag.subgraph([1]);
+        assert_eq!(sub.len(), 2);
+        assert_eq!(sub[&1].value, "b");
+        assert!(sub.has_dependency(&1, &0));
+        assert_eq!(sub.tips().map(|(k, _)| *k).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(sub.roots().map(|(k, _)| *k).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(sub[&0].dependents, HashSet::from_iter([1]));
+
+        let sub = dag.subgraph([2, 3, 9]);
+        assert_eq!(sub.len(), 4);
+        assert_eq!(
+            sub.tips().map(|(k, _)| *k).collect::<HashSet<_>>(),
+            HashSet::from_iter([2, 3])
+        );
+        assert!(dag.subgraph([]).is_empty());
+    }
 }
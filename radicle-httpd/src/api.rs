@@ -17,6 +17,7 @@ use tower_http::cors::{self, CorsLayer};
 use radicle::cob::issue::Issues;
 use radicle::cob::patch::Patches;
 use radicle::identity::Id;
+use radicle::storage::git::Repository;
 use radicle::storage::{ReadRepository, ReadStorage};
 use radicle::Profile;
 
@@ -43,12 +44,23 @@ impl Context {
         }
     }
 
+    /// Open a repository of storage, if it is public. Private repositories are
+    /// reported as not found.
+    pub fn repository(&self, id: Id) -> Result<Repository, error::Error> {
+        let repo = self.profile.storage.repository(id)?;
+
+        if !repo.is_public()? {
+            return Err(error::Error::NotFound);
+        }
+        Ok(repo)
+    }
+
     pub fn project_info(&self, id: Id) -> Result<project::Info, error::Error> {
-        let storage = &self.profile.storage;
-        let repo = storage.repository(id)?;
+        let repo = self.repository(id)?;
         let (_, head) = repo.head()?;
         let doc = repo.identity_doc()?.1.verified()?;
         let payload = doc.project()?;
+        let fork = doc.fork().ok();
         let delegates = doc.delegates;
         let issues = Issues::open(&repo)?.counts()?;
         let patches = Patches::open(&repo)?.counts()?;
@@ -60,6 +72,7 @@ impl Context {
             issues,
             patches,
             id,
+            fork,
         })
     }
 
@@ -122,7 +135,7 @@ mod project {
     use radicle::cob;
     use radicle::git::Oid;
     use radicle::identity::project::Project;
-    use radicle::identity::Id;
+    use radicle::identity::{Fork, Id};
     use radicle::prelude::Did;
 
     /// Project info.
@@ -137,5 +150,8 @@ mod project {
         pub patches: cob::patch::PatchCounts,
         pub issues: cob::issue::IssueCounts,
         pub id: Id,
+        /// The project this project was forked from, if any.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub fork: Option<Fork>,
     }
 }
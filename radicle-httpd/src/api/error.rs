@@ -69,6 +69,10 @@ pub enum Error {
     /// Identity doc error.
     #[error(transparent)]
     IdentityDoc(#[from] radicle::identity::doc::DocError),
+
+    /// Release error.
+    #[error(transparent)]
+    Release(#[from] radicle::release::ReleaseError),
 }
 
 impl IntoResponse for Error {
@@ -1,5 +1,7 @@
 //! Utilities for building JSON responses of our API.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::path::Path;
 use std::str;
 
@@ -12,6 +14,7 @@ use radicle::cob::thread;
 use radicle::cob::thread::{CommentId, Thread};
 use radicle::cob::{ActorId, Author, Reaction, Timestamp};
 use radicle::git::RefString;
+use radicle::identity::Person;
 use radicle::storage::{git, refs, ReadRepository};
 use radicle_surf::blob::Blob;
 use radicle_surf::tree::Tree;
@@ -92,26 +95,26 @@ pub(crate) fn tree(tree: &Tree, path: &str, stats: &Stats) -> Value {
 }
 
 /// Returns JSON for an `issue`.
-pub(crate) fn issue(id: IssueId, issue: Issue) -> Value {
+pub(crate) fn issue(id: IssueId, issue: Issue, authors: &Authors) -> Value {
     json!({
         "id": id.to_string(),
-        "author": issue.author(),
+        "author": authors.get(&issue.author()),
         "title": issue.title(),
         "state": issue.state(),
         "assignees": issue.assigned().collect::<Vec<_>>(),
         "discussion": issue
           .comments()
-          .map(|(id, comment)| Comment::new(id, comment, issue.thread()))
+          .map(|(id, comment)| Comment::new(id, comment, issue.thread(), authors))
           .collect::<Vec<_>>(),
         "tags": issue.tags().collect::<Vec<_>>(),
     })
 }
 
 /// Returns JSON for a `patch`.
-pub(crate) fn patch(id: PatchId, patch: Patch, repo: &git::Repository) -> Value {
+pub(crate) fn patch(id: PatchId, patch: Patch, authors: &Authors) -> Value {
     json!({
         "id": id.to_string(),
-        "author": patch.author(),
+        "author": authors.get(patch.author()),
         "title": patch.title(),
         "description": patch.description(),
         "state": patch.state(),
@@ -123,11 +126,11 @@ pub(crate) fn patch(id: PatchId, patch: Patch, repo: &git::Repository) -> Value
                 "description": rev.description(),
                 "base": rev.base,
                 "oid": rev.oid,
-                "refs": get_refs(repo, patch.author().id(), &rev.oid).unwrap_or(vec![]),
+                "refs": get_refs(authors.repo, patch.author().id(), &rev.oid).unwrap_or(vec![]),
                 "merges": rev.merges().collect::<Vec<_>>(),
                 "discussions": rev.discussion
                   .comments()
-                  .map(|(id, comment)| Comment::new(id, comment, &rev.discussion))
+                  .map(|(id, comment)| Comment::new(id, comment, &rev.discussion, authors))
                   .collect::<Vec<_>>(),
                 "timestamp": rev.timestamp,
                 "reviews": rev.reviews().collect::<Vec<_>>(),
@@ -136,6 +139,45 @@ pub(crate) fn patch(id: PatchId, patch: Patch, repo: &git::Repository) -> Value
     })
 }
 
+/// Looks up the display names of authors in a repository, loading the person
+/// document of each author at most once.
+pub(crate) struct Authors<'a> {
+    repo: &'a git::Repository,
+    names: RefCell<HashMap<ActorId, Option<String>>>,
+}
+
+impl<'a> Authors<'a> {
+    pub(crate) fn new(repo: &'a git::Repository) -> Self {
+        Self {
+            repo,
+            names: RefCell::default(),
+        }
+    }
+
+    /// Returns JSON for an `author`, along with their display name if they published
+    /// a person document in the repository.
+    pub(crate) fn get(&self, author: &Author) -> Value {
+        let name = self
+            .names
+            .borrow_mut()
+            .entry(*author.id())
+            .or_insert_with(|| {
+                Person::load(author.id(), self.repo)
+                    .ok()
+                    .map(|at| at.person.name)
+            })
+            .clone();
+
+        match name {
+            Some(name) => json!({
+                "id": author.id(),
+                "name": name,
+            }),
+            None => json!(author),
+        }
+    }
+}
+
 /// Returns the name part of a path string.
 fn name_in_path(path: &str) -> &str {
     match path.rsplit('/').next() {
@@ -169,7 +211,7 @@ fn get_refs(
 #[serde(rename_all = "camelCase")]
 struct Comment<'a> {
     id: CommentId,
-    author: Author,
+    author: Value,
     body: &'a str,
     reactions: Vec<(&'a ActorId, &'a Reaction)>,
     timestamp: Timestamp,
@@ -177,10 +219,15 @@ struct Comment<'a> {
 }
 
 impl<'a> Comment<'a> {
-    fn new(id: &'a CommentId, comment: &'a thread::Comment, thread: &'a Thread) -> Self {
+    fn new(
+        id: &'a CommentId,
+        comment: &'a thread::Comment,
+        thread: &'a Thread,
+        authors: &Authors,
+    ) -> Self {
         Self {
             id: *id,
-            author: Author::new(comment.author()),
+            author: authors.get(&Author::new(comment.author())),
             body: comment.body(),
             reactions: thread.reactions(id).collect::<Vec<_>>(),
             timestamp: comment.timestamp(),
@@ -35,7 +35,7 @@ async fn delegates_projects_handler(
     let per_page = per_page.unwrap_or(10);
     let storage = &ctx.profile.storage;
     let projects = storage
-        .repositories()?
+        .public_inventory()?
         .into_iter()
         .filter_map(|id| {
             let Ok(repo) = storage.repository(id) else { return None };
@@ -44,6 +44,7 @@ async fn delegates_projects_handler(
             let Ok(doc) = doc.verified() else { return None };
             let Ok(payload) = doc.project() else { return None };
 
+            let fork = doc.fork().ok();
             let delegates = doc.delegates;
             if !delegates.iter().any(|d| *d == delegate) {
                 return None;
@@ -61,6 +62,7 @@ async fn delegates_projects_handler(
                 issues,
                 patches,
                 id,
+                fork,
             })
         })
         .skip(page * per_page)
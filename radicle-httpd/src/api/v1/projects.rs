@@ -13,9 +13,10 @@ use serde_json::json;
 use tower_http::set_header::SetResponseHeaderLayer;
 
 use radicle::cob::{issue, patch, thread, ActorId, Tag};
-use radicle::identity::Id;
+use radicle::identity::{Id, Identity};
 use radicle::node::NodeId;
-use radicle::storage::git::paths;
+use radicle::release;
+use radicle::storage::git::divergence;
 use radicle::storage::{ReadRepository, ReadStorage, WriteRepository};
 use radicle_surf::{Glob, Oid, Repository};
 
@@ -44,8 +45,12 @@ pub fn router(ctx: Context) -> Router {
         )
         .route("/projects/:project/tree/:sha/", get(tree_handler_root))
         .route("/projects/:project/tree/:sha/*path", get(tree_handler))
+        .route("/projects/:project/forks", get(forks_handler))
+        .route("/projects/:project/identity", get(identity_handler))
         .route("/projects/:project/remotes", get(remotes_handler))
         .route("/projects/:project/remotes/:peer", get(remote_handler))
+        .route("/projects/:project/releases", get(releases_handler))
+        .route("/projects/:project/divergence", get(divergence_handler))
         .route("/projects/:project/blob/:sha/*path", get(blob_handler))
         .route("/projects/:project/readme/:sha", get(readme_handler))
         .route(
@@ -78,7 +83,7 @@ async fn project_root_handler(
     let per_page = per_page.unwrap_or(10);
     let storage = &ctx.profile.storage;
     let projects = storage
-        .repositories()?
+        .public_inventory()?
         .into_iter()
         .filter_map(|id| {
             let Ok(repo) = storage.repository(id) else { return None };
@@ -90,6 +95,7 @@ async fn project_root_handler(
             let Ok(issues) = issues.counts() else { return None };
             let Ok(patches) = patch::Patches::open(&repo) else { return None };
             let Ok(patches) = patches.counts() else { return None };
+            let fork = doc.fork().ok();
             let delegates = doc.delegates;
 
             Some(Info {
@@ -99,6 +105,7 @@ async fn project_root_handler(
                 issues,
                 patches,
                 id,
+                fork,
             })
         })
         .skip(page * per_page)
@@ -116,6 +123,33 @@ async fn project_handler(State(ctx): State<Context>, Path(id): Path<Id>) -> impl
     Ok::<_, Error>(Json(info))
 }
 
+/// Get the projects forked from a project, that are in storage.
+/// `GET /projects/:project/forks`
+async fn forks_handler(
+    State(ctx): State<Context>,
+    Path(project): Path<Id>,
+    Query(qs): Query<PaginationQuery>,
+) -> impl IntoResponse {
+    let PaginationQuery { page, per_page } = qs;
+    let page = page.unwrap_or(0);
+    let per_page = per_page.unwrap_or(10);
+    let storage = &ctx.profile.storage;
+    // Make sure the upstream project exists.
+    ctx.repository(project)?;
+
+    let forks = storage
+        .public_inventory()?
+        .into_iter()
+        .filter(|id| *id != project)
+        .filter_map(|id| ctx.project_info(id).ok())
+        .filter(|info| info.fork.as_ref().map_or(false, |f| f.upstream == project))
+        .skip(page * per_page)
+        .take(per_page)
+        .collect::<Vec<_>>();
+
+    Ok::<_, Error>(Json(forks))
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CommitsQueryString {
@@ -150,8 +184,7 @@ async fn history_handler(
         }
     };
 
-    let storage = &ctx.profile.storage;
-    let repo = Repository::open(paths::repository(storage, &project))?;
+    let repo = Repository::open(ctx.repository(project)?.path())?;
 
     // If a pagination is defined, we do not want to paginate the commits, and we return all of them on the first page.
     let page = page.unwrap_or(0);
@@ -213,8 +246,7 @@ async fn commit_handler(
     State(ctx): State<Context>,
     Path((project, sha)): Path<(Id, Oid)>,
 ) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = Repository::open(paths::repository(storage, &project))?;
+    let repo = Repository::open(ctx.repository(project)?.path())?;
     let commit = repo.commit(sha)?;
 
     let diff = repo.diff_commit(commit.id)?;
@@ -239,8 +271,7 @@ async fn diff_handler(
     State(ctx): State<Context>,
     Path((project, base, oid)): Path<(Id, Oid, Oid)>,
 ) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = Repository::open(paths::repository(storage, &project))?;
+    let repo = Repository::open(ctx.repository(project)?.path())?;
     let base = repo.commit(base)?;
     let commit = repo.commit(oid)?;
     let diff = repo.diff(base.id, commit.id)?;
@@ -270,8 +301,7 @@ async fn activity_handler(
 ) -> impl IntoResponse {
     let current_date = chrono::Utc::now().timestamp();
     let one_year_ago = chrono::Duration::weeks(52);
-    let storage = &ctx.profile.storage;
-    let repo = Repository::open(paths::repository(storage, &project))?;
+    let repo = Repository::open(ctx.repository(project)?.path())?;
     let head = repo.head()?;
     let timestamps = repo
         .history(head)?
@@ -304,8 +334,7 @@ async fn tree_handler(
     State(ctx): State<Context>,
     Path((project, sha, path)): Path<(Id, Oid, String)>,
 ) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = Repository::open(paths::repository(storage, &project))?;
+    let repo = Repository::open(ctx.repository(project)?.path())?;
     let tree = repo.tree(sha, &path)?;
     let stats = repo.stats_from(&sha)?;
     let response = api::json::tree(&tree, &path, &stats);
@@ -313,11 +342,38 @@ async fn tree_handler(
     Ok::<_, Error>(Json(response))
 }
 
+/// Get project identity history.
+/// `GET /projects/:project/identity`
+async fn identity_handler(
+    State(ctx): State<Context>,
+    Path(project): Path<Id>,
+) -> impl IntoResponse {
+    let repo = ctx.repository(project)?;
+    let head = repo.identity()?.head;
+    let versions = Identity::versions(head, &repo)?
+        .map(|version| {
+            version.map(|v| {
+                json!({
+                    "commit": v.at.commit,
+                    "blob": v.at.blob,
+                    "revision": v.revision,
+                    "doc": v.at.doc,
+                    "signatures": v.at.sigs.keys().collect::<Vec<_>>(),
+                    "quorum": v.quorum,
+                    "threshold": v.threshold,
+                    "verified": v.is_verified(),
+                })
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok::<_, Error>(Json(versions))
+}
+
 /// Get all project remotes.
 /// `GET /projects/:project/remotes`
 async fn remotes_handler(State(ctx): State<Context>, Path(project): Path<Id>) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let remotes = repo
         .remotes()?
         .filter_map(|r| r.map(|r| r.1).ok())
@@ -349,8 +405,7 @@ async fn remote_handler(
     State(ctx): State<Context>,
     Path((project, node_id)): Path<(Id, NodeId)>,
 ) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let remote = repo.remote(&node_id)?;
     let refs = remote
         .refs
@@ -370,14 +425,76 @@ async fn remote_handler(
     Ok::<_, Error>(Json(remote))
 }
 
+/// Get project releases, with their verification status.
+/// `GET /projects/:project/releases`
+async fn releases_handler(
+    State(ctx): State<Context>,
+    Path(project): Path<Id>,
+) -> impl IntoResponse {
+    let repo = ctx.repository(project)?;
+    let releases = release::list(&repo)?
+        .into_iter()
+        .map(|r| {
+            json!({
+                "name": r.name,
+                "target": r.target,
+                "notes": r.notes,
+                "signatures": r.signatures.iter().map(|s| s.key).collect::<Vec<_>>(),
+                "canonical": r.canonical,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok::<_, Error>(Json(releases))
+}
+
+/// Get the divergence of the delegates' default branches.
+/// `GET /projects/:project/divergence`
+async fn divergence_handler(
+    State(ctx): State<Context>,
+    Path(project): Path<Id>,
+) -> impl IntoResponse {
+    let repo = ctx.repository(project)?;
+    let report = divergence::report(&repo)?;
+    let delegates = report
+        .delegates
+        .iter()
+        .map(|d| {
+            let others = d
+                .others
+                .iter()
+                .map(|(id, ab)| json!({ "id": id, "ahead": ab.ahead, "behind": ab.behind }))
+                .collect::<Vec<_>>();
+
+            let canonical = d
+                .canonical
+                .map(|ab| json!({ "ahead": ab.ahead, "behind": ab.behind }));
+
+            json!({
+                "id": d.remote,
+                "head": d.head,
+                "canonical": canonical,
+                "others": others,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok::<_, Error>(Json(json!({
+        "branch": report.branch.as_str(),
+        "canonical": report.canonical,
+        "threshold": report.threshold,
+        "delegates": delegates,
+        "blocking": report.blocking.map(|b| b.to_string()),
+    })))
+}
+
 /// Get project source file.
 /// `GET /projects/:project/blob/:sha/*path`
 async fn blob_handler(
     State(ctx): State<Context>,
     Path((project, sha, path)): Path<(Id, Oid, String)>,
 ) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = Repository::open(paths::repository(storage, &project))?;
+    let repo = Repository::open(ctx.repository(project)?.path())?;
     let blob = repo.blob(sha, &path)?;
     let response = api::json::blob(&blob, &path);
 
@@ -390,8 +507,7 @@ async fn readme_handler(
     State(ctx): State<Context>,
     Path((project, sha)): Path<(Id, Oid)>,
 ) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = Repository::open(paths::repository(storage, &project))?;
+    let repo = Repository::open(ctx.repository(project)?.path())?;
     let paths = &[
         "README",
         "README.md",
@@ -421,16 +537,16 @@ async fn issues_handler(
     let PaginationQuery { page, per_page } = qs;
     let page = page.unwrap_or(0);
     let per_page = per_page.unwrap_or(10);
-    let storage = &ctx.profile.storage;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let issues = issue::Issues::open(&repo)?;
     let mut issues: Vec<_> = issues.all()?.filter_map(|r| r.ok()).collect::<Vec<_>>();
     issues.sort_by(|(_, a, _), (_, b, _)| b.timestamp().cmp(&a.timestamp()));
+    let authors = api::json::Authors::new(&repo);
     let issues = issues
         .into_iter()
-        .map(|(id, issue, _)| api::json::issue(id, issue))
         .skip(page * per_page)
         .take(per_page)
+        .map(|(id, issue, _)| api::json::issue(id, issue, &authors))
         .collect::<Vec<_>>();
 
     Ok::<_, Error>(Json(issues))
@@ -454,12 +570,11 @@ async fn issue_create_handler(
 ) -> impl IntoResponse {
     let sessions = ctx.sessions.read().await;
     sessions.get(&token).ok_or(Error::Auth("Unauthorized"))?;
-    let storage = &ctx.profile.storage;
     let signer = ctx
         .profile
         .signer()
         .map_err(|_| Error::Auth("Unauthorized"))?;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let mut issues = issue::Issues::open(&repo)?;
     let issue = issues
         .create(
@@ -491,9 +606,8 @@ async fn issue_update_handler(
         .get(&token)
         .ok_or(Error::Auth("Unauthorized"))?;
 
-    let storage = &ctx.profile.storage;
     let signer = ctx.profile.signer().unwrap();
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let mut issues = issue::Issues::open(&repo)?;
     let mut issue = issues.get_mut(&issue_id.into())?;
 
@@ -540,13 +654,16 @@ async fn issue_handler(
     State(ctx): State<Context>,
     Path((project, issue_id)): Path<(Id, Oid)>,
 ) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let issue = issue::Issues::open(&repo)?
         .get(&issue_id.into())?
         .ok_or(Error::NotFound)?;
 
-    Ok::<_, Error>(Json(api::json::issue(issue_id.into(), issue)))
+    Ok::<_, Error>(Json(api::json::issue(
+        issue_id.into(),
+        issue,
+        &api::json::Authors::new(&repo),
+    )))
 }
 
 #[derive(Deserialize, Serialize)]
@@ -571,12 +688,11 @@ async fn patch_create_handler(
         .await
         .get(&token)
         .ok_or(Error::Auth("Unauthorized"))?;
-    let storage = &ctx.profile.storage;
     let signer = ctx
         .profile
         .signer()
         .map_err(|_| Error::Auth("Unauthorized"))?;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let mut patches = patch::Patches::open(&repo)?;
     let base_oid = repo.raw().merge_base(*patch.target, *patch.oid)?;
 
@@ -610,12 +726,11 @@ async fn patch_update_handler(
         .await
         .get(&token)
         .ok_or(Error::Auth("Unauthorized"))?;
-    let storage = &ctx.profile.storage;
     let signer = ctx
         .profile
         .signer()
         .map_err(|_| Error::Auth("Unauthorized"))?;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let mut patches = patch::Patches::open(&repo)?;
     let mut patch = patches.get_mut(&patch_id.into())?;
     match action {
@@ -683,8 +798,7 @@ async fn patches_handler(
     let PaginationQuery { page, per_page } = qs;
     let page = page.unwrap_or(0);
     let per_page = per_page.unwrap_or(10);
-    let storage = &ctx.profile.storage;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let patches = patch::Patches::open(&repo)?;
     let mut patches = patches.all()?.filter_map(|r| r.ok()).collect::<Vec<_>>();
     patches.sort_by(|(_, a, _), (_, b, _)| b.timestamp().cmp(&a.timestamp()));
@@ -704,13 +818,16 @@ async fn patch_handler(
     State(ctx): State<Context>,
     Path((project, patch_id)): Path<(Id, Oid)>,
 ) -> impl IntoResponse {
-    let storage = &ctx.profile.storage;
-    let repo = storage.repository(project)?;
+    let repo = ctx.repository(project)?;
     let patch = patch::Patches::open(&repo)?
         .get(&patch_id.into())?
         .ok_or(Error::NotFound)?;
 
-    Ok::<_, Error>(Json(api::json::patch(patch_id.into(), patch, &repo)))
+    Ok::<_, Error>(Json(api::json::patch(
+        patch_id.into(),
+        patch,
+        &api::json::Authors::new(&repo),
+    )))
 }
 
 #[cfg(test)]
@@ -718,6 +835,7 @@ mod routes {
     use axum::body::Body;
     use axum::http::StatusCode;
     use pretty_assertions::assert_eq;
+    use radicle_crypto::test::signer::MockSigner;
     use serde_json::json;
 
     use crate::test::*;
@@ -1202,6 +1320,61 @@ mod routes {
         );
     }
 
+    #[tokio::test]
+    async fn test_projects_forks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = seed(tmp.path());
+        let app = super::router(ctx.clone());
+        let response = get(&app, format!("/projects/{RID}/forks")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.json().await, json!([]));
+
+        let signer = MockSigner::from_seed([0xff; 32]);
+        let (id, _, _) = radicle::rad::hard_fork(
+            RID.parse().unwrap(),
+            Some(String::from("hello-world-fork")),
+            &signer,
+            &ctx.profile().storage,
+        )
+        .unwrap();
+
+        let response = get(&app, format!("/projects/{RID}/forks")).await;
+        let forks = response.json().await;
+        let forks = forks.as_array().unwrap();
+
+        assert_eq!(forks.len(), 1);
+        assert_eq!(forks[0]["id"], json!(id));
+        assert_eq!(forks[0]["name"], json!("hello-world-fork"));
+        assert_eq!(forks[0]["fork"]["upstream"], json!(RID));
+        assert_eq!(forks[0]["fork"]["head"], json!(HEAD));
+
+        let response = get(&app, format!("/projects/{id}")).await;
+        assert_eq!(response.json().await["fork"]["upstream"], json!(RID));
+    }
+
+    #[tokio::test]
+    async fn test_projects_identity() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = super::router(seed(tmp.path()));
+        let response = get(&app, format!("/projects/{RID}/identity")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let versions = response.json().await;
+        let versions = versions.as_array().unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0]["revision"], json!(0));
+        assert_eq!(versions[0]["doc"]["delegates"], json!([DID]));
+        assert_eq!(
+            versions[0]["signatures"],
+            json!(["z6MknSLrJoTcukLrE435hVNQT4JUhbvWLX4kUzqkEStBU8Vi"])
+        );
+        assert_eq!(versions[0]["quorum"], json!(1));
+        assert_eq!(versions[0]["threshold"], json!(1));
+        assert_eq!(versions[0]["verified"], json!(true));
+    }
+
     #[tokio::test]
     async fn test_projects_remotes_root() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1246,6 +1419,77 @@ mod routes {
         );
     }
 
+    #[tokio::test]
+    async fn test_projects_releases() {
+        use radicle::storage::{ReadStorage as _, WriteRepository as _};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let ctx = seed(tmp.path());
+        let app = super::router(ctx.clone());
+        let response = get(&app, format!("/projects/{RID}/releases")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.json().await, json!([]));
+
+        let signer = MockSigner::from_seed([0xff; 32]);
+        let repo = ctx
+            .profile()
+            .storage
+            .repository(RID.parse().unwrap())
+            .unwrap();
+        radicle::release::create(
+            &repo,
+            &radicle::git::refname!("v1.0"),
+            HEAD.parse().unwrap(),
+            "First release.",
+            &signer,
+        )
+        .unwrap();
+        repo.sign_refs(&signer).unwrap();
+
+        let response = get(&app, format!("/projects/{RID}/releases")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json().await,
+            json!([
+              {
+                "name": "v1.0",
+                "target": HEAD,
+                "notes": "First release.",
+                "signatures": [DID.strip_prefix("did:key:").unwrap()],
+                "canonical": true,
+              }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_projects_divergence() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app = super::router(seed(tmp.path()));
+        let response = get(&app, format!("/projects/{RID}/divergence")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json().await,
+            json!({
+                "branch": "refs/heads/master",
+                "canonical": HEAD,
+                "threshold": 1,
+                "delegates": [
+                    {
+                        "id": "z6MknSLrJoTcukLrE435hVNQT4JUhbvWLX4kUzqkEStBU8Vi",
+                        "head": HEAD,
+                        "canonical": { "ahead": 0, "behind": 0 },
+                        "others": [],
+                    }
+                ],
+                "blocking": null,
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_projects_blob() {
         let tmp = tempfile::tempdir().unwrap();
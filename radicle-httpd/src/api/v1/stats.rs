@@ -4,6 +4,8 @@ use axum::routing::get;
 use axum::{Json, Router};
 use serde_json::json;
 
+use radicle::storage::ReadStorage;
+
 use crate::api::error::Error;
 use crate::api::Context;
 
@@ -17,7 +19,7 @@ pub fn router(ctx: Context) -> Router {
 /// `GET /stats`
 async fn stats_handler(State(ctx): State<Context>) -> impl IntoResponse {
     let storage = &ctx.profile.storage;
-    let projects = storage.repositories()?.len();
+    let projects = storage.public_inventory()?.len();
 
     Ok::<_, Error>(Json(
         json!({ "projects": { "count": projects }, "users": { "count": 0 } }),
@@ -55,6 +55,18 @@ impl IntoResponse for GitError {
 /// Errors relating to the `/raw` route.
 #[derive(Debug, thiserror::Error)]
 pub enum RawError {
+    /// The entity was not found.
+    #[error("not found")]
+    NotFound,
+
+    /// Storage error.
+    #[error(transparent)]
+    Storage(#[from] radicle::storage::Error),
+
+    /// Identity error.
+    #[error(transparent)]
+    Identity(#[from] radicle::identity::IdentityError),
+
     /// Surf error.
     #[error(transparent)]
     Surf(#[from] radicle_surf::Error),
@@ -68,6 +80,7 @@ impl RawError {
     pub fn status(&self) -> http::StatusCode {
         match self {
             RawError::SurfFile(_) => http::StatusCode::NOT_FOUND,
+            RawError::NotFound => http::StatusCode::NOT_FOUND,
             _ => http::StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
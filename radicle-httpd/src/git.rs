@@ -18,6 +18,7 @@ use hyper::body::Buf as _;
 
 use radicle::identity::Id;
 use radicle::profile::Profile;
+use radicle::storage::{ReadRepository, ReadStorage};
 
 use crate::error::GitError as Error;
 
@@ -47,6 +48,15 @@ async fn git_handler(
             *rid
         }
     };
+    // Private repositories are never served.
+    let is_public = profile
+        .storage
+        .repository(rid)
+        .ok()
+        .and_then(|repo| repo.is_public().ok());
+    if is_public != Some(true) {
+        return Err(Error::NotFound);
+    }
 
     let (status, headers, body) = git_http_backend(
         &profile, method, headers, body, remote, rid, &request, query,
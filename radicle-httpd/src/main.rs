@@ -1,6 +1,7 @@
 use std::{collections::HashMap, process};
 
 use radicle::prelude::Id;
+use radicle::profile;
 use radicle_httpd as httpd;
 use tracing::dispatcher::Dispatch;
 
@@ -44,13 +45,15 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Parse command-line arguments into HTTP options.
-fn parse_options() -> Result<httpd::Options, lexopt::Error> {
+/// Parse command-line arguments into HTTP options. Arguments take precedence over the `web`
+/// settings of the profile configuration.
+fn parse_options() -> anyhow::Result<httpd::Options> {
     use lexopt::prelude::*;
 
+    let config = profile::Config::load(&profile::home()?.config())?.web;
     let mut parser = lexopt::Parser::from_env();
-    let mut listen = None;
-    let mut aliases = HashMap::new();
+    let mut listen = config.listen;
+    let mut aliases = config.aliases.into_iter().collect::<HashMap<_, _>>();
 
     while let Some(arg) = parser.next()? {
         match arg {
@@ -68,7 +71,7 @@ fn parse_options() -> Result<httpd::Options, lexopt::Error> {
                 println!("usage: radicle-httpd [--listen <addr>] [--alias <name> <rid>]..");
                 process::exit(0);
             }
-            _ => return Err(arg.unexpected()),
+            _ => return Err(arg.unexpected().into()),
         }
     }
     Ok(httpd::Options {
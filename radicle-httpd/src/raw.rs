@@ -12,6 +12,7 @@ use tower_http::cors;
 use radicle::prelude::Id;
 use radicle::profile::Profile;
 use radicle::storage::git::paths;
+use radicle::storage::{ReadRepository, ReadStorage};
 use radicle_surf::{Oid, Repository};
 
 use crate::axum_extra::Path;
@@ -109,6 +110,10 @@ async fn file_handler(
     State(profile): State<Arc<Profile>>,
 ) -> impl IntoResponse {
     let storage = &profile.storage;
+    // Private repositories are never served.
+    if !storage.repository(project)?.is_public()? {
+        return Err(Error::NotFound);
+    }
     let repo = Repository::open(paths::repository(storage, &project))?;
     let mut response_headers = HeaderMap::new();
 
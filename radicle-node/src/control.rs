@@ -6,9 +6,9 @@ use std::os::unix::net::UnixListener;
 use std::os::unix::net::UnixStream;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::{io, net};
+use std::{io, net, thread};
 
-use radicle::node::Handle;
+use radicle::node::{Events, Handle};
 use serde_json as json;
 
 use crate::identity::Id;
@@ -107,6 +107,11 @@ fn command<H: Handle<Error = runtime::HandleError>>(
 
             json::to_writer(writer, &seeds)?;
         }
+        CommandName::Rejections => {
+            let rejections = handle.rejections()?;
+
+            json::to_writer(writer, &rejections)?;
+        }
         CommandName::TrackRepo => {
             let (rid, scope) = parse::args(cmd)?;
 
@@ -184,6 +189,13 @@ fn command<H: Handle<Error = runtime::HandleError>>(
                 return Err(CommandError::Runtime(e));
             }
         },
+        CommandName::Subscribe => {
+            let events = handle.subscribe()?;
+            let stream = stream.try_clone()?;
+
+            // Events are streamed from their own thread, so that we can keep processing commands.
+            thread::spawn(move || subscribe(events, stream));
+        }
         CommandName::Status => {
             CommandResult::ok().to_writer(writer).ok();
         }
@@ -211,6 +223,24 @@ fn fetch<W: Write, H: Handle<Error = runtime::HandleError>>(
     Ok(())
 }
 
+fn subscribe<E: std::error::Error>(events: Events<E>, stream: UnixStream) {
+    let mut writer = LineWriter::new(stream);
+
+    for event in events {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                log::error!(target: "control", "Event stream failed: {e}");
+                break;
+            }
+        };
+        if json::to_writer(&mut writer, &event).is_err() || writer.write_all(b"\n").is_err() {
+            break;
+        }
+    }
+    log::debug!(target: "control", "Event subscriber disconnected..");
+}
+
 mod parse {
     use super::*;
 
@@ -259,7 +289,7 @@ mod tests {
     use super::*;
     use crate::identity::Id;
     use crate::node::Handle;
-    use crate::node::{Node, NodeId};
+    use crate::node::{Event, Node, NodeId};
     use crate::service::tracking::Scope;
     use crate::test;
 
@@ -333,4 +363,41 @@ mod tests {
         assert!(handle.untrack_node(peer).unwrap());
         assert!(!handle.untrack_node(peer).unwrap());
     }
+
+    #[test]
+    fn test_subscribe() {
+        let tmp = tempfile::tempdir().unwrap();
+        let socket = tmp.path().join("node.sock");
+        let proj = test::arbitrary::gen::<Id>(1);
+        let peer = test::arbitrary::gen::<NodeId>(1);
+        let listener = UnixListener::bind(&socket).unwrap();
+        let handle = Node::new(&socket);
+        let events = vec![
+            Event::PeerConnected { nid: peer },
+            Event::RefsFetched {
+                remote: peer,
+                rid: proj,
+                updated: vec![],
+            },
+        ];
+
+        thread::spawn({
+            let handle = crate::test::handle::Handle {
+                events: events.clone(),
+                ..crate::test::handle::Handle::default()
+            };
+
+            move || crate::control::listen(listener, handle)
+        });
+
+        // Wait for node to be online.
+        while !handle.is_running() {}
+
+        let received = handle
+            .subscribe()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(received, events);
+    }
 }
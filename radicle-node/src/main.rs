@@ -21,6 +21,7 @@ Usage
 
 Options
 
+    --allow-rollbacks                Accept signed refs updates that roll back a remote
     --connect          <peer>        Connect to the given peer address on start
     --external-address <address>     Publicly accessible address (default 0.0.0.0:8776)
     --git-daemon       <address>     Address to bind git-daemon to (default 0.0.0.0:9418)
@@ -29,10 +30,13 @@ Options
     --help                           Print help
     --listen           <address>     Address to listen on
 
+Options take precedence over the `node` settings of the profile configuration.
+
 "#;
 
 #[derive(Debug)]
 struct Options {
+    allow_rollbacks: bool,
     connect: Vec<(NodeId, Address)>,
     external_addresses: Vec<Address>,
     daemon: Option<net::SocketAddr>,
@@ -43,20 +47,35 @@ struct Options {
 }
 
 impl Options {
-    fn from_env() -> Result<Self, anyhow::Error> {
+    fn from_env(config: profile::config::Node) -> Result<Self, anyhow::Error> {
         use lexopt::prelude::*;
 
         let mut parser = lexopt::Parser::from_env();
         let mut connect = Vec::new();
         let mut external_addresses = Vec::new();
-        let mut limits = service::config::Limits::default();
+        let mut limits = service::config::Limits {
+            repo_quota: config.limits.repo_quota,
+            remote_quota: config.limits.remote_quota,
+            ..service::config::Limits::default()
+        };
         let mut listen = Vec::new();
-        let mut daemon = None;
-        let mut tracking_policy = Policy::default();
-        let mut tracking_scope = Scope::default();
+        let mut daemon = config.git_daemon;
+        let mut tracking_policy = config.tracking_policy;
+        let mut tracking_scope = config.tracking_scope;
+        let mut allow_rollbacks = config.allow_rollbacks;
+
+        if let Some(size) = config.limits.routing_max_size {
+            limits.routing_max_size = size;
+        }
+        if let Some(secs) = config.limits.routing_max_age {
+            limits.routing_max_age = LocalDuration::from_secs(secs);
+        }
 
         while let Some(arg) = parser.next()? {
             match arg {
+                Long("allow-rollbacks") => {
+                    allow_rollbacks = true;
+                }
                 Long("connect") => {
                     let peer: PeerAddr<NodeId, Address> = parser.value()?.parse()?;
                     connect.push((peer.id, peer.addr.clone()));
@@ -90,6 +109,24 @@ impl Options {
                 Long("limit-routing-max-size") => {
                     limits.routing_max_size = parser.value()?.parse()?;
                 }
+                Long("limit-repo-max-bytes") => {
+                    limits.repo_quota.max_bytes = Some(parser.value()?.parse()?);
+                }
+                Long("limit-repo-max-refs") => {
+                    limits.repo_quota.max_refs = Some(parser.value()?.parse()?);
+                }
+                Long("limit-repo-max-object-size") => {
+                    limits.repo_quota.max_object_size = Some(parser.value()?.parse()?);
+                }
+                Long("limit-remote-max-bytes") => {
+                    limits.remote_quota.max_bytes = Some(parser.value()?.parse()?);
+                }
+                Long("limit-remote-max-refs") => {
+                    limits.remote_quota.max_refs = Some(parser.value()?.parse()?);
+                }
+                Long("limit-remote-max-object-size") => {
+                    limits.remote_quota.max_object_size = Some(parser.value()?.parse()?);
+                }
                 Long("listen") => {
                     let addr = parser.value()?.parse()?;
                     listen.push(addr);
@@ -102,6 +139,12 @@ impl Options {
             }
         }
 
+        if connect.is_empty() {
+            connect = config.connect.into_iter().map(Into::into).collect();
+        }
+        if listen.is_empty() {
+            listen = config.listen;
+        }
         if external_addresses.len() > service::ADDRESS_LIMIT {
             anyhow::bail!(
                 "external address limit ({}) exceeded",
@@ -110,6 +153,7 @@ impl Options {
         }
 
         Ok(Self {
+            allow_rollbacks,
             connect,
             daemon,
             external_addresses,
@@ -127,8 +171,8 @@ fn execute() -> anyhow::Result<()> {
     log::info!(target: "node", "Starting node..");
     log::info!(target: "node", "Version {} ({})", env!("CARGO_PKG_VERSION"), env!("GIT_HEAD"));
 
-    let options = Options::from_env()?;
     let home = profile::home()?;
+    let options = Options::from_env(profile::Config::load(&home.config())?.node)?;
 
     log::info!(target: "node", "Unlocking node keystore..");
 
@@ -145,6 +189,7 @@ fn execute() -> anyhow::Result<()> {
         limits: options.limits,
         policy: options.tracking_policy,
         scope: options.tracking_scope,
+        allow_rollbacks: options.allow_rollbacks,
         ..service::Config::default()
     };
     let proxy = net::SocketAddr::new(net::Ipv4Addr::LOCALHOST.into(), 9050);
@@ -17,6 +17,8 @@ use radicle::git;
 use radicle::node::Handle as _;
 use radicle::node::{ADDRESS_DB_FILE, ROUTING_DB_FILE, TRACKING_DB_FILE};
 use radicle::profile::Home;
+use radicle::storage::git::maintenance;
+use radicle::storage::Namespaces;
 use radicle::Storage;
 
 use crate::address;
@@ -32,6 +34,9 @@ use crate::{service, LocalTime};
 pub use handle::Error as HandleError;
 pub use handle::Handle;
 
+/// How often storage maintenance is run.
+pub const MAINTENANCE_INTERVAL: time::Duration = time::Duration::from_secs(60 * 60 * 24);
+
 /// A client error.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -77,7 +82,8 @@ impl<T> Default for Emitter<T> {
 }
 
 impl<T: Clone> Emitter<T> {
-    /// Emit event to subscribers and drop those who can't receive it.
+    /// Emit event to subscribers and drop those who can't receive it, either because they
+    /// are gone, or because their channel is full.
     pub(crate) fn emit(&self, event: T) {
         self.subscribers
             .lock()
@@ -107,6 +113,8 @@ pub struct Runtime<G: Signer + Ecdh> {
     pub pool: worker::Pool,
     pub local_addrs: Vec<net::SocketAddr>,
     pub signals: chan::Receiver<()>,
+    /// Read-only tracking configuration, used by storage maintenance.
+    pub tracking: tracking::Config,
 }
 
 impl<G: Signer + Ecdh + 'static> Runtime<G> {
@@ -142,12 +150,20 @@ impl<G: Signer + Ecdh + 'static> Runtime<G> {
         let routing = routing::Table::open(routing_db)?;
 
         log::info!(target: "node", "Opening tracking policy table {}..", tracking_db.display());
-        let tracking = tracking::Store::open(tracking_db)?;
+        let tracking = tracking::Store::open(&tracking_db)?;
         let tracking = tracking::Config::new(config.policy, config.scope, tracking);
+        let tracking_reader = tracking::Config::new(
+            config.policy,
+            config.scope,
+            tracking::Store::reader(&tracking_db)?,
+        );
 
         log::info!(target: "node", "Default tracking policy set to '{}'", &config.policy);
         log::info!(target: "node", "Initializing service ({:?})..", network);
         let emitter: Emitter<Event> = Default::default();
+        let repo_quota = config.limits.repo_quota.clone();
+        let remote_quota = config.limits.remote_quota.clone();
+        let allow_rollbacks = config.allow_rollbacks;
         let service = service::Service::new(
             config,
             clock,
@@ -195,6 +211,9 @@ impl<G: Signer + Ecdh + 'static> Runtime<G> {
                 storage: storage.clone(),
                 daemon,
                 atomic,
+                repo_quota,
+                remote_quota,
+                allow_rollbacks,
             },
         );
         let control = match UnixListener::bind(home.socket()) {
@@ -218,6 +237,7 @@ impl<G: Signer + Ecdh + 'static> Runtime<G> {
             pool,
             signals,
             local_addrs,
+            tracking: tracking_reader,
         })
     }
 
@@ -256,6 +276,18 @@ impl<G: Signer + Ecdh + 'static> Runtime<G> {
             }
         })?;
 
+        // Shared storage holds the namespaces of other local profiles, which we don't track.
+        let shared = home.is_storage_shared().unwrap_or_else(|e| {
+            log::error!(target: "node", "Failed to check whether storage is shared: {e}");
+            true
+        });
+        thread::Builder::new().name(self.id.to_human()).spawn({
+            let id = self.id;
+            let storage = self.storage.clone();
+            let tracking = self.tracking;
+            move || maintain(id, storage, tracking, shared)
+        })?;
+
         self.pool.run().unwrap();
         self.reactor.join().unwrap();
 
@@ -273,6 +305,43 @@ impl<G: Signer + Ecdh + 'static> Runtime<G> {
     }
 }
 
+/// Run storage maintenance periodically, keeping only the remotes we track. If the storage is
+/// shared with other profiles, no remote is removed.
+fn maintain(id: NodeId, storage: Storage, tracking: tracking::Config, shared: bool) {
+    if shared {
+        log::info!(
+            target: "node",
+            "Storage is shared with other profiles: remotes won't be removed"
+        );
+    }
+    let keep = |rid: &radicle::identity::Id, remote: &NodeId| {
+        if shared || remote == &id {
+            return true;
+        }
+        match tracking.namespaces_for(&storage, rid) {
+            Ok(Namespaces::All) => true,
+            Ok(Namespaces::One(pk)) => &pk == remote,
+            Ok(Namespaces::Many(pks)) => pks.contains(remote),
+            // Don't remove anything if we aren't sure.
+            Err(_) => true,
+        }
+    };
+
+    loop {
+        thread::sleep(MAINTENANCE_INTERVAL);
+
+        log::info!(target: "node", "Running storage maintenance..");
+        match maintenance::run(&storage, &keep, &maintenance::Options::default()) {
+            Ok(report) => log::info!(
+                target: "node",
+                "Storage maintenance completed: {} repositories, {} remote(s) removed, {} bytes reclaimed",
+                report.repositories, report.remotes, report.reclaimed
+            ),
+            Err(e) => log::error!(target: "node", "Storage maintenance failed: {e}"),
+        }
+    }
+}
+
 pub mod daemon {
     use std::path::Path;
     use std::process::{Child, Command, Stdio};
@@ -302,6 +371,8 @@ pub mod daemon {
             .envs(radicle::git::env::GIT_DEFAULT_CONFIG)
             .env("GIT_PROTOCOL", "version=2")
             .current_dir(storage)
+            // Let peers make partial fetches. The setting is passed on to `git-upload-pack`.
+            .args(["-c", "uploadpack.allowFilter=true"])
             .arg("daemon")
             // Make all git directories available.
             .arg("--export-all")
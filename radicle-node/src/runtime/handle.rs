@@ -6,7 +6,7 @@ use std::sync::Arc;
 
 use crossbeam_channel as chan;
 use cyphernet::Ecdh;
-use radicle::node::Seeds;
+use radicle::node::{Events, Rejection, Seeds};
 use thiserror::Error;
 
 use crate::crypto::Signer;
@@ -22,6 +22,10 @@ use crate::service::{NodeId, Sessions};
 use crate::wire;
 use crate::worker::TaskResult;
 
+/// Maximum number of events queued for a subscriber. Subscribers that fall further behind
+/// are dropped, so that they can't make the node buffer events indefinitely.
+pub const MAX_PENDING_EVENTS: usize = 1024;
+
 /// An error resulting from a handle method.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -154,6 +158,12 @@ impl<G: Signer + Ecdh + 'static> radicle::node::Handle for Handle<G> {
         receiver.recv().map_err(Error::from)
     }
 
+    fn rejections(&mut self) -> Result<Vec<Rejection>, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::Rejections(sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
     fn track_node(&mut self, id: NodeId, alias: Option<String>) -> Result<bool, Error> {
         let (sender, receiver) = chan::bounded(1);
         self.command(service::Command::TrackNode(id, alias, sender))?;
@@ -207,6 +217,13 @@ impl<G: Signer + Ecdh + 'static> radicle::node::Handle for Handle<G> {
         Ok(sessions)
     }
 
+    fn subscribe(&self) -> Result<Events<Error>, Error> {
+        let (sender, receiver) = chan::bounded(MAX_PENDING_EVENTS);
+        self.emitter.subscribers.lock().unwrap().push(sender);
+
+        Ok(Box::new(receiver.into_iter().map(Ok)))
+    }
+
     fn shutdown(self) -> Result<(), Error> {
         // If the current value is `false`, set it to `true`, otherwise error.
         if self
@@ -9,7 +9,7 @@ pub mod session;
 pub mod tracking;
 
 use std::collections::hash_map::Entry;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 use std::{fmt, net, str};
@@ -27,12 +27,12 @@ use crate::identity::IdentityError;
 use crate::identity::{Doc, Id};
 use crate::node;
 use crate::node::routing;
-use crate::node::{Address, Features, FetchResult, Seed, Seeds};
+use crate::node::{Address, Features, FetchResult, Rejection, Seed, Seeds};
 use crate::prelude::*;
 use crate::runtime::Emitter;
 use crate::service::message::{Announcement, AnnouncementMessage, Ping};
 use crate::service::message::{NodeAnnouncement, RefsAnnouncement};
-use crate::service::reactor::FetchDirection;
+use crate::service::reactor::{FetchDirection, FetchPhase};
 use crate::service::session::GossipState;
 use crate::service::tracking::Scope;
 use crate::storage;
@@ -41,7 +41,7 @@ use crate::storage::{ReadRepository, RefUpdate};
 use crate::worker::FetchError;
 use crate::Link;
 
-pub use crate::node::NodeId;
+pub use crate::node::{Event, NodeId};
 pub use crate::service::config::{Config, Network};
 pub use crate::service::message::{Message, ZeroBytes};
 pub use crate::service::reactor::Fetch;
@@ -76,6 +76,8 @@ pub const SUBSCRIBE_BACKLOG_DELTA: LocalDuration = LocalDuration::from_mins(60);
 pub const MIN_RECONNECTION_DELTA: LocalDuration = LocalDuration::from_secs(3);
 /// Maximum amount of time to wait before reconnecting to a peer.
 pub const MAX_RECONNECTION_DELTA: LocalDuration = LocalDuration::from_mins(60);
+/// Maximum number of rejected fetches to remember. See [`Command::Rejections`].
+pub const MAX_REJECTIONS: usize = 32;
 
 /// Maximum external address limit imposed by message size limits.
 pub use message::ADDRESS_LIMIT;
@@ -84,22 +86,16 @@ pub use message::INVENTORY_LIMIT;
 /// Maximum number of project git references imposed by message size limits.
 pub use message::REF_REMOTE_LIMIT;
 
-/// A service event.
-#[derive(Debug, Clone)]
-pub enum Event {
-    RefsFetched {
-        remote: NodeId,
-        rid: Id,
-        updated: Vec<RefUpdate>,
-    },
-}
-
 /// General service error.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error(transparent)]
     Storage(#[from] storage::Error),
     #[error(transparent)]
+    Identity(#[from] IdentityError),
+    #[error(transparent)]
+    Refs(#[from] storage::refs::Error),
+    #[error(transparent)]
     Routing(#[from] routing::Error),
     #[error(transparent)]
     Tracking(#[from] tracking::Error),
@@ -124,6 +120,8 @@ pub enum Command {
     Seeds(Id, chan::Sender<Seeds>),
     /// Fetch the given repository from the network.
     Fetch(Id, NodeId, chan::Sender<FetchResult>),
+    /// Get the latest fetches rejected for exceeding a storage quota.
+    Rejections(chan::Sender<Vec<Rejection>>),
     /// Track the given repository.
     TrackRepo(Id, Scope, chan::Sender<bool>),
     /// Untrack the given repository.
@@ -145,6 +143,7 @@ impl fmt::Debug for Command {
             Self::Connect(id, addr) => write!(f, "Connect({id}, {addr})"),
             Self::Seeds(id, _) => write!(f, "Seeds({id})"),
             Self::Fetch(id, node, _) => write!(f, "Fetch({id}, {node})"),
+            Self::Rejections(_) => write!(f, "Rejections(..)"),
             Self::TrackRepo(id, scope, _) => write!(f, "TrackRepo({id}, {scope})"),
             Self::UntrackRepo(id, _) => write!(f, "UntrackRepo({id})"),
             Self::TrackNode(id, _, _) => write!(f, "TrackNode({id})"),
@@ -191,6 +190,8 @@ pub struct Service<R, A, S, G> {
     rng: Rng,
     /// Fetch requests initiated by user, which are waiting for results.
     fetch_reqs: HashMap<(Id, NodeId), chan::Sender<FetchResult>>,
+    /// Latest fetches rejected for exceeding a storage quota, oldest first.
+    rejections: VecDeque<Rejection>,
     /// Current tracked repository bloom filter.
     filter: Filter,
     /// Last time the service was idle.
@@ -255,6 +256,7 @@ where
             reactor: Reactor::default(),
             sessions,
             fetch_reqs: HashMap::new(),
+            rejections: VecDeque::new(),
             filter: Filter::empty(),
             last_idle: LocalTime::default(),
             last_sync: LocalTime::default(),
@@ -429,7 +431,7 @@ where
         if now - self.last_announce >= ANNOUNCE_INTERVAL {
             if let Err(err) = self
                 .storage
-                .inventory()
+                .public_inventory()
                 .and_then(|i| self.announce_inventory(i))
             {
                 error!(target: "service", "Error announcing inventory: {}", err);
@@ -471,6 +473,9 @@ where
                 self.fetch_reqs.insert((rid, seed), resp);
                 self.fetch(rid, &seed);
             }
+            Command::Rejections(resp) => {
+                resp.send(self.rejections.iter().cloned().collect()).ok();
+            }
             Command::TrackRepo(rid, scope, resp) => {
                 // Update our tracking policy.
                 let tracked = self
@@ -512,7 +517,7 @@ where
             Command::AnnounceInventory => {
                 if let Err(err) = self
                     .storage
-                    .inventory()
+                    .public_inventory()
                     .and_then(|i| self.announce_inventory(i))
                 {
                     error!("Error announcing inventory: {}", err);
@@ -531,6 +536,20 @@ where
     }
 
     pub fn fetch(&mut self, rid: Id, from: &NodeId) {
+        let phase = self.initial_phase(&rid);
+
+        self.fetch_phase(rid, from, None, phase)
+    }
+
+    /// Fetch a phase of a repository. If no namespaces are given, they are taken from the
+    /// tracking policy of the repository.
+    fn fetch_phase(
+        &mut self,
+        rid: Id,
+        from: &NodeId,
+        namespaces: Option<Namespaces>,
+        phase: FetchPhase,
+    ) {
         let Some(session) = self.sessions.get_mut(from) else {
             error!(target: "service", "Session {from} does not exist; cannot initiate fetch");
             return;
@@ -543,10 +562,14 @@ where
             session::FetchResult::Ready(fetch) => {
                 debug!(target: "service", "Fetch initiated for {rid} with {seed}..");
 
-                match self.tracking.namespaces_for(&self.storage, &rid) {
+                let namespaces = match namespaces {
+                    Some(ns) => Ok(ns),
+                    None => self.tracking.namespaces_for(&self.storage, &rid),
+                };
+                match namespaces {
                     Ok(ns) => {
                         self.reactor.write(session, fetch);
-                        session.to_requesting(rid, ns);
+                        session.to_requesting(rid, ns, phase);
                     }
                     Err(err) => {
                         error!(target: "service", "Error getting namespaces for {rid}: {err}");
@@ -580,12 +603,72 @@ where
         }
     }
 
+    /// Get the phase a fetch of the given repository starts with.
+    fn initial_phase(&self, rid: &Id) -> FetchPhase {
+        match self.storage.repository(*rid).and_then(|r| r.fetch_limits()) {
+            Ok(limits) if !limits.is_empty() => FetchPhase::Metadata,
+            _ => FetchPhase::Full,
+        }
+    }
+
+    /// Get the phase that follows a completed fetch phase, along with the namespaces to
+    /// fetch, if the fetch isn't complete.
+    fn next_phase(
+        &self,
+        rid: &Id,
+        namespaces: &Namespaces,
+        phase: &FetchPhase,
+    ) -> Option<(Namespaces, FetchPhase)> {
+        match phase {
+            FetchPhase::Full | FetchPhase::Limited(_) => None,
+            FetchPhase::Tips => {
+                let limits = self.storage.repository(*rid).ok()?.fetch_limits().ok()?;
+
+                Some((namespaces.clone(), FetchPhase::Limited(limits)))
+            }
+            FetchPhase::Metadata => {
+                let repo = self.storage.repository(*rid).ok()?;
+
+                if let Namespaces::All = namespaces {
+                    // Now that we have the signed refs of every remote, we know which
+                    // namespaces to fetch the rest of the metadata of.
+                    let remotes = Namespaces::remotes(&repo).ok()??;
+
+                    return Some((remotes, FetchPhase::Metadata));
+                }
+                let limits = repo.fetch_limits().ok()?;
+
+                if limits.filter.is_some() {
+                    // Filters omit the blobs of the tips, which are needed for checkouts.
+                    return Some((namespaces.clone(), FetchPhase::Tips));
+                }
+                Some((namespaces.clone(), FetchPhase::Limited(limits)))
+            }
+        }
+    }
+
     pub fn fetched(&mut self, fetch: Fetch, result: Result<Vec<RefUpdate>, FetchError>) {
         let remote = fetch.remote;
         let rid = fetch.rid;
 
         match fetch.direction {
-            FetchDirection::Initiator { namespaces } => {
+            FetchDirection::Initiator {
+                namespaces, phase, ..
+            } => {
+                if result.is_ok() {
+                    if let Some((namespaces, phase)) = self.next_phase(&rid, &namespaces, &phase) {
+                        debug!(target: "service", "Continuing fetch of {rid} from {remote}..");
+
+                        self.switch_to_gossip(remote);
+                        self.fetch_phase(rid, &remote, Some(namespaces), phase);
+
+                        return;
+                    }
+                }
+                // Only the outcome of the latest fetch of a repository from a node is kept.
+                self.rejections
+                    .retain(|r| !(r.rid == rid && r.from == remote));
+
                 let result = match result {
                     Ok(updated) => {
                         log::debug!(target: "service", "Fetched {rid} from {remote}");
@@ -602,6 +685,23 @@ where
                         let reason = err.to_string();
                         error!(target: "service", "Fetch failed for {rid} from {remote}: {reason}");
 
+                        self.emitter.emit(Event::FetchFailed {
+                            remote,
+                            rid,
+                            reason: reason.clone(),
+                        });
+
+                        if err.is_quota_exceeded() {
+                            if self.rejections.len() >= MAX_REJECTIONS {
+                                self.rejections.pop_front();
+                            }
+                            self.rejections.push_back(Rejection {
+                                rid,
+                                from: remote,
+                                reason: reason.clone(),
+                            });
+                        }
+
                         // For now, we only disconnect the remote in case of timeout. In the future,
                         // there may be other reasons to disconnect.
                         if err.is_timeout() {
@@ -669,6 +769,8 @@ where
     pub fn connected(&mut self, remote: NodeId, link: Link) {
         info!(target: "service", "Connected to {} ({:?})", remote, link);
 
+        self.emitter.emit(Event::PeerConnected { nid: remote });
+
         let msgs = self.initial(link);
 
         if link.is_outbound() {
@@ -711,6 +813,11 @@ where
         };
         let link = session.link;
 
+        self.emitter.emit(Event::PeerDisconnected {
+            nid: remote,
+            reason: reason.to_string(),
+        });
+
         // If the peer disconnected while we were waiting for a [`Message::FetchOk`],
         // return a failure to any potential fetcher.
         if let Some((requested, _)) = session.requesting() {
@@ -803,6 +910,11 @@ where
                     debug!(target: "service", "Ignoring stale inventory announcement from {announcer} (t={})", self.time());
                     return Ok(false);
                 }
+                self.emitter.emit(Event::InventoryAnnounced {
+                    nid: *announcer,
+                    inventory: message.inventory.to_vec(),
+                    timestamp,
+                });
 
                 match self.sync_routing(&message.inventory, *announcer, message.timestamp) {
                     Ok(updated) => {
@@ -872,6 +984,11 @@ where
                     debug!(target: "service", "Ignoring stale refs announcement from {announcer} (time={timestamp})");
                     return Ok(false);
                 }
+                self.emitter.emit(Event::RefsAnnounced {
+                    nid: *announcer,
+                    rid: message.rid,
+                    timestamp,
+                });
 
                 // TODO: Buffer/throttle fetches.
                 let repo_entry = self.tracking.repo_policy(&message.rid).expect(
@@ -933,6 +1050,11 @@ where
                         return Ok(false);
                     }
                 };
+                self.emitter.emit(Event::NodeAnnounced {
+                    nid: *announcer,
+                    alias: alias.to_owned(),
+                    timestamp,
+                });
 
                 // If this node isn't a seed, we're not interested in adding it
                 // to our address book, but other nodes may be, so we relay the message anyway.
@@ -1134,7 +1256,7 @@ where
             }
             (session::State::Connected { protocol, .. }, Message::FetchOk { rid }) => {
                 let session::Protocol::Gossip {
-                    state: GossipState::Requesting { rid: requested, namespaces }
+                    state: GossipState::Requesting { rid: requested, namespaces, phase }
                 } = protocol else {
                     // As long as we disconnect peers who don't respond to our fetch requests within
                     // the alloted time, this shouldn't happen by mistake.
@@ -1153,12 +1275,25 @@ where
                     return Err(session::Error::Misbehavior);
                 }
                 let namespaces = namespaces.clone();
+                let phase = phase.clone();
+                let large_objects = self
+                    .tracking
+                    .large_objects_policy(&rid)
+                    .expect("Service::handle_message: error accessing large object policy")
+                    == tracking::Policy::Track;
 
                 debug!(target: "service", "Fetch accepted for {rid} from {remote}..");
 
                 // Instruct the transport to handover the socket to the worker.
-                self.reactor
-                    .fetch(peer, rid, FetchDirection::Initiator { namespaces });
+                self.reactor.fetch(
+                    peer,
+                    rid,
+                    FetchDirection::Initiator {
+                        namespaces,
+                        phase,
+                        large_objects,
+                    },
+                );
             }
             (session::State::Attempted { .. } | session::State::Initial, msg) => {
                 error!(target: "service", "Received {:?} from connecting peer {}", msg, peer.id);
@@ -1233,9 +1368,16 @@ where
     }
 
     /// Announce local refs for given id.
-    fn announce_refs(&mut self, rid: Id, namespaces: &Namespaces) -> Result<(), storage::Error> {
+    fn announce_refs(&mut self, rid: Id, namespaces: &Namespaces) -> Result<(), Error> {
         let repo = self.storage.repository(rid)?;
-        let peers = self.sessions.connected().map(|(_, p)| p);
+        let (_, doc) = repo.identity_doc()?;
+        let doc = doc.verified().map_err(IdentityError::from)?;
+        // Private repositories are only announced to the nodes they are visible to.
+        let peers = self
+            .sessions
+            .connected()
+            .map(|(_, p)| p)
+            .filter(|p| doc.is_visible_to(&p.id));
         let timestamp = self.time();
         let mut refs = BoundedVec::<_, REF_REMOTE_LIMIT>::new();
 
@@ -1305,7 +1447,7 @@ where
                 if !updated.is_empty() {
                     if let Err(e) = self
                         .storage
-                        .inventory()
+                        .public_inventory()
                         .and_then(|i| self.announce_inventory(i))
                     {
                         error!(target: "service", "Failed to announce inventory: {e}");
@@ -1839,7 +1981,7 @@ mod gossip {
         filter: Filter,
         config: &Config,
     ) -> Vec<Message> {
-        let inventory = match storage.inventory() {
+        let inventory = match storage.public_inventory() {
             Ok(i) => i,
             Err(e) => {
                 error!("Error getting local inventory for handshake: {}", e);
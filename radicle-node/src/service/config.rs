@@ -2,6 +2,8 @@ use localtime::LocalDuration;
 
 use radicle::node::Address;
 
+pub use radicle::profile::config::Quota;
+
 use crate::service::tracking::{Policy, Scope};
 use crate::service::NodeId;
 
@@ -20,6 +22,10 @@ pub struct Limits {
     pub routing_max_size: usize,
     /// How long to keep a routing table entry before being pruned.
     pub routing_max_age: LocalDuration,
+    /// Storage quota of each repository.
+    pub repo_quota: Quota,
+    /// Storage quota of each remote of a repository.
+    pub remote_quota: Quota,
 }
 
 impl Default for Limits {
@@ -27,6 +33,8 @@ impl Default for Limits {
         Self {
             routing_max_size: 1000,
             routing_max_age: LocalDuration::from_mins(7 * 24 * 60),
+            repo_quota: Quota::default(),
+            remote_quota: Quota::default(),
         }
     }
 }
@@ -49,6 +57,8 @@ pub struct Config {
     pub policy: Policy,
     /// Default tracking scope.
     pub scope: Scope,
+    /// Whether to accept signed refs updates that roll back the refs of a remote.
+    pub allow_rollbacks: bool,
 }
 
 impl Default for Config {
@@ -61,6 +71,7 @@ impl Default for Config {
             limits: Limits::default(),
             policy: Policy::default(),
             scope: Scope::default(),
+            allow_rollbacks: false,
         }
     }
 }
@@ -5,7 +5,7 @@ use log::*;
 
 use crate::prelude::*;
 use crate::service::session::Session;
-use crate::storage::Namespaces;
+use crate::storage::{FetchLimits, Namespaces};
 
 use super::message::{Announcement, AnnouncementMessage};
 
@@ -42,7 +42,7 @@ impl Fetch {
 
     pub fn initiated(&self) -> Option<&Namespaces> {
         match &self.direction {
-            FetchDirection::Initiator { namespaces } => Some(namespaces),
+            FetchDirection::Initiator { namespaces, .. } => Some(namespaces),
             FetchDirection::Responder => None,
         }
     }
@@ -55,12 +55,35 @@ pub enum FetchDirection {
     Initiator {
         /// Namespaces to fetch.
         namespaces: Namespaces,
+        /// Phase of the fetch.
+        phase: FetchPhase,
+        /// Whether to fetch all large objects, or only the wanted ones.
+        large_objects: bool,
     },
     /// Server is responding to a fetch request by uploading the
     /// specified `refspecs` sent by the client.
     Responder,
 }
 
+/// Phase of an initiated fetch.
+///
+/// Repositories with [`FetchLimits`] are fetched in phases: first their identity, signed refs
+/// and collaborative objects are fetched in full, then their other refs are fetched with the
+/// limits applied. When the limits include a filter, the tips of these refs are fetched in
+/// full beforehand, so that they can be checked out.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum FetchPhase {
+    /// Fetch all refs. This is the only phase of repositories without limits.
+    #[default]
+    Full,
+    /// Fetch the identity, signed refs and collaborative objects, without limits.
+    Metadata,
+    /// Fetch the tips of all refs, with a depth of one and without filter.
+    Tips,
+    /// Fetch all refs, with the given limits.
+    Limited(FetchLimits),
+}
+
 impl FetchDirection {
     pub fn is_initiator(&self) -> bool {
         matches!(self, Self::Initiator { .. })
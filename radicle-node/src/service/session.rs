@@ -5,6 +5,7 @@ use radicle::storage::Namespaces;
 
 use crate::service::message;
 use crate::service::message::Message;
+use crate::service::reactor::FetchPhase;
 use crate::service::{Id, LocalTime, NodeId, Reactor, Rng};
 use crate::Link;
 
@@ -26,7 +27,11 @@ pub enum GossipState {
     #[default]
     Idle,
     /// Requesting a fetch for the given RID. Waiting for a [`Message::FetchOk`].
-    Requesting { rid: Id, namespaces: Namespaces },
+    Requesting {
+        rid: Id,
+        namespaces: Namespaces,
+        phase: FetchPhase,
+    },
 }
 
 /// Session protocol.
@@ -287,12 +292,16 @@ impl Session {
         }
     }
 
-    pub fn to_requesting(&mut self, rid: Id, namespaces: Namespaces) {
+    pub fn to_requesting(&mut self, rid: Id, namespaces: Namespaces, phase: FetchPhase) {
         let State::Connected { protocol, .. } = &mut self.state else {
             panic!("Session::to_requesting: cannot transition to 'requesting': session is not connected");
         };
         *protocol = Protocol::Gossip {
-            state: GossipState::Requesting { rid, namespaces },
+            state: GossipState::Requesting {
+                rid,
+                namespaces,
+                phase,
+            },
         };
     }
 
@@ -358,7 +367,10 @@ impl Session {
         if let State::Connected {
             protocol:
                 Protocol::Gossip {
-                    state: GossipState::Requesting { rid, namespaces },
+                    state:
+                        GossipState::Requesting {
+                            rid, namespaces, ..
+                        },
                 },
             ..
         } = &self.state
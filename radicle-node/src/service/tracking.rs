@@ -118,15 +118,18 @@ impl Config {
                         .filter_map(|node| (node.policy == Policy::Track).then_some(node.id))
                         .collect();
 
-                    let ns = if let Ok(repo) = storage.repository(*rid) {
-                        let delegates = repo
-                            .delegates()
-                            .map_err(|err| FailedDelegates { rid: *rid, err })?
-                            .map(PublicKey::from);
-                        trusted.extend(delegates);
-                        NonEmpty::from_vec(trusted).map(Namespaces::Many)
-                    } else {
-                        Some(Namespaces::All)
+                    // Nb. Repositories may exist without any remotes, eg. when they are
+                    // created ahead of their first fetch to set fetch limits.
+                    let ns = match storage.repository(*rid) {
+                        Ok(repo) if !repo.is_empty().unwrap_or(true) => {
+                            let delegates = repo
+                                .delegates()
+                                .map_err(|err| FailedDelegates { rid: *rid, err })?
+                                .map(PublicKey::from);
+                            trusted.extend(delegates);
+                            NonEmpty::from_vec(trusted).map(Namespaces::Many)
+                        }
+                        _ => Some(Namespaces::All),
                     };
 
                     ns.ok_or_else(|| {
@@ -16,7 +16,7 @@ use radicle::crypto::test::signer::MockSigner;
 use radicle::crypto::{KeyPair, Seed, Signer};
 use radicle::git;
 use radicle::git::refname;
-use radicle::identity::Id;
+use radicle::identity::{Id, Visibility};
 use radicle::node::routing::Store;
 use radicle::node::Handle as _;
 use radicle::profile::Home;
@@ -318,16 +318,27 @@ impl<G: cyphernet::Ecdh<Pk = NodeId> + Signer + Clone> Node<G> {
 
     /// Populate a storage instance with a project.
     pub fn project(&mut self, name: &str, description: &str) -> Id {
+        self.project_with_visibility(name, description, Visibility::default())
+    }
+
+    /// Populate a storage instance with a project of the given visibility.
+    pub fn project_with_visibility(
+        &mut self,
+        name: &str,
+        description: &str,
+        visibility: Visibility,
+    ) -> Id {
         transport::local::register(self.storage.clone());
 
         let tmp = tempfile::tempdir().unwrap();
         let (repo, _) = fixtures::repository(tmp.path());
 
-        let id = rad::init(
+        let id = rad::init_with_visibility(
             &repo,
             name,
             description,
             refname!("master"),
+            visibility,
             &self.signer,
             &self.storage,
         )
@@ -2,7 +2,7 @@ use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 
 use crate::identity::Id;
-use crate::node::{FetchResult, Seeds};
+use crate::node::{Event, Events, FetchResult, Rejection, Seeds};
 use crate::runtime::HandleError;
 use crate::service::NodeId;
 use crate::service::{self, tracking};
@@ -13,6 +13,7 @@ pub struct Handle {
     pub updates: Arc<Mutex<Vec<Id>>>,
     pub tracking_repos: HashSet<Id>,
     pub tracking_nodes: HashSet<NodeId>,
+    pub events: Vec<Event>,
 }
 
 impl radicle::node::Handle for Handle {
@@ -35,6 +36,10 @@ impl radicle::node::Handle for Handle {
         Ok(FetchResult::from(Ok::<Vec<RefUpdate>, Self::Error>(vec![])))
     }
 
+    fn rejections(&mut self) -> Result<Vec<Rejection>, Self::Error> {
+        Ok(vec![])
+    }
+
     fn track_repo(&mut self, id: Id, _scope: tracking::Scope) -> Result<bool, Self::Error> {
         Ok(self.tracking_repos.insert(id))
     }
@@ -69,6 +74,10 @@ impl radicle::node::Handle for Handle {
         unimplemented!();
     }
 
+    fn subscribe(&self) -> Result<Events<Self::Error>, Self::Error> {
+        Ok(Box::new(self.events.clone().into_iter().map(Ok)))
+    }
+
     fn shutdown(self) -> Result<(), Self::Error> {
         Ok(())
     }
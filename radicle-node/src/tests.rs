@@ -17,13 +17,14 @@ use crate::prelude::{LocalDuration, Timestamp};
 use crate::service::config::*;
 use crate::service::filter::Filter;
 use crate::service::message::*;
-use crate::service::reactor::FetchDirection;
 use crate::service::reactor::Io;
+use crate::service::reactor::{FetchDirection, FetchPhase};
 use crate::service::ServiceState as _;
 use crate::service::*;
 use crate::storage::git::transport::{local, remote};
 use crate::storage::git::Storage;
-use crate::storage::{Namespaces, ReadStorage};
+use crate::storage::memory::MemoryStorage;
+use crate::storage::{FetchLimits, Namespaces, ReadStorage};
 use crate::test::arbitrary;
 use crate::test::assert_matches;
 use crate::test::fixtures;
@@ -228,7 +229,7 @@ fn test_inventory_sync() {
     let mut alice = Peer::config(
         "alice",
         [7, 7, 7, 7],
-        Storage::open(tmp.path().join("alice")).unwrap(),
+        MemoryStorage::new(),
         peer::Config::default(),
     );
     let bob_signer = MockSigner::default();
@@ -270,6 +271,7 @@ fn test_inventory_pruning() {
             limits: Limits {
                 routing_max_size: 0,
                 routing_max_age: LocalDuration::from_secs(0),
+                ..Limits::default()
             },
             peer_projects: vec![10; 5],
             wait_time: LocalDuration::from_mins(7 * 24 * 60) + LocalDuration::from_secs(1),
@@ -280,6 +282,7 @@ fn test_inventory_pruning() {
             limits: Limits {
                 routing_max_size: 0,
                 routing_max_age: LocalDuration::from_mins(7 * 24 * 60),
+                ..Limits::default()
             },
             peer_projects: vec![10; 5],
             wait_time: LocalDuration::from_mins(7 * 24 * 60) + LocalDuration::from_secs(1),
@@ -290,6 +293,7 @@ fn test_inventory_pruning() {
             limits: Limits {
                 routing_max_size: 50,
                 routing_max_age: LocalDuration::from_mins(0),
+                ..Limits::default()
             },
             peer_projects: vec![10; 5],
             wait_time: LocalDuration::from_mins(7 * 24 * 60) + LocalDuration::from_secs(1),
@@ -300,6 +304,7 @@ fn test_inventory_pruning() {
             limits: Limits {
                 routing_max_size: 25,
                 routing_max_age: LocalDuration::from_mins(7 * 24 * 60),
+                ..Limits::default()
             },
             peer_projects: vec![10; 5],
             wait_time: LocalDuration::from_mins(7 * 24 * 60) + LocalDuration::from_secs(1),
@@ -606,13 +611,13 @@ fn test_refs_announcement_relay() {
     let mut alice = Peer::config(
         "alice",
         [7, 7, 7, 7],
-        Storage::open(tmp.path().join("alice")).unwrap(),
+        MemoryStorage::new(),
         peer::Config::default(),
     );
     let eve = Peer::config(
         "eve",
         [8, 8, 8, 8],
-        Storage::open(tmp.path().join("eve")).unwrap(),
+        MemoryStorage::new(),
         peer::Config::default(),
     );
 
@@ -678,7 +683,7 @@ fn test_refs_announcement_fetch_trusted_no_inventory() {
     let mut alice = Peer::config(
         "alice",
         [7, 7, 7, 7],
-        Storage::open(tmp.path().join("alice")).unwrap(),
+        MemoryStorage::new(),
         peer::Config::default(),
     );
     let bob = {
@@ -834,6 +839,8 @@ fn test_gossip_during_fetch() {
             rid,
             direction: FetchDirection::Initiator {
                 namespaces: Namespaces::All,
+                phase: FetchPhase::Full,
+                large_objects: false,
             },
             remote: bob.id,
         },
@@ -1257,8 +1264,10 @@ fn test_push_and_pull() {
         .unwrap()
         .is_some());
     assert_matches!(
-        bob_events.recv(),
-        Ok(service::Event::RefsFetched { remote, .. })
+        bob_events
+            .try_iter()
+            .find(|e| matches!(e, service::Event::RefsFetched { .. })),
+        Some(service::Event::RefsFetched { remote, .. })
         if remote == eve.node_id(),
         "Bob fetched from Eve"
     );
@@ -1394,6 +1403,8 @@ fn test_queued_fetch() {
             rid,
             direction: FetchDirection::Initiator {
                 namespaces: Namespaces::All,
+                phase: FetchPhase::Full,
+                large_objects: false,
             },
             remote: bob.id,
         },
@@ -1420,6 +1431,8 @@ fn test_queued_fetch() {
             rid: rid2,
             direction: FetchDirection::Initiator {
                 namespaces: Namespaces::All,
+                phase: FetchPhase::Full,
+                large_objects: false,
             },
             remote: bob.id,
         },
@@ -1437,3 +1450,78 @@ fn test_queued_fetch() {
     let last_io = alice.outbox().last().unwrap();
     assert_matches!(last_io, Io::Fetch(fetch) if fetch.rid == rid3);
 }
+
+#[test]
+fn test_fetch_phases() {
+    let mut storage = arbitrary::nonempty_storage(2);
+    let mut repo_keys = storage.inventory.keys();
+    let rid = *repo_keys.next().unwrap();
+    let rid2 = *repo_keys.next().unwrap();
+    let limits = FetchLimits {
+        depth: None,
+        filter: Some(crate::storage::Filter::BlobNone),
+    };
+    let limits2 = FetchLimits {
+        depth: std::num::NonZeroU32::new(1),
+        filter: None,
+    };
+    storage.limits.insert(rid, limits.clone());
+    storage.limits.insert(rid2, limits2.clone());
+
+    let mut alice = Peer::with_storage("alice", [7, 7, 7, 7], storage);
+    let bob = Peer::new("bob", [8, 8, 8, 8]);
+    let (send, recv) = chan::bounded::<node::FetchResult>(1);
+    let fetched = |rid, phase| Fetch {
+        rid,
+        direction: FetchDirection::Initiator {
+            namespaces: Namespaces::One(bob.id),
+            phase,
+            large_objects: false,
+        },
+        remote: bob.id,
+    };
+
+    alice.connect_to(&bob);
+    alice.command(Command::Fetch(rid, bob.id, send));
+
+    // Repositories with a filter are fetched in three phases.
+    for phase in [
+        FetchPhase::Metadata,
+        FetchPhase::Tips,
+        FetchPhase::Limited(limits),
+    ] {
+        assert_eq!(alice.messages(bob.id).last(), Some(Message::Fetch { rid }));
+        alice.receive(bob.id(), Message::FetchOk { rid });
+        assert_matches!(
+            alice.outbox().last(),
+            Some(Io::Fetch(Fetch {
+                direction: FetchDirection::Initiator { phase: p, .. },
+                ..
+            })) if p == phase
+        );
+        assert!(recv.try_recv().is_err(), "The fetch isn't complete yet");
+        alice.fetched(fetched(rid, phase), Ok(vec![]));
+    }
+    assert_matches!(recv.try_recv(), Ok(node::FetchResult::Success { .. }));
+
+    // Without a filter, the tips aren't fetched separately.
+    let (send, recv) = chan::bounded::<node::FetchResult>(1);
+    alice.command(Command::Fetch(rid2, bob.id, send));
+
+    for phase in [FetchPhase::Metadata, FetchPhase::Limited(limits2)] {
+        assert_eq!(
+            alice.messages(bob.id).last(),
+            Some(Message::Fetch { rid: rid2 })
+        );
+        alice.receive(bob.id(), Message::FetchOk { rid: rid2 });
+        assert_matches!(
+            alice.outbox().last(),
+            Some(Io::Fetch(Fetch {
+                direction: FetchDirection::Initiator { phase: p, .. },
+                ..
+            })) if p == phase
+        );
+        alice.fetched(fetched(rid2, phase), Ok(vec![]));
+    }
+    assert_matches!(recv.try_recv(), Ok(node::FetchResult::Success { .. }));
+}
@@ -1,11 +1,15 @@
 use std::{collections::HashSet, thread, time};
 
 use radicle::crypto::{test::signer::MockSigner, Signer};
+use radicle::git::Oid;
+use radicle::identity::{Did, Person, Visibility};
 use radicle::node::{FetchResult, Handle as _};
-use radicle::storage::{ReadRepository, ReadStorage};
+use radicle::storage::git::lfs;
+use radicle::storage::{ReadRepository, ReadStorage, WriteRepository};
 use radicle::{assert_matches, rad};
 
 use crate::service;
+use crate::service::config::{Limits, Quota};
 use crate::service::tracking::Scope;
 use crate::storage::git::transport;
 use crate::test::environment::{converge, Node};
@@ -195,6 +199,70 @@ fn test_replication() {
     assert_matches!(alice.storage.repository(acme).unwrap().verify(), Ok(()));
 }
 
+#[test]
+fn test_replication_person() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let alice = Node::init(tmp.path());
+    let mut bob = Node::init(tmp.path());
+    let acme = bob.project("acme", "");
+
+    let person = Person::new("Bob").unwrap();
+    let bob_repo = bob.storage.repository(acme).unwrap();
+    person.publish(&bob.signer, &[], bob_repo.raw()).unwrap();
+    bob_repo.sign_refs(&bob.signer).unwrap();
+
+    let mut alice = alice.spawn(service::Config::default());
+    let bob = bob.spawn(service::Config::default());
+
+    alice.connect(&bob);
+    converge([&alice, &bob]);
+
+    let _ = alice.handle.track_repo(acme, Scope::All).unwrap();
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(result.is_success());
+
+    let alice_repo = alice.storage.repository(acme).unwrap();
+    let at = Person::load(&bob.id, &alice_repo).unwrap();
+    assert_eq!(at.person, person);
+}
+
+#[test]
+fn test_replication_private() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let alice = Node::init(tmp.path());
+    let eve = Node::init(tmp.path());
+    let mut bob = Node::init(tmp.path());
+    let acme = bob.project("acme", "");
+    let secret =
+        bob.project_with_visibility("secret", "", Visibility::private([Did::from(eve.id)]));
+
+    let mut alice = alice.spawn(service::Config::default());
+    let mut eve = eve.spawn(service::Config::default());
+    let bob = bob.spawn(service::Config::default());
+
+    alice.connect(&bob);
+    eve.connect(&bob);
+
+    // The private repository is never announced.
+    alice.routes_to(&[(acme, bob.id)]);
+    eve.routes_to(&[(acme, bob.id)]);
+
+    // It is only served to the nodes it is visible to.
+    let _ = alice.handle.track_repo(secret, Scope::All).unwrap();
+    let result = alice.handle.fetch(secret, bob.id).unwrap();
+    assert!(!result.is_success());
+    assert!(alice.storage.get(&bob.id, secret).unwrap().is_none());
+
+    let _ = eve.handle.track_repo(secret, Scope::All).unwrap();
+    let result = eve.handle.fetch(secret, bob.id).unwrap();
+    assert!(result.is_success());
+    assert!(eve.storage.get(&bob.id, secret).unwrap().is_some());
+}
+
 #[test]
 fn test_dont_fetch_owned_refs() {
     logger::init(log::Level::Debug);
@@ -362,6 +430,265 @@ fn test_fetch_up_to_date() {
     assert_eq!(result.success(), Some(vec![]));
 }
 
+#[test]
+fn test_fetch_sigrefs_rollback() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let alice = Node::init(tmp.path());
+    let mut bob = Node::init(tmp.path());
+    let acme = bob.project("acme", "");
+
+    let mut alice = alice.spawn(service::Config::default());
+    let bob = bob.spawn(service::Config::default());
+
+    alice.connect(&bob);
+    converge([&alice, &bob]);
+
+    let _ = alice.handle.track_repo(acme, Scope::All).unwrap();
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(result.is_success());
+
+    let bob_repo = bob.storage.repository(acme).unwrap();
+    let (_, head) = bob_repo.canonical_head().unwrap();
+    let feature = format!("refs/namespaces/{}/refs/heads/feature", bob.id);
+    let old = bob_repo.sigrefs().unwrap()[&bob.id];
+
+    bob_repo
+        .raw()
+        .reference(&feature, *head, false, "")
+        .unwrap();
+    bob_repo.sign_refs(&bob.signer).unwrap();
+
+    let new = bob_repo.sigrefs().unwrap()[&bob.id];
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(result.is_success());
+
+    let alice_repo = alice.storage.repository(acme).unwrap();
+    assert_eq!(alice_repo.sigrefs().unwrap()[&bob.id], new);
+
+    // Bob replays his older signed refs in a new commit, which git sees as a fast-forward.
+    let forged = {
+        let raw = bob_repo.raw();
+        let tree = raw.find_commit(*old).unwrap().tree().unwrap();
+        let parent = raw.find_commit(*new).unwrap();
+        let author = raw.signature().unwrap();
+
+        raw.commit(None, &author, &author, "Forged", &tree, &[&parent])
+            .unwrap()
+    };
+    bob_repo
+        .raw()
+        .reference(
+            &format!("refs/namespaces/{}/refs/rad/sigrefs", bob.id),
+            forged,
+            true,
+            "",
+        )
+        .unwrap();
+    bob_repo
+        .raw()
+        .find_reference(&feature)
+        .unwrap()
+        .delete()
+        .unwrap();
+
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(result.is_success());
+    assert_eq!(alice_repo.sigrefs().unwrap()[&bob.id], new);
+    assert!(alice_repo.raw().find_reference(&feature).is_ok());
+    assert_matches!(alice_repo.verify(), Ok(()));
+}
+
+#[test]
+fn test_fetch_quota_exceeded() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let alice = Node::init(tmp.path());
+    let mut bob = Node::init(tmp.path());
+    let acme = bob.project("acme", "");
+
+    let mut alice = alice.spawn(service::Config {
+        limits: Limits {
+            repo_quota: Quota {
+                max_object_size: Some(4096),
+                ..Quota::default()
+            },
+            ..Limits::default()
+        },
+        ..service::Config::default()
+    });
+    let bob = bob.spawn(service::Config::default());
+
+    alice.connect(&bob);
+    converge([&alice, &bob]);
+
+    let _ = alice.handle.track_repo(acme, Scope::All).unwrap();
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(result.is_success());
+
+    let bob_repo = bob.storage.repository(acme).unwrap();
+    let (_, head) = bob_repo.canonical_head().unwrap();
+    let feature = format!("refs/namespaces/{}/refs/heads/feature", bob.id);
+    let old = bob_repo.sigrefs().unwrap()[&bob.id];
+
+    // Bob commits an object over alice's quota.
+    let blob = {
+        let raw = bob_repo.raw();
+        let blob = raw.blob(&[0xff; 1024 * 64]).unwrap();
+        let parent = raw.find_commit(*head).unwrap();
+        let mut tree = raw.treebuilder(Some(&parent.tree().unwrap())).unwrap();
+        tree.insert("large.bin", blob, 0o100644).unwrap();
+        let tree = raw.find_tree(tree.write().unwrap()).unwrap();
+        let author = raw.signature().unwrap();
+        let commit = raw
+            .commit(None, &author, &author, "Large", &tree, &[&parent])
+            .unwrap();
+
+        raw.reference(&feature, commit, false, "").unwrap();
+        blob
+    };
+    bob_repo.sign_refs(&bob.signer).unwrap();
+
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(!result.is_success());
+
+    // The fetch is rolled back: neither the refs nor the objects are added.
+    let alice_repo = alice.storage.repository(acme).unwrap();
+    assert_eq!(alice_repo.sigrefs().unwrap()[&bob.id], old);
+    assert!(alice_repo.raw().find_reference(&feature).is_err());
+    assert!(alice_repo.raw().find_blob(blob).is_err());
+    assert_matches!(alice_repo.verify(), Ok(()));
+}
+
+#[test]
+fn test_fetch_remote_quota_exceeded() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let alice = Node::init(tmp.path());
+    let eve = Node::init(tmp.path());
+    let mut bob = Node::init(tmp.path());
+    let acme = bob.project("acme", "");
+
+    let mut alice = alice.spawn(service::Config {
+        limits: Limits {
+            remote_quota: Quota {
+                max_object_size: Some(4096),
+                ..Quota::default()
+            },
+            ..Limits::default()
+        },
+        ..service::Config::default()
+    });
+    let mut eve = eve.spawn(service::Config::default());
+    let mut bob = bob.spawn(service::Config::default());
+
+    alice.connect(&bob);
+    eve.connect(&bob);
+    converge([&alice, &bob, &eve]);
+
+    // Eve forks the project, and commits an object over alice's remote quota.
+    let _ = eve.handle.track_repo(acme, Scope::All).unwrap();
+    let result = eve.handle.fetch(acme, bob.id).unwrap();
+    assert!(result.is_success());
+    rad::fork(acme, &eve.signer, &eve.storage).unwrap();
+
+    let eve_repo = eve.storage.repository(acme).unwrap();
+    let (_, head) = eve_repo.head().unwrap();
+    let blob = {
+        let raw = eve_repo.raw();
+        let blob = raw.blob(&[0xff; 1024 * 64]).unwrap();
+        let parent = raw.find_commit(*head).unwrap();
+        let mut tree = raw.treebuilder(Some(&parent.tree().unwrap())).unwrap();
+        tree.insert("large.bin", blob, 0o100644).unwrap();
+        let tree = raw.find_tree(tree.write().unwrap()).unwrap();
+        let author = raw.signature().unwrap();
+        let commit = raw
+            .commit(None, &author, &author, "Large", &tree, &[&parent])
+            .unwrap();
+
+        raw.reference(
+            &format!("refs/namespaces/{}/refs/heads/feature", eve.id),
+            commit,
+            false,
+            "",
+        )
+        .unwrap();
+        blob
+    };
+    eve_repo.sign_refs(&eve.signer).unwrap();
+
+    // Bob replicates eve's fork.
+    let _ = bob.handle.track_repo(acme, Scope::All).unwrap();
+    let result = bob.handle.fetch(acme, eve.id).unwrap();
+    assert!(result.is_success());
+
+    let _ = alice.handle.track_repo(acme, Scope::All).unwrap();
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(!result.is_success());
+
+    // Bob's refs are fetched, but neither eve's refs nor the objects only they reference.
+    let alice_repo = alice.storage.repository(acme).unwrap();
+    let sigrefs = alice_repo.sigrefs().unwrap();
+    assert!(sigrefs.contains_key(&bob.id));
+    assert!(!sigrefs.contains_key(&eve.id));
+    assert!(alice_repo.raw().find_blob(blob).is_err());
+    assert_matches!(alice_repo.verify(), Ok(()));
+}
+
+#[test]
+fn test_fetch_large_object() {
+    logger::init(log::Level::Debug);
+
+    let tmp = tempfile::tempdir().unwrap();
+    let alice = Node::init(tmp.path());
+    let mut bob = Node::init(tmp.path());
+    let acme = bob.project("acme", "");
+
+    let mut alice = alice.spawn(service::Config::default());
+    let bob = bob.spawn(service::Config::default());
+
+    alice.connect(&bob);
+    converge([&alice, &bob]);
+
+    let _ = alice.handle.track_repo(acme, Scope::All).unwrap();
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(result.is_success());
+
+    let content = vec![0xff; 1024 * 64];
+    let bob_repo = bob.storage.repository(acme).unwrap();
+    let pointer = bob_repo.store_large_object(content.as_slice()).unwrap();
+
+    // Bob also names the same object after a different one.
+    let other = Oid::from(bob_repo.raw().blob(b"other").unwrap());
+    bob_repo
+        .raw()
+        .reference(&lfs::refname(&other), *pointer.oid, false, "")
+        .unwrap();
+
+    let alice_repo = alice.storage.repository(acme).unwrap();
+    alice_repo.want_large_object(&pointer.oid).unwrap();
+    alice_repo.want_large_object(&other).unwrap();
+
+    let result = alice.handle.fetch(acme, bob.id).unwrap();
+    assert!(result.is_success());
+    assert_eq!(
+        alice_repo
+            .large_object(&pointer.oid)
+            .unwrap()
+            .unwrap()
+            .content(),
+        content.as_slice()
+    );
+    assert!(alice_repo.large_object(&other).unwrap().is_none());
+    assert!(alice_repo
+        .raw()
+        .find_reference(&lfs::refname(&other))
+        .is_err());
+}
+
 #[test]
 #[ignore = "failing"]
 #[should_panic]
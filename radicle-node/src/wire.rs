@@ -420,7 +420,7 @@ impl<V> Encode for SignedRefs<V> {
     fn encode<W: io::Write + ?Sized>(&self, writer: &mut W) -> Result<usize, io::Error> {
         let mut n = 0;
 
-        n += self.refs.encode(writer)?;
+        n += self.payload().encode(writer)?;
         n += self.signature.encode(writer)?;
 
         Ok(n)
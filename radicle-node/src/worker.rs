@@ -1,3 +1,6 @@
+mod quota;
+mod staging;
+
 use std::io::{prelude::*, BufReader};
 use std::ops::ControlFlow;
 use std::thread::JoinHandle;
@@ -10,15 +13,24 @@ use netservices::{AsConnection, NetSession, SplitIo};
 
 use radicle::crypto::{PublicKey, Signer};
 use radicle::identity::{Id, IdentityError};
-use radicle::storage::{Namespaces, ReadRepository, RefUpdate, WriteRepository, WriteStorage};
-use radicle::{git, Storage};
+use radicle::storage::git::lfs;
+use radicle::storage::{
+    Namespaces, ReadRepository, ReadStorage, RefUpdate, WriteRepository, WriteStorage,
+};
+use radicle::{cob, git, release, Storage};
 use reactor::poller::popol;
 
 use crate::runtime::Handle;
-use crate::service::reactor::{Fetch, FetchDirection};
+use crate::service::config::Quota;
+use crate::service::reactor::{Fetch, FetchDirection, FetchPhase};
 use crate::storage;
 use crate::wire::{WireReader, WireSession, WireWriter};
 
+pub use quota::QuotaError;
+
+/// Name of the remote that partial fetches go through. See [`FetchPhase::Limited`].
+const PROMISOR_REMOTE: &str = "rad";
+
 /// Worker pool configuration.
 pub struct Config {
     /// Number of worker threads.
@@ -33,6 +45,12 @@ pub struct Config {
     pub daemon: net::SocketAddr,
     /// Git storage.
     pub storage: Storage,
+    /// Storage quota of each repository.
+    pub repo_quota: Quota,
+    /// Storage quota of each remote of a repository.
+    pub remote_quota: Quota,
+    /// Whether to accept signed refs updates that roll back the refs of a remote.
+    pub allow_rollbacks: bool,
 }
 
 /// Error returned by fetch.
@@ -54,6 +72,13 @@ pub enum FetchError {
     Upload(#[from] UploadError),
     #[error("remote aborted fetch")]
     RemoteAbortedFetch,
+    #[error("repository quota exceeded: {0}")]
+    RepoQuotaExceeded(QuotaError),
+    #[error("quota of remote {remote} exceeded: {error}")]
+    RemoteQuotaExceeded {
+        remote: PublicKey,
+        error: QuotaError,
+    },
 }
 
 impl FetchError {
@@ -61,6 +86,14 @@ impl FetchError {
     pub fn is_timeout(&self) -> bool {
         matches!(self, FetchError::Io(e) if e.kind() == io::ErrorKind::TimedOut)
     }
+
+    /// Check if the fetch was rejected for exceeding a storage quota.
+    pub fn is_quota_exceeded(&self) -> bool {
+        matches!(
+            self,
+            FetchError::RepoQuotaExceeded(_) | FetchError::RemoteQuotaExceeded { .. }
+        )
+    }
 }
 
 /// Error returned by fetch responder.
@@ -72,6 +105,14 @@ pub enum UploadError {
     CommandMismatch,
     #[error("error parsing git command packet-line: {0}")]
     InvalidPacketLine(io::Error),
+    #[error("repository {0} is not visible to {1}")]
+    NotVisible(Id, PublicKey),
+    #[error("repository {0} is partial and can't be served")]
+    Partial(Id),
+    #[error(transparent)]
+    Storage(#[from] storage::Error),
+    #[error(transparent)]
+    Identity(#[from] IdentityError),
     #[error(transparent)]
     Io(#[from] io::Error),
 }
@@ -108,6 +149,9 @@ struct Worker<G: Signer + Ecdh> {
     handle: Handle<G>,
     atomic: bool,
     name: String,
+    repo_quota: Quota,
+    remote_quota: Quota,
+    allow_rollbacks: bool,
 }
 
 impl<G: Signer + Ecdh + 'static> Worker<G> {
@@ -155,7 +199,11 @@ impl<G: Signer + Ecdh + 'static> Worker<G> {
     ) -> (WireSession<G>, Result<Vec<RefUpdate>, FetchError>) {
         let rid = fetch.rid;
         match &fetch.direction {
-            FetchDirection::Initiator { namespaces } => {
+            FetchDirection::Initiator {
+                namespaces,
+                phase,
+                large_objects,
+            } => {
                 log::debug!(target: "worker", "Worker processing outgoing fetch for {}", fetch.rid);
 
                 let mut tunnel =
@@ -163,7 +211,7 @@ impl<G: Signer + Ecdh + 'static> Worker<G> {
                         Ok(tunnel) => tunnel,
                         Err((session, err)) => return (session, Err(err.into())),
                     };
-                let result = self.fetch(rid, namespaces, &mut tunnel);
+                let result = self.fetch(rid, namespaces, phase, *large_objects, &mut tunnel);
                 let mut session = tunnel.into_session();
 
                 if let Err(err) = &result {
@@ -262,6 +310,8 @@ impl<G: Signer + Ecdh + 'static> Worker<G> {
         &self,
         rid: Id,
         namespaces: &Namespaces,
+        phase: &FetchPhase,
+        large_objects: bool,
         tunnel: &mut Tunnel<WireSession<G>>,
     ) -> Result<Vec<RefUpdate>, FetchError> {
         let repo = match self.storage.repository_mut(rid) {
@@ -269,21 +319,40 @@ impl<G: Signer + Ecdh + 'static> Worker<G> {
             Err(e) if e.is_not_found() => self.storage.create(rid),
             Err(e) => Err(e),
         }?;
+        // Storage maintenance takes this lock before removing refs or pruning objects
+        // early, which could otherwise happen while the fetch relies on them.
+        let _lock = repo.lock(self.timeout)?;
+        staging::clear_stale(&repo)?;
+        quota::Quarantine::clear_stale(&repo)?;
+        // Fetched objects are quarantined, and only moved into the repository once checked.
+        let quarantine = quota::Quarantine::new(&repo)?;
         let tunnel_addr = tunnel.local_addr()?;
+        let url = format!("git://{tunnel_addr}/{}", repo.id.canonical());
         let mut cmd = process::Command::new("git");
         cmd.current_dir(repo.path())
             .env_clear()
             .envs(env::vars().filter(|(k, _)| k == "PATH" || k.starts_with("GIT_TRACE")))
             .envs(git::env::GIT_DEFAULT_CONFIG)
-            .args(["-c", "protocol.version=2"])
-            .arg("fetch")
-            .arg("--verbose");
+            .envs(quarantine.env(&repo))
+            .args(["-c", "protocol.version=2"]);
+
+        if let FetchPhase::Limited(_) = phase {
+            // Partial fetches must go through a named remote, which git then registers as
+            // the promisor remote of the repository.
+            cmd.arg("-c")
+                .arg(format!("remote.{PROMISOR_REMOTE}.url={url}"));
+        }
+        cmd.arg("fetch").arg("--verbose");
 
         match namespaces {
             Namespaces::All => {
                 // We should not prune in this case, because it would mean that namespaces that
                 // don't exit on the remote would be deleted locally.
             }
+            Namespaces::One(_) if large_objects => {
+                // We should not prune in this case either, because it would mean that large
+                // objects that don't exist on the remote would be deleted locally.
+            }
             Namespaces::One(_) => {
                 // TODO: Make sure we verify before pruning, as pruning may get us into
                 // a state we can't roll back.
@@ -299,12 +368,48 @@ impl<G: Signer + Ecdh + 'static> Worker<G> {
             cmd.arg("--atomic");
         }
 
+        let mut fetchspecs = match phase {
+            FetchPhase::Full => {
+                cmd.arg(url);
+                namespaces.as_fetchspecs()
+            }
+            FetchPhase::Metadata => {
+                cmd.arg(url);
+                namespaces.as_metadata_fetchspecs()
+            }
+            FetchPhase::Tips => {
+                cmd.arg("--depth=1").arg(url);
+                namespaces.as_fetchspecs()
+            }
+            FetchPhase::Limited(limits) => {
+                cmd.args(limits.as_args());
+
+                if limits.depth.is_none() && repo.path().join("shallow").exists() {
+                    // Fetch the history beyond the tips fetched in the previous phase.
+                    cmd.arg("--unshallow");
+                }
+                cmd.arg(PROMISOR_REMOTE);
+                namespaces.as_fetchspecs()
+            }
+        };
+        if *phase != FetchPhase::Metadata {
+            // Large objects are only fetched in full if our policy is to store them.
+            // Otherwise, we only fetch the ones that were asked for.
+            if large_objects {
+                fetchspecs.push(lfs::fetchspec(None));
+            } else {
+                for oid in repo.wanted_large_objects()? {
+                    fetchspecs.push(lfs::fetchspec(Some(&oid)));
+                }
+            }
+        }
         // Ignore our own remote when fetching
-        let mut fetchspecs = namespaces.as_fetchspecs();
         fetchspecs.push(format!("^refs/namespaces/{}/*", self.local));
 
-        cmd.arg(format!("git://{tunnel_addr}/{}", repo.id.canonical()))
-            .args(&fetchspecs)
+        // Fetched refs are staged, and only applied once checked.
+        let (staging, fetchspecs) = staging::Staging::new(&repo, &fetchspecs, &self.local)?;
+
+        cmd.args(&fetchspecs)
             .stdout(process::Stdio::piped())
             .stderr(process::Stdio::piped())
             .stdin(process::Stdio::piped());
@@ -326,13 +431,94 @@ impl<G: Signer + Ecdh + 'static> Worker<G> {
         let result = child.wait()?;
         if result.success() {
             log::debug!(target: "worker", "Fetch for {} exited successfully", rid);
-            let head = repo.set_head()?;
-            log::debug!(target: "worker", "Head for {} set to {head}", rid);
+
+            let fetched = staging.updates(&repo)?;
+            let mut updates = fetched.clone();
+            for (remote, e) in repo.check_sigrefs(&updates.sigrefs())? {
+                if self.allow_rollbacks {
+                    log::warn!(target: "worker", "Accepted signed refs rollback of {remote} in {rid}: {e}");
+                } else {
+                    log::warn!(target: "worker", "Refused signed refs update of {remote} in {rid}: {e}");
+                    updates.reject(&remote);
+                }
+            }
+            // Clocks of collaborative object changes are only bounded here, when they are
+            // received, so that their ordering doesn't depend on when it is computed.
+            let now = cob::Timestamp::now();
+            for (remote, tips) in updates.cobs() {
+                let ahead = repo.changes_ahead(tips, now)?;
+
+                if let Some(change) = ahead.first() {
+                    log::warn!(target: "worker", "Refused updates of {remote} in {rid}: clock of change {change} is ahead");
+                    updates.reject(&remote);
+                }
+            }
+            let mut rejected = None;
+            match quota::enforce(
+                &repo,
+                &quarantine,
+                &mut updates,
+                &self.repo_quota,
+                &self.remote_quota,
+            ) {
+                Ok(remotes) => {
+                    for (remote, e) in remotes {
+                        log::warn!(target: "worker", "Rejected refs of {remote} in {rid}: {e}");
+                        rejected
+                            .get_or_insert(FetchError::RemoteQuotaExceeded { remote, error: e });
+                    }
+                }
+                Err(e) => {
+                    staging.discard(&repo)?;
+                    quarantine.discard()?;
+
+                    return Err(e);
+                }
+            }
+            // Objects only reachable from dropped updates are left out of the repository.
+            if updates == fetched {
+                quarantine.migrate(&repo)?;
+            } else {
+                quarantine.migrate_reachable(&repo, &updates)?;
+            }
+            staging.apply(&repo, &updates)?;
+
+            // Large objects aren't signed, so their refs are checked against their names.
+            for name in repo.prune_large_objects()? {
+                log::warn!(target: "worker", "Deleted large object ref {name} in {rid}: target mismatch");
+            }
+
             let head = repo.set_identity_head()?;
             log::debug!(target: "worker", "'refs/rad/id' for {} set to {head}", rid);
-            Ok(vec![])
+
+            if let FetchPhase::Metadata = phase {
+                // Branches are fetched in the next phase.
+                return rejected.map_or(Ok(vec![]), Err);
+            }
+            match repo.set_head() {
+                Ok(head) => log::debug!(target: "worker", "Head for {} set to {head}", rid),
+                Err(IdentityError::NoQuorum(branch)) => {
+                    log::debug!(target: "worker", "No quorum for {branch} in {rid}, keeping head");
+                }
+                Err(e) => return Err(e.into()),
+            }
+            for r in repo.set_canonical_refs()? {
+                if r.target.is_none() {
+                    log::debug!(target: "worker", "No quorum for {} in {}", r.name, rid);
+                }
+            }
+            // Releases signed by enough delegates are published under the canonical tags.
+            if let Err(e) = release::set_canonical(&repo) {
+                log::warn!(target: "worker", "Failed to set canonical releases of {rid}: {e}");
+            }
+            // Refs of remotes within their quota are kept, but the fetch is still reported
+            // as rejected.
+            rejected.map_or(Ok(vec![]), Err)
         } else {
             log::error!(target: "worker", "Fetch for {} failed", rid);
+            staging.discard(&repo)?;
+            quarantine.discard()?;
+
             Err(FetchError::CommandFailed {
                 code: result.code().unwrap_or(1),
             })
@@ -363,6 +549,9 @@ impl<G: Signer + Ecdh + 'static> Worker<G> {
             }
         };
 
+        let repo = self.storage.repository(fetch.rid)?;
+        authorize_upload(&repo, &fetch.remote)?;
+
         // Connect to our local git daemon, running as a child process.
         let daemon = net::TcpStream::connect_timeout(&self.daemon, self.timeout)
             .map_err(UploadError::DaemonConnectionFailed)?;
@@ -405,6 +594,20 @@ impl<G: Signer + Ecdh + 'static> Worker<G> {
     }
 }
 
+/// Check that a repository can be uploaded to the given remote.
+fn authorize_upload<R: ReadRepository>(repo: &R, remote: &PublicKey) -> Result<(), UploadError> {
+    // Private repositories are only served to the nodes they are visible to.
+    if !repo.is_visible_to(remote)? {
+        return Err(UploadError::NotVisible(repo.id(), *remote));
+    }
+    // Partial repositories are missing objects, which can't be fetched from their promisor
+    // remote outside of a fetch. Serving them would fail midway, or serve partial history.
+    if !repo.fetch_limits()?.is_empty() {
+        return Err(UploadError::Partial(repo.id()));
+    }
+    Ok(())
+}
+
 /// A pool of workers. One thread is allocated for each worker.
 pub struct Pool {
     pool: Vec<JoinHandle<Result<(), chan::RecvError>>>,
@@ -429,6 +632,9 @@ impl Pool {
                 timeout: config.timeout,
                 name: config.name.clone(),
                 atomic: config.atomic,
+                repo_quota: config.repo_quota.clone(),
+                remote_quota: config.remote_quota.clone(),
+                allow_rollbacks: config.allow_rollbacks,
             };
             let thread = thread::Builder::new()
                 .name(config.name.clone())
@@ -646,3 +852,45 @@ pub mod pktline {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use radicle::crypto::test::signer::MockSigner;
+    use radicle::identity::{Did, Visibility};
+    use radicle::rad;
+    use radicle::storage::git::transport;
+    use radicle::test::fixtures;
+
+    use super::*;
+
+    #[test]
+    fn test_authorize_upload_private() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::default();
+        let eve = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (working, _) = fixtures::repository(tmp.path().join("working"));
+        let (rid, _, _) = rad::init_with_visibility(
+            &working,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            Visibility::private([Did::from(*bob.public_key())]),
+            &alice,
+            &storage,
+        )
+        .unwrap();
+        let repo = storage.repository(rid).unwrap();
+
+        assert!(authorize_upload(&repo, alice.public_key()).is_ok());
+        assert!(authorize_upload(&repo, bob.public_key()).is_ok());
+        assert!(matches!(
+            authorize_upload(&repo, eve.public_key()),
+            Err(UploadError::NotVisible(id, remote)) if id == rid && remote == *eve.public_key()
+        ));
+    }
+}
@@ -0,0 +1,436 @@
+//! Storage quotas, enforced on the refs and objects added by a fetch.
+//!
+//! Objects are never fetched into the repository directly. Instead, the fetch writes them to
+//! a [`Quarantine`] directory, which is an alternate object directory of the repository. Once
+//! the fetch is done, and before the staged ref updates are applied, the usage of every
+//! remote that was updated is measured against the remote quota, and the usage of the
+//! repository against the repository quota. Remotes over their quota have their updates
+//! dropped, and if the repository is over its quota, the quarantine is discarded. Otherwise,
+//! the quarantined objects are moved into the repository, leaving out the ones that are only
+//! reachable from dropped updates.
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, io, process};
+
+use radicle::crypto::PublicKey;
+use radicle::git;
+use radicle::git::Oid;
+use radicle::storage::git::Repository;
+use radicle::storage::ReadRepository;
+
+use crate::service::config::Quota;
+
+use super::staging;
+use super::staging::Updates;
+use super::FetchError;
+
+/// Directory of the repository under which every fetch quarantines its objects.
+pub const QUARANTINE: &str = "incoming";
+
+/// A storage quota was exceeded.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum QuotaError {
+    #[error("{size} bytes of objects exceed the limit of {limit} bytes")]
+    Bytes { size: u64, limit: u64 },
+    #[error("{count} refs exceed the limit of {limit} refs")]
+    Refs { count: usize, limit: usize },
+    #[error("object {oid} of {size} bytes exceeds the limit of {limit} bytes")]
+    ObjectSize { oid: Oid, size: u64, limit: u64 },
+}
+
+/// Storage usage, measured against a [`Quota`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Usage {
+    /// Size of the git objects, in bytes.
+    pub bytes: u64,
+    /// Number of refs.
+    pub refs: usize,
+    /// Largest git object, and its size in bytes.
+    pub largest: Option<(Oid, u64)>,
+}
+
+impl Usage {
+    /// Check the usage against a quota.
+    pub fn check(&self, quota: &Quota) -> Result<(), QuotaError> {
+        if let Some(limit) = quota.max_bytes {
+            if self.bytes > limit {
+                return Err(QuotaError::Bytes {
+                    size: self.bytes,
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = quota.max_refs {
+            if self.refs > limit {
+                return Err(QuotaError::Refs {
+                    count: self.refs,
+                    limit,
+                });
+            }
+        }
+        if let (Some(limit), Some((oid, size))) = (quota.max_object_size, self.largest) {
+            if size > limit {
+                return Err(QuotaError::ObjectSize { oid, size, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Object directory that a fetch writes to, until its objects are checked. It is unique to
+/// the fetch, and removed when dropped.
+#[derive(Debug)]
+pub struct Quarantine {
+    dir: tempfile::TempDir,
+}
+
+impl Quarantine {
+    /// Create a quarantine in the given repository, and add it to the repository's object
+    /// database, so that the fetched objects can be read before they are moved.
+    pub fn new(repo: &Repository) -> Result<Self, FetchError> {
+        let root = repo.path().join(QUARANTINE);
+
+        fs::create_dir_all(&root)?;
+
+        let dir = tempfile::tempdir_in(root)?;
+
+        fs::create_dir_all(dir.path().join("pack"))?;
+        repo.backend
+            .odb()?
+            .add_disk_alternate(&dir.path().to_string_lossy())?;
+
+        Ok(Self { dir })
+    }
+
+    /// Remove the objects left over by interrupted fetches. Since a quarantine is only
+    /// removed by the fetch that created it, this must only be called while holding the
+    /// repository lock, when no fetch is in progress.
+    pub fn clear_stale(repo: &Repository) -> Result<(), io::Error> {
+        let root = repo.path().join(QUARANTINE);
+
+        if root.exists() {
+            fs::remove_dir_all(root)?;
+        }
+        Ok(())
+    }
+
+    /// Environment of the git commands that write to or read from the quarantine.
+    pub fn env(&self, repo: &Repository) -> [(&'static str, PathBuf); 2] {
+        [
+            ("GIT_OBJECT_DIRECTORY", self.dir.path().to_path_buf()),
+            (
+                "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+                repo.path().join("objects"),
+            ),
+        ]
+    }
+
+    /// Move the quarantined objects into the repository.
+    pub fn migrate(self, repo: &Repository) -> Result<(), io::Error> {
+        let objects = repo.path().join("objects");
+        let mut files = object_files(self.dir.path())?
+            .into_keys()
+            .collect::<Vec<_>>();
+        // Pack indexes are moved last, so that git never finds an index without its pack.
+        files.sort_by_key(|path| path.extension() == Some(OsStr::new("idx")));
+
+        for src in files {
+            let dst = objects.join(
+                src.strip_prefix(self.dir.path())
+                    .expect("Quarantine::migrate: files are in the quarantine"),
+            );
+            // Objects are immutable, so existing files can be kept as they are.
+            if dst.exists() {
+                continue;
+            }
+            if let Some(parent) = dst.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&src, &dst)?;
+        }
+        self.dir.close()
+    }
+
+    /// Move the quarantined objects reachable from the given updates into the repository,
+    /// and discard the others. This is used when some of the fetched updates were dropped,
+    /// so that the objects only they reference don't end up in the repository.
+    pub fn migrate_reachable(self, repo: &Repository, updates: &Updates) -> Result<(), FetchError> {
+        let tips = updates
+            .iter()
+            .filter_map(|(_, oid)| *oid)
+            .collect::<Vec<_>>();
+
+        if tips.is_empty() {
+            return self.discard().map_err(FetchError::from);
+        }
+        let known = references(repo)?.into_values().collect::<Vec<_>>();
+        let pack = repo.path().join("objects").join("pack").join("pack");
+        let mut child = process::Command::new("git")
+            .current_dir(repo.path())
+            .envs(git::env::GIT_DEFAULT_CONFIG)
+            .envs(self.env(repo))
+            .args(["pack-objects", "--revs", "--missing=allow-any", "-q"])
+            .arg(pack)
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            for oid in &tips {
+                writeln!(stdin, "{oid}")?;
+            }
+            for oid in &known {
+                writeln!(stdin, "^{oid}")?;
+            }
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(FetchError::CommandFailed {
+                code: status.code().unwrap_or(1),
+            });
+        }
+        self.discard().map_err(FetchError::from)
+    }
+
+    /// Discard the quarantined objects, leaving the repository untouched.
+    pub fn discard(self) -> Result<(), io::Error> {
+        self.dir.close()
+    }
+}
+
+/// Enforce the given quotas on the refs and objects added by a fetch, before its updates
+/// are applied.
+///
+/// Remotes over the remote quota have their updates dropped, and are returned. Delegates are
+/// exempt from the remote quota, since their refs make up the canonical history. If the
+/// repository is over its quota, an error is returned, and the fetch must be discarded.
+pub fn enforce(
+    repo: &Repository,
+    quarantine: &Quarantine,
+    updates: &mut Updates,
+    repo_quota: &Quota,
+    remote_quota: &Quota,
+) -> Result<Vec<(PublicKey, QuotaError)>, FetchError> {
+    if repo_quota.is_unlimited() && remote_quota.is_unlimited() {
+        return Ok(vec![]);
+    }
+    let updated = updates
+        .iter()
+        .filter_map(|(name, _)| git::parse_ref_namespaced::<PublicKey>(name).ok())
+        .map(|(remote, _)| remote)
+        .collect::<HashSet<_>>();
+
+    if updated.is_empty() {
+        return Ok(vec![]);
+    }
+    let current = references(repo)?;
+    let mut rejected = Vec::new();
+
+    if !remote_quota.is_unlimited() {
+        let refs = updated_references(&current, updates);
+        let delegates = repo
+            .delegates()
+            .map(|ds| ds.iter().map(|d| *d.as_key()).collect::<HashSet<_>>())
+            .unwrap_or_default();
+        // Objects of a remote are the ones that aren't part of the canonical history.
+        let canonical = current
+            .iter()
+            .filter(|(name, _)| !name.starts_with("refs/namespaces/"))
+            .map(|(_, oid)| *oid)
+            .collect::<Vec<_>>();
+
+        for remote in updated.difference(&delegates) {
+            let prefix = format!("refs/namespaces/{remote}/");
+            let tips = refs
+                .iter()
+                .filter(|(name, _)| name.starts_with(&prefix))
+                .map(|(_, oid)| *oid)
+                .collect::<Vec<_>>();
+            let mut usage = objects(repo, quarantine, &tips, &canonical)?;
+            usage.refs = tips.len();
+
+            if let Err(e) = usage.check(remote_quota) {
+                updates.reject(remote);
+                rejected.push((*remote, e));
+            }
+        }
+    }
+
+    if !repo_quota.is_unlimited() {
+        let refs = updated_references(&current, updates);
+        let tips = refs.values().copied().collect::<Vec<_>>();
+        let known = current.values().copied().collect::<Vec<_>>();
+        let bytes = object_files(&repo.path().join("objects"))?
+            .values()
+            .chain(object_files(quarantine.dir.path())?.values())
+            .sum();
+        // Only the objects added by the fetch are checked against the maximum object size,
+        // but all of them count towards the total size.
+        let usage = Usage {
+            bytes,
+            refs: refs.len(),
+            ..objects(repo, quarantine, &tips, &known)?
+        };
+
+        if let Err(e) = usage.check(repo_quota) {
+            return Err(FetchError::RepoQuotaExceeded(e));
+        }
+    }
+    Ok(rejected)
+}
+
+/// Get the references of a repository once the given updates are applied.
+fn updated_references(current: &HashMap<String, Oid>, updates: &Updates) -> HashMap<String, Oid> {
+    let mut refs = current.clone();
+
+    for (name, oid) in updates.iter() {
+        match oid {
+            Some(oid) => refs.insert(name.clone(), *oid),
+            None => refs.remove(name),
+        };
+    }
+    refs
+}
+
+/// Get the direct references of a repository, excluding the staged ones.
+fn references(repo: &Repository) -> Result<HashMap<String, Oid>, git::raw::Error> {
+    let mut refs = HashMap::new();
+
+    for r in repo.backend.references()? {
+        let r = r?;
+        if let (Some(name), Some(oid)) = (r.name(), r.target()) {
+            if !name.starts_with(staging::PREFIX) {
+                refs.insert(name.to_owned(), oid.into());
+            }
+        }
+    }
+    Ok(refs)
+}
+
+/// Measure the size of the objects reachable from `tips` but not from `exclude`.
+fn objects(
+    repo: &Repository,
+    quarantine: &Quarantine,
+    tips: &[Oid],
+    exclude: &[Oid],
+) -> Result<Usage, FetchError> {
+    let mut child = process::Command::new("git")
+        .current_dir(repo.path())
+        .envs(git::env::GIT_DEFAULT_CONFIG)
+        .envs(quarantine.env(repo))
+        .args(["rev-list", "--objects", "--missing=allow-any", "--stdin"])
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::null())
+        .spawn()?;
+
+    // The revisions are all read before anything is output, so we can write them at once.
+    if let Some(mut stdin) = child.stdin.take() {
+        for oid in tips {
+            writeln!(stdin, "{oid}")?;
+        }
+        for oid in exclude {
+            writeln!(stdin, "^{oid}")?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(FetchError::CommandFailed {
+            code: output.status.code().unwrap_or(1),
+        });
+    }
+    let odb = repo.backend.odb()?;
+    let mut usage = Usage::default();
+
+    for line in output.stdout.as_slice().lines() {
+        let line = line?;
+        let Some(oid) = line.split(' ').next().and_then(|s| Oid::from_str(s).ok()) else {
+            continue;
+        };
+        // Objects can be missing from partial clones.
+        let Ok((size, _)) = odb.read_header(*oid) else {
+            continue;
+        };
+        let size = size as u64;
+
+        usage.bytes += size;
+        if usage.largest.map_or(true, |(_, largest)| size > largest) {
+            usage.largest = Some((oid, size));
+        }
+    }
+    Ok(usage)
+}
+
+/// Get the files under the given directory, and their sizes.
+fn object_files(dir: &Path) -> Result<HashMap<PathBuf, u64>, io::Error> {
+    let mut files = HashMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+
+        if meta.is_dir() {
+            files.extend(object_files(&entry.path())?);
+        } else {
+            files.insert(entry.path(), meta.len());
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_check() {
+        let oid = Oid::from_str("8d5b3c4e5d6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b").unwrap();
+        let usage = Usage {
+            bytes: 2048,
+            refs: 8,
+            largest: Some((oid, 1024)),
+        };
+
+        assert_eq!(usage.check(&Quota::default()), Ok(()));
+        assert_eq!(
+            usage.check(&Quota {
+                max_bytes: Some(1024),
+                ..Quota::default()
+            }),
+            Err(QuotaError::Bytes {
+                size: 2048,
+                limit: 1024
+            })
+        );
+        assert_eq!(
+            usage.check(&Quota {
+                max_refs: Some(4),
+                ..Quota::default()
+            }),
+            Err(QuotaError::Refs { count: 8, limit: 4 })
+        );
+        assert_eq!(
+            usage.check(&Quota {
+                max_object_size: Some(512),
+                ..Quota::default()
+            }),
+            Err(QuotaError::ObjectSize {
+                oid,
+                size: 1024,
+                limit: 512
+            })
+        );
+        assert_eq!(
+            usage.check(&Quota {
+                max_bytes: Some(4096),
+                max_refs: Some(8),
+                max_object_size: Some(1024),
+            }),
+            Ok(())
+        );
+    }
+}
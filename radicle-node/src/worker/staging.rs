@@ -0,0 +1,205 @@
+//! Staging of the refs updated by a fetch.
+//!
+//! A fetch doesn't update the refs of a repository directly. Before it, the refs in scope of
+//! the fetchspecs are copied under a prefix unique to the fetch, within [`PREFIX`], and the
+//! fetchspecs are rewritten to update these copies instead. Once the fetch is done, the
+//! staged updates are checked, and only the ones that pass are applied to the repository.
+use std::collections::{BTreeMap, HashMap};
+
+use radicle::crypto::PublicKey;
+use radicle::git;
+use radicle::git::Oid;
+use radicle::storage::git::Repository;
+use radicle::storage::refs::SIGREFS_BRANCH;
+
+/// Prefix under which refs are staged.
+pub const PREFIX: &str = "refs/staging/";
+
+/// Refs staged for a fetch.
+#[derive(Debug)]
+pub struct Staging {
+    /// Prefix under which the refs of this fetch are staged.
+    prefix: String,
+    /// Refs in scope of the fetch, before it.
+    refs: BTreeMap<String, Oid>,
+    /// Namespace that is never updated by the fetch, ie. our own.
+    exclude: String,
+}
+
+impl Staging {
+    /// Stage the refs matching the destinations of the given fetchspecs, excluding the
+    /// namespace of `local`. Returns the fetchspecs rewritten to update the staged refs.
+    pub fn new(
+        repo: &Repository,
+        fetchspecs: &[String],
+        local: &PublicKey,
+    ) -> Result<(Self, Vec<String>), git::raw::Error> {
+        let prefix = format!("{PREFIX}{:016x}/", fastrand::u64(..));
+        let exclude = format!("refs/namespaces/{local}/");
+        let mut refs = BTreeMap::new();
+        let mut staged = Vec::with_capacity(fetchspecs.len());
+
+        for spec in fetchspecs {
+            let Some((src, dst)) = spec.split_once(':') else {
+                // Negative fetchspecs, which have no destination.
+                staged.push(spec.clone());
+                continue;
+            };
+            for r in repo.backend.references_glob(dst)? {
+                let r = r?;
+                let (Some(name), Some(oid)) = (r.name(), r.target()) else {
+                    continue;
+                };
+                if name.starts_with(&exclude) {
+                    continue;
+                }
+                repo.backend.reference(
+                    &staged_name(&prefix, name),
+                    oid,
+                    true,
+                    "stage (radicle)",
+                )?;
+                refs.insert(name.to_owned(), oid.into());
+            }
+            staged.push(format!("{src}:{}", staged_name(&prefix, dst)));
+        }
+        Ok((
+            Self {
+                prefix,
+                refs,
+                exclude,
+            },
+            staged,
+        ))
+    }
+
+    /// Get the updates made by the fetch, by ref name. Deleted refs have no target.
+    pub fn updates(&self, repo: &Repository) -> Result<Updates, git::raw::Error> {
+        let mut updates = BTreeMap::new();
+        let mut staged = HashMap::new();
+
+        for r in repo.backend.references_glob(&format!("{}*", self.prefix))? {
+            let r = r?;
+            let (Some(name), Some(oid)) = (r.name(), r.target()) else {
+                continue;
+            };
+            let name = format!("refs/{}", &name[self.prefix.len()..]);
+
+            if name.starts_with(&self.exclude) {
+                continue;
+            }
+            staged.insert(name, Oid::from(oid));
+        }
+        for (name, oid) in &staged {
+            if self.refs.get(name) != Some(oid) {
+                updates.insert(name.clone(), Some(*oid));
+            }
+        }
+        for name in self.refs.keys() {
+            if !staged.contains_key(name) {
+                updates.insert(name.clone(), None);
+            }
+        }
+        Ok(Updates(updates))
+    }
+
+    /// Apply the given updates to the repository, and clear the staged refs.
+    pub fn apply(self, repo: &Repository, updates: &Updates) -> Result<(), git::raw::Error> {
+        for (name, oid) in updates.iter() {
+            match oid {
+                Some(oid) => {
+                    repo.backend
+                        .reference(name, **oid, true, "fetch (radicle)")?;
+                }
+                None => {
+                    if let Ok(mut r) = repo.backend.find_reference(name) {
+                        r.delete()?;
+                    }
+                }
+            }
+        }
+        clear(repo, &self.prefix)
+    }
+
+    /// Discard the staged refs, leaving the repository untouched.
+    pub fn discard(self, repo: &Repository) -> Result<(), git::raw::Error> {
+        clear(repo, &self.prefix)
+    }
+}
+
+/// Ref updates made by a fetch, by ref name. Deleted refs have no target.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Updates(BTreeMap<String, Option<Oid>>);
+
+impl Updates {
+    /// Iterate over the updates.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Option<Oid>)> {
+        self.0.iter()
+    }
+
+    /// Get the signed refs updates, by remote.
+    pub fn sigrefs(&self) -> HashMap<PublicKey, Oid> {
+        self.0
+            .iter()
+            .filter_map(|(name, oid)| {
+                let (remote, refname) = git::parse_ref_namespaced::<PublicKey>(name).ok()?;
+
+                if refname == *SIGREFS_BRANCH {
+                    oid.map(|oid| (remote, oid))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Get the tips of the collaborative objects updated, by remote.
+    pub fn cobs(&self) -> HashMap<PublicKey, Vec<Oid>> {
+        let mut cobs = HashMap::<_, Vec<_>>::new();
+
+        for (name, oid) in &self.0 {
+            let Some(oid) = oid else {
+                continue;
+            };
+            let Ok((remote, refname)) = git::parse_ref_namespaced::<PublicKey>(name) else {
+                continue;
+            };
+            if refname.as_str().starts_with("refs/cobs/") {
+                cobs.entry(remote).or_default().push(*oid);
+            }
+        }
+        cobs
+    }
+
+    /// Drop the updates of the given remote, whose refs are then left untouched.
+    pub fn reject(&mut self, remote: &PublicKey) {
+        let prefix = format!("refs/namespaces/{remote}/");
+
+        self.0.retain(|name, _| !name.starts_with(&prefix));
+    }
+}
+
+/// Name under which the given ref is staged.
+fn staged_name(prefix: &str, name: &str) -> String {
+    format!("{prefix}{}", name.strip_prefix("refs/").unwrap_or(name))
+}
+
+/// Delete the refs left over by interrupted fetches. Since staged refs are only deleted by
+/// the fetch that staged them, this must only be called while holding the repository lock,
+/// when no fetch is in progress.
+pub fn clear_stale(repo: &Repository) -> Result<(), git::raw::Error> {
+    clear(repo, PREFIX)
+}
+
+/// Delete all refs staged under the given prefix.
+fn clear(repo: &Repository, prefix: &str) -> Result<(), git::raw::Error> {
+    let mut refs = Vec::new();
+
+    for r in repo.backend.references_glob(&format!("{prefix}*"))? {
+        refs.push(r?);
+    }
+    for mut r in refs {
+        r.delete()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//! Git filter for large objects. See [`radicle::storage::git::lfs`].
+//!
+//! The filter is enabled in a working copy with:
+//!
+//! ```text
+//! git config filter.rad.clean "git-rad-lfs clean %f"
+//! git config filter.rad.smudge "git-rad-lfs smudge %f"
+//! git config filter.rad.required true
+//! ```
+//!
+//! Large files are then marked with the `filter=rad` attribute, in `.gitattributes`.
+use std::io::{Read, Write};
+use std::{env, io};
+
+use radicle::git::raw;
+use radicle::node::Handle;
+use radicle::storage::git::lfs::{Pointer, POINTER_MAX_SIZE};
+use radicle::storage::git::Repository;
+use radicle::storage::ReadStorage;
+
+use crate::Error;
+
+/// Run the large object filter using the given profile.
+pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let working = raw::Repository::open_from_env()?;
+    let (_, rid) = radicle::rad::remote(&working)?;
+    let repo = profile.storage.repository(rid)?;
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+
+    // Pointer files are small, so we only need to look at the start of the input to know
+    // whether it is one.
+    let mut head = Vec::with_capacity(POINTER_MAX_SIZE + 1);
+    (&mut stdin)
+        .take(POINTER_MAX_SIZE as u64 + 1)
+        .read_to_end(&mut head)?;
+    let pointer = Pointer::decode(&head);
+
+    match (args.first().map(|a| a.as_str()), pointer) {
+        // Files that are already pointers are left as they are.
+        (Some("clean"), Some(pointer)) => {
+            stdout.write_all(pointer.encode().as_bytes())?;
+        }
+        (Some("clean"), None) => {
+            let pointer = repo.store_large_object(head.as_slice().chain(&mut stdin))?;
+            stdout.write_all(pointer.encode().as_bytes())?;
+        }
+        (Some("smudge"), Some(pointer)) => {
+            if repo.large_object(&pointer.oid)?.is_none() {
+                fetch(&repo, &profile, &pointer)?;
+            }
+            if let Some(blob) = repo.large_object(&pointer.oid)? {
+                stdout.write_all(blob.content())?;
+            } else {
+                // Leave the pointer in place; the object will be fetched in the background,
+                // and can be checked out later.
+                eprintln!(
+                    "warning: large object {} not found, keeping pointer file",
+                    pointer.oid
+                );
+                stdout.write_all(pointer.encode().as_bytes())?;
+            }
+        }
+        // Files that were committed before the filter was enabled aren't pointers.
+        (Some("smudge"), None) => {
+            stdout.write_all(&head)?;
+            io::copy(&mut stdin, &mut stdout)?;
+        }
+        _ => {
+            return Err(Error::InvalidArguments(args).into());
+        }
+    }
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Fetch a large object from the seeds our node is connected to.
+///
+/// If the object can't be found, it stays wanted, and is fetched along with the repository
+/// the next time it is fetched.
+fn fetch(
+    repo: &Repository,
+    profile: &radicle::Profile,
+    pointer: &Pointer,
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    repo.want_large_object(&pointer.oid)?;
+
+    let mut node = radicle::Node::new(profile.socket());
+    if !node.is_running() {
+        return Ok(());
+    }
+    let seeds = node
+        .seeds(repo.id)?
+        .connected()
+        .copied()
+        .collect::<Vec<_>>();
+
+    for seed in seeds {
+        eprintln!("Fetching large object {} from {seed}..", pointer.oid);
+
+        node.fetch(repo.id, seed)?;
+        if repo.large_object(&pointer.oid)?.is_some() {
+            repo.unwant_large_object(&pointer.oid)?;
+            break;
+        }
+    }
+    Ok(())
+}
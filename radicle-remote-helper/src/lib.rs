@@ -1,13 +1,16 @@
 #![allow(clippy::collapsible_if)]
+pub mod lfs;
+
 use std::path::PathBuf;
 use std::{env, io, process};
 
 use thiserror::Error;
 
 use radicle::crypto::PublicKey;
+use radicle::identity::Id;
 use radicle::node::Handle;
 use radicle::storage::git::transport::local::{Url, UrlError};
-use radicle::storage::{ReadRepository, WriteRepository, WriteStorage};
+use radicle::storage::{CanonicalRef, ReadRepository, WriteRepository, WriteStorage};
 
 /// The service invoked by git on the remote repository, during a push.
 const GIT_RECEIVE_PACK: &str = "git-receive-pack";
@@ -31,6 +34,9 @@ pub enum Error {
     /// Invalid arguments received.
     #[error("invalid arguments: {0:?}")]
     InvalidArguments(Vec<String>),
+    /// The project is archived, and we aren't a delegate.
+    #[error("project `{0}` is archived, only delegates can push to it")]
+    Archived(Id),
     /// Error with the remote url.
     #[error("invalid remote url: {0}")]
     RemoteUrl(#[from] UrlError),
@@ -82,10 +88,15 @@ pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error +
                 // 1. Our key is not in ssh-agent, which means we won't be able to sign the refs.
                 // 2. Our key is not the one loaded in the profile, which means that the signed refs
                 //    won't match the remote we're pushing to.
+                // 3. The project is archived and we're not a delegate.
                 let signer = if *service == GIT_RECEIVE_PACK {
                     if profile.public_key != namespace {
                         return Err(Error::KeyMismatch(profile.public_key).into());
                     }
+                    let doc = proj.identity()?.doc;
+                    if doc.archived().is_some() && !doc.is_delegate(&namespace) {
+                        return Err(Error::Archived(url.repo).into());
+                    }
                     let signer = profile.signer()?;
 
                     Some(signer)
@@ -111,6 +122,13 @@ pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error +
                     if let Some(signer) = signer {
                         proj.sign_refs(&signer)?;
                         proj.set_head()?;
+                        // Show how our branches diverge from the canonical ones.
+                        for r in proj.set_canonical_refs()? {
+                            if !r.heads.iter().any(|(remote, _)| *remote == namespace) {
+                                continue;
+                            }
+                            divergence(&proj, &namespace, &r)?;
+                        }
                         // Connect to local node and announce refs to the network.
                         // If our node is not running, we simply skip this step, as the
                         // refs will be announced eventually, when the node restarts.
@@ -135,3 +153,31 @@ pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error +
 
     Ok(())
 }
+
+/// Print the divergence of a remote's reference from its canonical target.
+fn divergence<R: WriteRepository>(
+    repo: &R,
+    remote: &PublicKey,
+    r: &CanonicalRef,
+) -> Result<(), radicle::git::raw::Error> {
+    let Some((_, head)) = r.heads.iter().find(|(k, _)| k == remote) else {
+        return Ok(());
+    };
+    match r.target {
+        None => {
+            eprintln!(
+                "warning: `{}` has no canonical target, quorum not reached",
+                r.name
+            );
+        }
+        Some(target) if target == *head || r.is_tag() => {}
+        Some(target) => {
+            let (ahead, behind) = repo.raw().graph_ahead_behind(**head, *target)?;
+            eprintln!(
+                "`{}` is {ahead} commit(s) ahead and {behind} commit(s) behind its canonical target {target}",
+                r.name
+            );
+        }
+    }
+    Ok(())
+}
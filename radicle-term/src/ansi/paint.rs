@@ -253,6 +253,15 @@ impl Paint<()> {
     pub fn is_enabled() -> bool {
         concolor::get(concolor::Stream::Stdout).ansi_color()
     }
+
+    /// Force coloring on or off, regardless of the output stream.
+    pub fn force(enabled: bool) {
+        if enabled {
+            concolor::set(concolor::ColorChoice::Always);
+        } else {
+            concolor::set(concolor::ColorChoice::Never);
+        }
+    }
 }
 
 /// Shorthand for [`Paint::new`].
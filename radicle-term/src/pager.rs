@@ -0,0 +1,39 @@
+use std::io::Write;
+use std::process;
+use std::{env, io};
+
+/// Output the given text through the configured pager, if standard output is a terminal.
+/// Otherwise, or if no pager is configured, the text is printed as-is.
+pub fn page(text: &str) -> io::Result<()> {
+    let stdout = io::stdout();
+    let pager = self::default_pager().filter(|_| termion::is_tty(&stdout));
+
+    let Some(pager) = pager else {
+        return stdout.lock().write_all(text.as_bytes());
+    };
+    // Like git, run the pager through the shell, so that it can be given arguments.
+    let mut child = process::Command::new("sh")
+        .arg("-c")
+        .arg(pager)
+        .stdin(process::Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager exits early if it's quit before reaching the end of the text.
+        match stdin.write_all(text.as_bytes()) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+    child.wait()?;
+
+    Ok(())
+}
+
+/// Get the default pager command.
+pub fn default_pager() -> Option<String> {
+    match env::var("PAGER") {
+        Ok(pager) if !pager.trim().is_empty() => Some(pager),
+        _ => None,
+    }
+}
@@ -15,7 +15,7 @@ use crate::{
         common::Timestamp,
         store::{self, FromHistory as _, Transaction},
     },
-    identity::{doc::DocError, Did, Identity, IdentityError},
+    identity::{doc::DocError, Did, Identity, IdentityError, Permission},
     prelude::Doc,
     storage::{git as storage, RemoteId, WriteRepository},
 };
@@ -68,6 +68,26 @@ pub enum Action {
     },
 }
 
+impl Action {
+    /// The permission required to carry out this action, if any.
+    ///
+    /// * [`Action::Accept`]: [`Permission::Identity`], including for the proposal author.
+    /// * [`Action::Reject`]: [`Permission::Identity`], including for the proposal author.
+    /// * [`Action::Commit`]: [`Permission::Identity`], including for the proposal author.
+    /// * [`Action::Close`]: [`Permission::Triage`], unless carried out by the proposal author.
+    /// * [`Action::Edit`]: [`Permission::Triage`], unless carried out by the proposal author.
+    /// * [`Action::Redact`]: [`Permission::Triage`], unless carried out by the proposal author.
+    /// * [`Action::Revision`]: none, anyone can propose a revision.
+    /// * [`Action::Thread`]: none, anyone can comment.
+    pub fn permission(&self) -> Option<Permission> {
+        match self {
+            Self::Accept { .. } | Self::Reject { .. } | Self::Commit => Some(Permission::Identity),
+            Self::Close | Self::Edit { .. } | Self::Redact { .. } => Some(Permission::Triage),
+            Self::Revision { .. } | Self::Thread { .. } => None,
+        }
+    }
+}
+
 /// Error applying an operation onto a state.
 #[derive(Error, Debug)]
 pub enum ApplyError {
@@ -125,6 +145,8 @@ pub enum Error {
     Apply(#[from] ApplyError),
     #[error("store: {0}")]
     Store(#[from] store::Error),
+    #[error("actor {0} lacks the `{1}` permission")]
+    Unauthorized(PublicKey, Permission),
 }
 
 /// Propose a new [`Doc`] for an [`Identity`]. The proposal can be
@@ -145,7 +167,7 @@ pub struct Proposal {
     /// List of revisions for this proposal.
     revisions: GMap<RevisionId, Redactable<Revision>>,
     /// Timeline of events.
-    timeline: GSet<(clock::Lamport, EntryId)>,
+    timeline: GSet<(clock::Hybrid, EntryId)>,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -310,7 +332,7 @@ impl store::FromHistory for Proposal {
             let author = Author::new(op.author);
             let timestamp = op.timestamp;
 
-            self.timeline.insert((op.clock, id));
+            self.timeline.insert((op.hybrid, id));
 
             match op.action {
                 Action::Accept {
@@ -362,6 +384,7 @@ impl store::FromHistory for Proposal {
                             op.author,
                             op.timestamp,
                             op.clock,
+                            op.hybrid,
                         )])?
                     }
                     Some(Redactable::Redacted) => return Err(ApplyError::Redacted(revision)),
@@ -372,6 +395,18 @@ impl store::FromHistory for Proposal {
 
         Ok(())
     }
+
+    fn authorize(
+        &self,
+        op: &Op,
+        author: &Did,
+        identity: &Doc<Verified>,
+    ) -> Result<(), store::Unauthorized> {
+        match op.action.permission() {
+            Some(permission) => store::authorize(&op.author, author, permission, identity),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -586,6 +621,16 @@ impl<'a, 'g> ProposalMut<'a, 'g> {
         &self.clock
     }
 
+    /// Check that an actor is allowed to perform an action that requires the given
+    /// permission. Only delegates of the current identity can accept, reject or
+    /// commit proposals.
+    pub fn authorize(&self, actor: &PublicKey, permission: Permission) -> Result<(), Error> {
+        if self.store.raw.identity().is_authorized(actor, permission) {
+            return Ok(());
+        }
+        Err(Error::Unauthorized(*actor, permission))
+    }
+
     /// Accept a proposal revision.
     pub fn accept<G: Signer>(
         &mut self,
@@ -593,6 +638,7 @@ impl<'a, 'g> ProposalMut<'a, 'g> {
         signature: Signature,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Identity)?;
         self.transaction("Accept", signer, |tx| tx.accept(revision, signature))
     }
 
@@ -602,6 +648,7 @@ impl<'a, 'g> ProposalMut<'a, 'g> {
         revision: RevisionId,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Identity)?;
         self.transaction("Reject", signer, |tx| tx.reject(revision))
     }
 
@@ -617,6 +664,7 @@ impl<'a, 'g> ProposalMut<'a, 'g> {
 
     /// Commit a proposal.
     pub fn commit<G: Signer>(&mut self, signer: &G) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Identity)?;
         self.transaction("Commit", signer, |tx| tx.push(Action::Commit))
     }
 
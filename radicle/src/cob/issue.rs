@@ -15,7 +15,8 @@ use crate::cob::store::Transaction;
 use crate::cob::thread;
 use crate::cob::thread::{CommentId, Thread};
 use crate::cob::{store, ActorId, EntryId, ObjectId, TypeName};
-use crate::crypto::Signer;
+use crate::crypto::{Signer, Verified};
+use crate::identity::{project, Doc, Permission};
 use crate::prelude::Did;
 use crate::storage::git as storage;
 
@@ -38,6 +39,8 @@ pub enum Error {
     Thread(#[from] thread::OpError),
     #[error("store: {0}")]
     Store(#[from] store::Error),
+    #[error("actor {0} lacks the `{1}` permission")]
+    Unauthorized(ActorId, Permission),
 }
 
 /// Reason why an issue was closed.
@@ -122,6 +125,10 @@ impl store::FromHistory for Issue {
         &*TYPENAME
     }
 
+    fn policy(metadata: &project::Metadata) -> project::Policy {
+        metadata.issue_policy
+    }
+
     fn apply(&mut self, ops: impl IntoIterator<Item = Op>) -> Result<(), Error> {
         for op in ops {
             match op.action {
@@ -154,12 +161,25 @@ impl store::FromHistory for Issue {
                         op.author,
                         op.timestamp,
                         op.clock,
+                        op.hybrid,
                     )])?;
                 }
             }
         }
         Ok(())
     }
+
+    fn authorize(
+        &self,
+        op: &Op,
+        author: &Did,
+        identity: &Doc<Verified>,
+    ) -> Result<(), store::Unauthorized> {
+        match op.action.permission() {
+            Some(permission) => store::authorize(&op.author, author, permission, identity),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Issue {
@@ -309,16 +329,19 @@ impl<'a, 'g> IssueMut<'a, 'g> {
         assignees: impl IntoIterator<Item = ActorId>,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Triage)?;
         self.transaction("Assign", signer, |tx| tx.assign(assignees, []))
     }
 
     /// Set the issue title.
     pub fn edit<G: Signer>(&mut self, title: impl ToString, signer: &G) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Triage)?;
         self.transaction("Edit", signer, |tx| tx.edit(title))
     }
 
     /// Lifecycle an issue.
     pub fn lifecycle<G: Signer>(&mut self, state: State, signer: &G) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Triage)?;
         self.transaction("Lifecycle", signer, |tx| tx.lifecycle(state))
     }
 
@@ -352,6 +375,7 @@ impl<'a, 'g> IssueMut<'a, 'g> {
         remove: impl IntoIterator<Item = Tag>,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Triage)?;
         self.transaction("Tag", signer, |tx| tx.tag(add, remove))
     }
 
@@ -371,10 +395,23 @@ impl<'a, 'g> IssueMut<'a, 'g> {
         assignees: impl IntoIterator<Item = ActorId>,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Triage)?;
         self.transaction("Unassign", signer, |tx| tx.assign([], assignees))
             .map_err(Error::from)
     }
 
+    /// Check that an actor is allowed to perform an action that requires the given
+    /// permission. See [`store::authorize`] for the rules.
+    pub fn authorize(&self, actor: &ActorId, permission: Permission) -> Result<(), Error> {
+        store::authorize(
+            actor,
+            self.author().id(),
+            permission,
+            self.store.raw.identity(),
+        )
+        .map_err(|e| Error::Unauthorized(e.actor, e.permission))
+    }
+
     pub fn transaction<G, F>(
         &mut self,
         message: &str,
@@ -526,6 +563,24 @@ pub enum Action {
     },
 }
 
+impl Action {
+    /// The permission required to carry out this action, if any.
+    ///
+    /// * [`Action::Assign`]: [`Permission::Triage`], unless carried out by the issue author.
+    /// * [`Action::Edit`]: [`Permission::Triage`], unless carried out by the issue author.
+    /// * [`Action::Lifecycle`]: [`Permission::Triage`], unless carried out by the issue author.
+    /// * [`Action::Tag`]: [`Permission::Triage`], unless carried out by the issue author.
+    /// * [`Action::Thread`]: none, anyone can comment and react.
+    pub fn permission(&self) -> Option<Permission> {
+        match self {
+            Self::Assign { .. } | Self::Edit { .. } | Self::Lifecycle { .. } | Self::Tag { .. } => {
+                Some(Permission::Triage)
+            }
+            Self::Thread { .. } => None,
+        }
+    }
+}
+
 impl From<thread::Action> for Action {
     fn from(action: thread::Action) -> Self {
         Self::Thread { action }
@@ -538,6 +593,8 @@ mod test {
 
     use super::*;
     use crate::cob::Reaction;
+    use crate::crypto::test::signer::MockSigner;
+    use crate::identity::doc::{PayloadId, Role};
     use crate::test;
     use crate::test::arbitrary;
 
@@ -675,6 +732,322 @@ mod test {
         assert_eq!(*issue.state(), State::Open);
     }
 
+    #[test]
+    fn test_issue_unauthorized() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let bob = MockSigner::default();
+        let mut issues = Issues::open(&project).unwrap();
+        let mut issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+
+        let err = issue.lifecycle(State::Open, &bob).unwrap_err();
+        assert!(matches!(err, Error::Unauthorized(_, Permission::Triage)));
+
+        let err = issue.tag([], [], &bob).unwrap_err();
+        assert!(matches!(err, Error::Unauthorized(_, Permission::Triage)));
+
+        issue.lifecycle(State::Open, &signer).unwrap();
+    }
+
+    #[test]
+    fn test_issue_unauthorized_ops_ignored() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let bob = MockSigner::default();
+        let mut issues = Issues::open(&project).unwrap();
+        let mut issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+        let id = issue.id;
+        let (root, _) = issue.root();
+        let root = *root;
+
+        // Bypass the checks of `IssueMut::lifecycle`, as a misbehaving peer would.
+        issue
+            .transaction("Lifecycle", &bob, |tx| {
+                tx.lifecycle(State::Closed {
+                    reason: CloseReason::Other,
+                })
+            })
+            .unwrap();
+        issue
+            .transaction("Comment", &bob, |tx| tx.comment("Closing.", root))
+            .unwrap();
+
+        let issue = issues.get(&id).unwrap().unwrap();
+        assert_eq!(*issue.state(), State::Open);
+        assert_eq!(issue.comments().count(), 2);
+    }
+
+    #[test]
+    fn test_issue_authorized_at_the_time() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let bob = MockSigner::default();
+        let bug = Tag::new("bug").unwrap();
+        let update = |msg: &str, f: &dyn Fn(&mut Doc<Verified>) -> bool| {
+            let (_, doc) = project.identity_doc().unwrap();
+            let mut doc = doc.verified().unwrap();
+
+            assert!(f(&mut doc));
+            doc.sign(&signer)
+                .and_then(|(_, sig)| {
+                    doc.update(
+                        signer.public_key(),
+                        msg,
+                        &[(signer.public_key(), sig)],
+                        project.raw(),
+                    )
+                })
+                .unwrap();
+        };
+
+        let mut issues = Issues::open(&project).unwrap();
+        let issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+        let id = issue.id;
+
+        // Bob triages the issue while he is a triager.
+        update("Add triager", &|doc| {
+            doc.assign(bob.public_key(), Role::Triager)
+        });
+        let mut issues = Issues::open(&project).unwrap();
+        let mut issue = issues.get_mut(&id).unwrap();
+        issue.tag([bug.clone()], [], &bob).unwrap();
+        let (root, _) = issue.root();
+        let root = *root;
+        issue.comment("Thanks.", root, &signer).unwrap();
+
+        // Once he no longer is, his changes are ignored, but the earlier ones remain,
+        // since a delegate built on them.
+        update("Remove triager", &|doc| {
+            doc.revoke(bob.public_key(), Role::Triager)
+        });
+        let mut issues = Issues::open(&project).unwrap();
+        let mut issue = issues.get_mut(&id).unwrap();
+        let entry = issue
+            .transaction("Lifecycle", &bob, |tx| {
+                tx.lifecycle(State::Closed {
+                    reason: CloseReason::Other,
+                })
+            })
+            .unwrap();
+
+        let (issue, _, rejected) = issues.get_with_rejected(&id).unwrap().unwrap();
+        assert_eq!(issue.tags().collect::<Vec<_>>(), vec![&bug]);
+        assert_eq!(*issue.state(), State::Open);
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].entry, entry);
+        assert!(matches!(
+            rejected[0].reason,
+            store::Rejection::Unauthorized(store::Unauthorized {
+                permission: Permission::Triage,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_issue_revoked_backdated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let bob = MockSigner::default();
+        let update = |msg: &str, f: &dyn Fn(&mut Doc<Verified>) -> bool| {
+            let (_, doc) = project.identity_doc().unwrap();
+            let mut doc = doc.verified().unwrap();
+
+            assert!(f(&mut doc));
+            doc.sign(&signer)
+                .and_then(|(_, sig)| {
+                    doc.update(
+                        signer.public_key(),
+                        msg,
+                        &[(signer.public_key(), sig)],
+                        project.raw(),
+                    )
+                })
+                .unwrap();
+        };
+
+        let mut issues = Issues::open(&project).unwrap();
+        let issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+        let id = issue.id;
+
+        update("Add triager", &|doc| {
+            doc.assign(bob.public_key(), Role::Triager)
+        });
+        let triager = project.identity_head().unwrap();
+        update("Remove triager", &|doc| {
+            doc.revoke(bob.public_key(), Role::Triager)
+        });
+
+        // Bob refers to the identity under which he was still a triager.
+        let action = Action::Lifecycle {
+            state: State::Closed {
+                reason: CloseReason::Other,
+            },
+        };
+        cob::update(
+            &project,
+            &bob,
+            triager,
+            bob.public_key(),
+            cob::Update {
+                object_id: id,
+                history_type: store::HISTORY_TYPE.to_owned(),
+                typename: TYPENAME.clone(),
+                message: String::from("Lifecycle"),
+                changes: nonempty::NonEmpty::new(store::encoding::encode(action).unwrap()),
+            },
+        )
+        .unwrap();
+
+        let issues = Issues::open(&project).unwrap();
+        let (issue, _, rejected) = issues.get_with_rejected(&id).unwrap().unwrap();
+        assert_eq!(*issue.state(), State::Open);
+        assert!(matches!(
+            rejected.as_slice(),
+            [store::Rejected {
+                reason: store::Rejection::Unauthorized(store::Unauthorized {
+                    permission: Permission::Triage,
+                    ..
+                }),
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_issue_archived() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let bob = MockSigner::default();
+        let mut issues = Issues::open(&project).unwrap();
+        let mut issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+        let id = issue.id;
+        let (root, _) = issue.root();
+        let root = *root;
+
+        issue.comment("Before archival.", root, &bob).unwrap();
+        issue.comment("Acknowledged.", root, &signer).unwrap();
+        let unarchived = project.identity_head().unwrap();
+
+        // Archive the project.
+        let (_, doc) = project.identity_doc().unwrap();
+        let mut doc = doc.verified().unwrap();
+        let mut proj = doc.project().unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        proj.set_archived(Some(now));
+        doc.payload.insert(PayloadId::project(), proj.into());
+        doc.sign(&signer)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    signer.public_key(),
+                    "Archive project",
+                    &[(signer.public_key(), sig)],
+                    project.raw(),
+                )
+            })
+            .unwrap();
+
+        let mut issues = Issues::open(&project).unwrap();
+        assert!(matches!(
+            issues.create("Another issue", "Blah blah blah.", &[], &[], &bob),
+            Err(Error::Store(store::Error::Archived))
+        ));
+
+        let mut issue = issues.get_mut(&id).unwrap();
+        assert!(matches!(
+            issue.comment("After archival.", root, &bob),
+            Err(Error::Store(store::Error::Archived))
+        ));
+        issue
+            .comment("Delegates can still comment.", root, &signer)
+            .unwrap();
+
+        // Changes made once the project is archived are rejected, whatever identity they
+        // refer to.
+        let action = Action::from(thread::Action::Comment {
+            body: String::from("Bypassing the store."),
+            reply_to: Some(root),
+        });
+        cob::update(
+            &project,
+            &bob,
+            unarchived,
+            bob.public_key(),
+            cob::Update {
+                object_id: id,
+                history_type: store::HISTORY_TYPE.to_owned(),
+                typename: TYPENAME.clone(),
+                message: String::from("Comment"),
+                changes: nonempty::NonEmpty::new(store::encoding::encode(action).unwrap()),
+            },
+        )
+        .unwrap();
+
+        let (issue, _, rejected) = issues.get_with_rejected(&id).unwrap().unwrap();
+        assert_eq!(issue.comments().count(), 4);
+        assert!(matches!(
+            rejected.as_slice(),
+            [store::Rejected {
+                reason: store::Rejection::Archived,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_issue_policy() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let bob = MockSigner::default();
+
+        // Only allow delegates to open issues.
+        let (_, doc) = project.identity_doc().unwrap();
+        let mut doc = doc.verified().unwrap();
+        let proj = doc.project().unwrap();
+        let metadata = project::Metadata {
+            issue_policy: project::Policy::Delegates,
+            ..proj.metadata().clone()
+        };
+        let proj = proj.with_metadata(metadata).unwrap();
+        doc.payload.insert(PayloadId::project(), proj.into());
+        doc.sign(&signer)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    signer.public_key(),
+                    "Restrict issues",
+                    &[(signer.public_key(), sig)],
+                    project.raw(),
+                )
+            })
+            .unwrap();
+
+        let mut issues = Issues::open(&project).unwrap();
+        assert!(matches!(
+            issues.create("Bob's issue", "Blah blah blah.", &[], &[], &bob),
+            Err(Error::Store(store::Error::Policy(
+                _,
+                project::Policy::Delegates
+            )))
+        ));
+        issues
+            .create("Alice's issue", "Blah blah blah.", &[], &[], &signer)
+            .unwrap();
+        assert_eq!(issues.all().unwrap().count(), 1);
+    }
+
     #[test]
     fn test_issue_create_and_unassign() {
         let tmp = tempfile::tempdir().unwrap();
@@ -34,6 +34,8 @@ pub struct Op<A> {
     pub clock: Lamport,
     /// Timestamp of this operation.
     pub timestamp: clock::Physical,
+    /// Hybrid logical clock. Used to order operations by time, consistently with causality.
+    pub hybrid: clock::Hybrid,
 }
 
 impl<A: Eq> PartialOrd for Op<A> {
@@ -55,6 +57,7 @@ impl<A> Op<A> {
         author: ActorId,
         timestamp: impl Into<clock::Physical>,
         clock: Lamport,
+        hybrid: clock::Hybrid,
     ) -> Self {
         Self {
             id,
@@ -62,6 +65,7 @@ impl<A> Op<A> {
             author,
             clock,
             timestamp: timestamp.into(),
+            hybrid,
         }
     }
 
@@ -90,6 +94,7 @@ where
                     author: *entry.actor(),
                     clock: entry.clock().into(),
                     timestamp: entry.timestamp().into(),
+                    hybrid: entry.hybrid(),
                 };
                 Ok::<_, Self::Error>(op)
             })
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use radicle_crdt::clock;
-use radicle_crdt::{GMap, GSet, LWWReg, LWWSet, Lamport, Max, Redactable, Semilattice};
+use radicle_crdt::{GMap, GSet, LWWReg, LWWSet, Max, Redactable, Semilattice};
 
 use crate::cob;
 use crate::cob::common::{Author, Tag, Timestamp};
@@ -23,6 +23,7 @@ use crate::cob::thread::Thread;
 use crate::cob::{store, ActorId, EntryId, ObjectId, TypeName};
 use crate::crypto::{PublicKey, Signer};
 use crate::git;
+use crate::identity::{project, Permission};
 use crate::prelude::*;
 use crate::storage::git as storage;
 
@@ -69,6 +70,8 @@ pub enum Error {
     Apply(#[from] ApplyError),
     #[error("store: {0}")]
     Store(#[from] store::Error),
+    #[error("actor {0} lacks the `{1}` permission")]
+    Unauthorized(ActorId, Permission),
 }
 
 /// Patch operation.
@@ -108,6 +111,26 @@ pub enum Action {
     },
 }
 
+impl Action {
+    /// The permission required to carry out this action, if any.
+    ///
+    /// * [`Action::Edit`]: [`Permission::Triage`], unless carried out by the patch author.
+    /// * [`Action::Tag`]: [`Permission::Triage`], unless carried out by the patch author.
+    /// * [`Action::Redact`]: [`Permission::Triage`], unless carried out by the patch author.
+    /// * [`Action::Review`]: [`Permission::Review`] if a verdict is given, otherwise none.
+    /// * [`Action::Merge`]: [`Permission::Merge`], including for the patch author.
+    /// * [`Action::Revision`]: none, anyone can propose a revision.
+    /// * [`Action::Thread`]: none, anyone can comment.
+    pub fn permission(&self) -> Option<Permission> {
+        match self {
+            Self::Edit { .. } | Self::Tag { .. } | Self::Redact { .. } => Some(Permission::Triage),
+            Self::Review { verdict, .. } => verdict.map(|_| Permission::Review),
+            Self::Merge { .. } => Some(Permission::Merge),
+            Self::Revision { .. } | Self::Thread { .. } => None,
+        }
+    }
+}
+
 /// Where a patch is intended to be merged.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -136,7 +159,7 @@ pub struct Patch {
     /// first revision.
     pub revisions: GMap<RevisionId, Redactable<Revision>>,
     /// Timeline of operations.
-    pub timeline: GSet<(Lamport, EntryId)>,
+    pub timeline: GSet<(clock::Hybrid, EntryId)>,
 }
 
 impl Semilattice for Patch {
@@ -247,13 +270,17 @@ impl store::FromHistory for Patch {
         &*TYPENAME
     }
 
+    fn policy(metadata: &project::Metadata) -> project::Policy {
+        metadata.patch_policy
+    }
+
     fn apply(&mut self, ops: impl IntoIterator<Item = Op>) -> Result<(), ApplyError> {
         for op in ops {
             let id = op.id;
             let author = Author::new(op.author);
             let timestamp = op.timestamp;
 
-            self.timeline.insert((op.clock, id));
+            self.timeline.insert((op.hybrid, id));
 
             match op.action {
                 Action::Edit {
@@ -336,9 +363,9 @@ impl store::FromHistory for Patch {
                     // TODO(cloudhead): Make sure we can deal with redacted revisions which are added
                     // to out of order, like in the `Merge` case.
                     if let Some(Redactable::Present(revision)) = self.revisions.get_mut(&revision) {
-                        revision
-                            .discussion
-                            .apply([cob::Op::new(op.id, action, op.author, timestamp, op.clock)])?;
+                        revision.discussion.apply([cob::Op::new(
+                            op.id, action, op.author, timestamp, op.clock, op.hybrid,
+                        )])?;
                     } else {
                         return Err(ApplyError::Missing(revision));
                     }
@@ -347,6 +374,18 @@ impl store::FromHistory for Patch {
         }
         Ok(())
     }
+
+    fn authorize(
+        &self,
+        op: &Op,
+        author: &Did,
+        identity: &Doc<Verified>,
+    ) -> Result<(), store::Unauthorized> {
+        match op.action.permission() {
+            Some(permission) => store::authorize(&op.author, author, permission, identity),
+            None => Ok(()),
+        }
+    }
 }
 
 /// A patch revision.
@@ -726,6 +765,18 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         &self.clock
     }
 
+    /// Check that an actor is allowed to perform an action that requires the given
+    /// permission. See [`store::authorize`] for the rules.
+    pub fn authorize(&self, actor: &ActorId, permission: Permission) -> Result<(), Error> {
+        store::authorize(
+            actor,
+            self.author().id(),
+            permission,
+            self.store.raw.identity(),
+        )
+        .map_err(|e| Error::Unauthorized(e.actor, e.permission))
+    }
+
     /// Edit patch metadata.
     pub fn edit<G: Signer>(
         &mut self,
@@ -734,6 +785,7 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         target: MergeTarget,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Triage)?;
         self.transaction("Edit", signer, |tx| tx.edit(title, description, target))
     }
 
@@ -767,6 +819,9 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         inline: Vec<CodeComment>,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        if verdict.is_some() {
+            self.authorize(signer.public_key(), Permission::Review)?;
+        }
         self.transaction("Review", signer, |tx| {
             tx.review(revision, verdict, comment, inline)
         })
@@ -779,6 +834,7 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         commit: git::Oid,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Merge)?;
         self.transaction("Merge revision", signer, |tx| tx.merge(revision, commit))
     }
 
@@ -802,6 +858,7 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         remove: impl IntoIterator<Item = Tag>,
         signer: &G,
     ) -> Result<EntryId, Error> {
+        self.authorize(signer.public_key(), Permission::Triage)?;
         self.transaction("Tag", signer, |tx| tx.tag(add, remove))
     }
 }
@@ -908,7 +965,7 @@ impl<'a> Patches<'a> {
     /// Get proposed patches.
     pub fn proposed(
         &self,
-    ) -> Result<impl Iterator<Item = (PatchId, Patch, clock::Lamport)>, Error> {
+    ) -> Result<impl Iterator<Item = (PatchId, Patch, clock::Lamport)> + '_, Error> {
         let all = self.all()?;
 
         Ok(all
@@ -1,6 +1,7 @@
 //! Generic COB storage.
 #![allow(clippy::large_enum_variant)]
 #![allow(clippy::type_complexity)]
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::ops::ControlFlow;
 
@@ -11,6 +12,8 @@ use serde::{Deserialize, Serialize};
 use crate::cob::op::{Op, Ops};
 use crate::cob::{ActorId, Create, EntryId, History, ObjectId, TypeName, Update, Updated};
 use crate::git;
+use crate::identity::project::{Metadata, Policy};
+use crate::identity::Permission;
 use crate::prelude::*;
 use crate::storage::git as storage;
 use crate::{cob, identity};
@@ -29,18 +32,160 @@ pub trait FromHistory: Sized + Default {
     /// The object type name.
     fn type_name() -> &'static TypeName;
 
+    /// The project policy governing who can create objects of this type.
+    /// By default, anyone can create them.
+    fn policy(_metadata: &Metadata) -> Policy {
+        Policy::Open
+    }
+
     /// Apply a list of operations to the state.
     fn apply(&mut self, ops: impl IntoIterator<Item = Op<Self::Action>>)
         -> Result<(), Self::Error>;
 
-    /// Create an object from a history.
-    fn from_history(history: &History) -> Result<(Self, Lamport), Error> {
+    /// Check that the author of an operation is allowed to carry it out, given the
+    /// current state, the object author and the repository identity document.
+    ///
+    /// Operations that aren't authorized are skipped when the object is created from
+    /// its history. By default, all operations are authorized.
+    fn authorize(
+        &self,
+        _op: &Op<Self::Action>,
+        _author: &Did,
+        _identity: &Doc<Verified>,
+    ) -> Result<(), Unauthorized> {
+        Ok(())
+    }
+
+    /// Create an object from a history, authorizing operations against the identity
+    /// document. See [`Identities`].
+    ///
+    /// Each entry is judged by the identity it refers to, or by a later one if it builds
+    /// on entries judged by that later identity. Since the identity an entry refers to
+    /// is chosen by its author, operations must also be allowed by the current identity,
+    /// unless a current delegate built on them under an identity that allows them: this
+    /// keeps changes made by actors who have since lost their roles, as long as they
+    /// were acknowledged, while changes backdated to before a revocation are skipped.
+    ///
+    /// The author of the object is the author of the root entry, whose operations
+    /// are always authorized. Objects whose author isn't allowed to create them by the
+    /// project [`Policy`] are rejected. Objects created by non-delegates on an archived
+    /// project are rejected, and operations made by non-delegates on such a project are
+    /// skipped, by the same rules.
+    ///
+    /// Skipped operations are returned along with the object.
+    fn from_history<I: Identities>(
+        history: &History,
+        identities: &I,
+    ) -> Result<(Self, Lamport, Vec<Rejected>), Error> {
+        let identity = identities.current();
+        let root = history.root();
+        let author = Did::from(root.actor());
+        let mut rejected = Vec::new();
+
+        let order = history.traverse(Vec::new(), |mut order, entry| {
+            order.push(*entry.id());
+            ControlFlow::Continue(order)
+        });
+        // The identity commit each entry is judged by: the one it refers to, unless one
+        // of the entries it depends on was judged by a later identity.
+        let mut judged: HashMap<EntryId, git::Oid> = HashMap::new();
+        for id in &order {
+            let Some(entry) = history.entry(id) else {
+                continue;
+            };
+            let mut resource = entry.resource();
+            for dependency in history.dependencies(id) {
+                if let Some(other) = judged.get(dependency) {
+                    if identities.is_after(*other, resource) {
+                        resource = *other;
+                    }
+                }
+            }
+            judged.insert(*id, resource);
+        }
+        // The earliest identity commit under which a current delegate built on each entry.
+        let mut acks: HashMap<EntryId, git::Oid> = HashMap::new();
+        for id in order.iter().rev() {
+            let mut ack = history
+                .entry(id)
+                .filter(|entry| identity.is_delegate(entry.actor()))
+                .and_then(|_| judged.get(id).copied());
+            for dependent in history.dependents(id) {
+                if let Some(other) = acks.get(dependent) {
+                    ack = match ack {
+                        Some(ack) if identities.is_after(*other, ack) => Some(ack),
+                        _ => Some(*other),
+                    };
+                }
+            }
+            if let Some(ack) = ack {
+                acks.insert(*id, ack);
+            }
+        }
+        // Entries referring to an identity outside of the identity history are judged by
+        // the current identity.
+        let mut docs: HashMap<git::Oid, Option<Doc<Verified>>> = HashMap::new();
+        for oid in judged.values().chain(acks.values()) {
+            docs.entry(*oid).or_insert_with(|| identities.at(*oid));
+        }
+        let judgement = |id: &EntryId| Judgement {
+            judged: judged
+                .get(id)
+                .and_then(|oid| docs.get(oid))
+                .and_then(|doc| doc.as_ref())
+                .unwrap_or(identity),
+            acknowledged: acks
+                .get(id)
+                .and_then(|oid| docs.get(oid))
+                .and_then(|doc| doc.as_ref()),
+            current: identity,
+        };
+
+        let root_judgement = judgement(root.id());
+        root_judgement.check(|doc| writable(root.actor(), doc))?;
+        root_judgement.check(|doc| permitted::<Self>(root.actor(), doc))?;
+
         let obj = history.traverse(Self::default(), |mut acc, entry| {
+            let judgement = judgement(entry.id());
+
             match Ops::try_from(entry) {
                 Ok(Ops(ops)) => {
-                    if let Err(err) = acc.apply(ops) {
-                        log::warn!("Error applying op to `{}` state: {err}", Self::type_name());
-                        return ControlFlow::Break(acc);
+                    for op in ops {
+                        if judgement.check(|doc| writable(&op.author, doc)).is_err() {
+                            log::warn!(
+                                "Rejecting op {} to `{}` state: project is archived",
+                                op.id,
+                                Self::type_name()
+                            );
+                            rejected.push(Rejected {
+                                entry: op.id,
+                                author: op.author,
+                                reason: Rejection::Archived,
+                            });
+                            continue;
+                        }
+                        // The object creator can carry out any action.
+                        if op.id != *root.id() {
+                            if let Err(err) =
+                                judgement.check(|doc| acc.authorize(&op, &author, doc))
+                            {
+                                log::warn!(
+                                    "Rejecting op {} to `{}` state: {err}",
+                                    op.id,
+                                    Self::type_name()
+                                );
+                                rejected.push(Rejected {
+                                    entry: op.id,
+                                    author: op.author,
+                                    reason: Rejection::Unauthorized(err),
+                                });
+                                continue;
+                            }
+                        }
+                        if let Err(err) = acc.apply([op]) {
+                            log::warn!("Error applying op to `{}` state: {err}", Self::type_name());
+                            return ControlFlow::Break(acc);
+                        }
                     }
                 }
                 Err(err) => {
@@ -54,7 +199,7 @@ pub trait FromHistory: Sized + Default {
             ControlFlow::Continue(acc)
         });
 
-        Ok((obj, history.clock().into()))
+        Ok((obj, history.clock().into(), rejected))
     }
 
     /// Create an object from individual operations.
@@ -67,6 +212,183 @@ pub trait FromHistory: Sized + Default {
     }
 }
 
+/// Identity documents in force when the operations of an object were made.
+///
+/// Each change of a collaborative object refers to the identity commit that was current
+/// when it was made. See [`FromHistory::from_history`] for how operations are authorized.
+pub trait Identities {
+    /// The current identity document.
+    fn current(&self) -> &Doc<Verified>;
+    /// Get the identity document at the given identity commit. Returns `None` if the
+    /// commit isn't part of the identity history.
+    fn at(&self, commit: git::Oid) -> Option<Doc<Verified>>;
+    /// Whether the identity commit `new` comes after `old` in the identity history.
+    fn is_after(&self, new: git::Oid, old: git::Oid) -> bool;
+}
+
+/// A single document stands for every version of the identity.
+impl Identities for Doc<Verified> {
+    fn current(&self) -> &Doc<Verified> {
+        self
+    }
+
+    fn at(&self, _commit: git::Oid) -> Option<Doc<Verified>> {
+        Some(self.clone())
+    }
+
+    fn is_after(&self, _new: git::Oid, _old: git::Oid) -> bool {
+        false
+    }
+}
+
+/// The identity history of a repository, up to the given head.
+pub struct IdentityHistory<'a> {
+    repo: &'a storage::Repository,
+    head: git::Oid,
+    current: &'a Doc<Verified>,
+}
+
+impl<'a> IdentityHistory<'a> {
+    /// Identity history of a repository, given its identity head and the document at
+    /// that head.
+    pub fn new(repo: &'a storage::Repository, head: git::Oid, current: &'a Doc<Verified>) -> Self {
+        Self {
+            repo,
+            head,
+            current,
+        }
+    }
+}
+
+impl<'a> Identities for IdentityHistory<'a> {
+    fn current(&self) -> &Doc<Verified> {
+        self.current
+    }
+
+    fn at(&self, commit: git::Oid) -> Option<Doc<Verified>> {
+        if commit == self.head {
+            return Some(self.current.clone());
+        }
+        if !self.is_after(self.head, commit) {
+            return None;
+        }
+        match Doc::<Verified>::load_at(commit, self.repo) {
+            Ok(at) => Some(at.doc),
+            Err(err) => {
+                log::warn!("Error loading identity document at {commit}: {err}");
+                None
+            }
+        }
+    }
+
+    fn is_after(&self, new: git::Oid, old: git::Oid) -> bool {
+        new != old
+            && self
+                .repo
+                .backend
+                .graph_descendant_of(*new, *old)
+                .unwrap_or(false)
+    }
+}
+
+/// An operation that was skipped when materializing an object.
+#[derive(Debug)]
+pub struct Rejected {
+    /// The entry under which the operation lives.
+    pub entry: EntryId,
+    /// The author of the operation.
+    pub author: ActorId,
+    /// Why the operation was skipped.
+    pub reason: Rejection,
+}
+
+/// The reason an operation was skipped.
+#[derive(Debug, thiserror::Error)]
+pub enum Rejection {
+    #[error("project is archived, only delegates can make changes")]
+    Archived,
+    #[error(transparent)]
+    Unauthorized(#[from] Unauthorized),
+}
+
+/// The identity documents an entry is checked against.
+struct Judgement<'a> {
+    /// The document the entry is judged by.
+    judged: &'a Doc<Verified>,
+    /// The earliest document under which a current delegate built on the entry.
+    acknowledged: Option<&'a Doc<Verified>>,
+    /// The current document.
+    current: &'a Doc<Verified>,
+}
+
+impl<'a> Judgement<'a> {
+    /// Check that the entry is allowed by the document it is judged by, and by either
+    /// the current document or the one it was acknowledged under. Returns the error
+    /// given by the current document otherwise.
+    fn check<E>(&self, f: impl Fn(&Doc<Verified>) -> Result<(), E>) -> Result<(), E> {
+        f(self.judged)?;
+
+        match f(self.current) {
+            Ok(()) => Ok(()),
+            Err(_) if self.acknowledged.map_or(false, |doc| f(doc).is_ok()) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Check that the given key can write to the project of the identity document, ie.
+/// that the project isn't archived or that the key belongs to a delegate.
+fn writable(key: &PublicKey, identity: &Doc<Verified>) -> Result<(), Error> {
+    if !identity.is_writable(key) {
+        return Err(Error::Archived);
+    }
+    Ok(())
+}
+
+/// Check that the given key is allowed to create objects of type `T`, according to the
+/// project policy of the identity document.
+fn permitted<T: FromHistory>(key: &PublicKey, identity: &Doc<Verified>) -> Result<(), Error> {
+    let Ok(project) = identity.project() else {
+        return Ok(());
+    };
+    match T::policy(project.metadata()) {
+        Policy::Open => Ok(()),
+        Policy::Delegates if identity.is_delegate(key) => Ok(()),
+        policy => Err(Error::Policy(T::type_name().clone(), policy)),
+    }
+}
+
+/// Error returned when the author of an operation lacks the permission to carry it out.
+#[derive(Debug, thiserror::Error)]
+#[error("actor {actor} lacks the `{permission}` permission")]
+pub struct Unauthorized {
+    pub actor: ActorId,
+    pub permission: Permission,
+}
+
+/// Check that an actor is allowed to carry out an action that requires the given
+/// permission, on an object authored by `author`.
+///
+/// Triage actions are allowed from the object author; all other permissions have to
+/// be granted by the identity document.
+pub fn authorize(
+    actor: &ActorId,
+    author: &Did,
+    permission: Permission,
+    identity: &Doc<Verified>,
+) -> Result<(), Unauthorized> {
+    if permission == Permission::Triage && author == &Did::from(actor) {
+        return Ok(());
+    }
+    if identity.is_authorized(actor, permission) {
+        return Ok(());
+    }
+    Err(Unauthorized {
+        actor: *actor,
+        permission,
+    })
+}
+
 /// Store error.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -88,11 +410,16 @@ pub enum Error {
     NotFound(TypeName, ObjectId),
     #[error("signed refs: {0}")]
     SignRefs(#[from] storage::Error),
+    #[error("project is archived, only delegates can make changes")]
+    Archived,
+    #[error("project policy for `{0}` objects is `{1}`")]
+    Policy(TypeName, Policy),
 }
 
 /// Storage for collaborative objects of a specific type `T` in a single repository.
 pub struct Store<'a, T> {
     parent: git::Oid,
+    identity: Doc<Verified>,
     repo: &'a storage::Repository,
     witness: PhantomData<T>,
 }
@@ -111,9 +438,26 @@ impl<'a, T> Store<'a, T> {
         Ok(Self {
             repo,
             parent: identity.head,
+            identity: identity.doc,
             witness: PhantomData,
         })
     }
+
+    /// The identity document of the repository, as of when the store was opened.
+    pub fn identity(&self) -> &Doc<Verified> {
+        &self.identity
+    }
+
+    /// The identity history of the repository, as of when the store was opened.
+    fn identities(&self) -> IdentityHistory<'_> {
+        IdentityHistory::new(self.repo, self.parent, &self.identity)
+    }
+
+    /// Check that the given key can write to an archived project, ie. that it belongs
+    /// to a delegate. Any key can write to a project that isn't archived.
+    fn writable(&self, key: &PublicKey) -> Result<(), Error> {
+        writable(key, &self.identity)
+    }
 }
 
 impl<'a, T: FromHistory> Store<'a, T>
@@ -128,6 +472,8 @@ where
         actions: impl Into<NonEmpty<T::Action>>,
         signer: &G,
     ) -> Result<Updated, Error> {
+        self.writable(signer.public_key())?;
+
         let changes = actions.into().try_map(encoding::encode)?;
         let updated = cob::update(
             self.repo,
@@ -155,6 +501,9 @@ where
         actions: impl Into<NonEmpty<T::Action>>,
         signer: &G,
     ) -> Result<(ObjectId, T, Lamport), Error> {
+        self.writable(signer.public_key())?;
+        permitted::<T>(signer.public_key(), &self.identity)?;
+
         let contents = actions.into().try_map(encoding::encode)?;
         let cob = cob::create(
             self.repo,
@@ -168,7 +517,7 @@ where
                 contents,
             },
         )?;
-        let (object, clock) = T::from_history(cob.history())?;
+        let (object, clock, _) = T::from_history(cob.history(), &self.identities())?;
 
         self.repo.sign_refs(signer).map_err(Error::SignRefs)?;
 
@@ -177,28 +526,38 @@ where
 
     /// Get an object.
     pub fn get(&self, id: &ObjectId) -> Result<Option<(T, Lamport)>, Error> {
+        Ok(self
+            .get_with_rejected(id)?
+            .map(|(obj, clock, _)| (obj, clock)))
+    }
+
+    /// Get an object, along with the operations that were skipped when materializing it.
+    pub fn get_with_rejected(
+        &self,
+        id: &ObjectId,
+    ) -> Result<Option<(T, Lamport, Vec<Rejected>)>, Error> {
         let cob = cob::get(self.repo, T::type_name(), id)?;
 
         if let Some(cob) = cob {
             if cob.manifest().history_type != HISTORY_TYPE {
                 return Err(Error::HistoryType(cob.manifest().history_type.clone()));
             }
-            let (obj, clock) = T::from_history(cob.history())?;
-
-            Ok(Some((obj, clock)))
+            T::from_history(cob.history(), &self.identities()).map(Some)
         } else {
             Ok(None)
         }
     }
 
-    /// Return all objects.
+    /// Return all objects. Objects that were rejected, eg. because they were created on
+    /// an archived project or against the project policy, are returned as errors.
     pub fn all(
         &self,
-    ) -> Result<impl Iterator<Item = Result<(ObjectId, T, Lamport), Error>>, Error> {
+    ) -> Result<impl Iterator<Item = Result<(ObjectId, T, Lamport), Error>> + '_, Error> {
         let raw = cob::list(self.repo, T::type_name())?;
+        let identities = self.identities();
 
-        Ok(raw.into_iter().map(|o| {
-            let (obj, clock) = T::from_history(o.history())?;
+        Ok(raw.into_iter().map(move |o| {
+            let (obj, clock, _) = T::from_history(o.history(), &identities)?;
             Ok((*o.id(), obj, clock))
         }))
     }
@@ -294,6 +653,13 @@ impl<T: FromHistory> Transaction<T> {
         let id = EntryId::from(head);
         let author = self.actor;
         let timestamp = object.history().timestamp().into();
+        // The ops are stamped with the clock of the new entry, not the greatest clock of the
+        // history, which may belong to a concurrent entry.
+        let hybrid = object
+            .history()
+            .entry(&id)
+            .map(|entry| entry.hybrid())
+            .unwrap_or_else(|| object.history().hybrid());
         let clock = self.clock.tick();
 
         // The history clock should be in sync with the tx clock.
@@ -308,6 +674,7 @@ impl<T: FromHistory> Transaction<T> {
                 author,
                 clock,
                 timestamp,
+                hybrid,
             })
             .collect();
 
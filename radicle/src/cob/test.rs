@@ -39,6 +39,7 @@ where
                 resource,
                 NonEmpty::new(data),
                 op.timestamp.as_secs(),
+                Some(op.hybrid),
             ),
             resource,
             witness: PhantomData,
@@ -54,6 +55,7 @@ where
             self.resource,
             NonEmpty::new(data),
             op.timestamp.as_secs(),
+            Some(op.hybrid),
         );
         self
     }
@@ -104,6 +106,7 @@ where
 pub struct Actor<G, A> {
     pub signer: G,
     pub clock: clock::Lamport,
+    pub hybrid: clock::Hybrid,
     pub ops: BTreeMap<(clock::Lamport, PublicKey), Op<A>>,
 }
 
@@ -118,6 +121,7 @@ impl<G, A> Actor<G, A> {
         Self {
             signer,
             clock: clock::Lamport::default(),
+            hybrid: clock::Hybrid::default(),
             ops: BTreeMap::default(),
         }
     }
@@ -127,9 +131,11 @@ impl<G: Signer, A: Clone + Serialize> Actor<G, A> {
     pub fn receive(&mut self, ops: impl IntoIterator<Item = Op<A>>) -> clock::Lamport {
         for op in ops {
             let clock = op.clock;
+            let hybrid = op.hybrid;
 
             self.ops.insert((clock, op.author), op);
             self.clock.merge(clock);
+            self.hybrid.merge(hybrid, clock::Physical::now());
         }
         self.clock
     }
@@ -138,6 +144,7 @@ impl<G: Signer, A: Clone + Serialize> Actor<G, A> {
     pub fn reset(&mut self) {
         self.ops.clear();
         self.clock = clock::Lamport::default();
+        self.hybrid = clock::Hybrid::default();
     }
 
     /// Returned an ordered list of events.
@@ -151,12 +158,14 @@ impl<G: Signer, A: Clone + Serialize> Actor<G, A> {
         let author = *self.signer.public_key();
         let clock = self.clock.tick();
         let timestamp = clock::Physical::now();
+        let hybrid = self.hybrid.tick(timestamp);
         let op = Op {
             id,
             action,
             author,
             clock,
             timestamp,
+            hybrid,
         };
         self.ops.insert((self.clock, author), op.clone());
 
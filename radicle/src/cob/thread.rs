@@ -10,6 +10,7 @@ use crate::cob;
 use crate::cob::common::{Reaction, Timestamp};
 use crate::cob::{ActorId, EntryId, Op};
 
+use crdt::clock;
 use crdt::clock::Lamport;
 use crdt::{GMap, GSet, LWWSet, Max, Redactable, Semilattice};
 
@@ -165,8 +166,8 @@ pub struct Thread {
     comments: GMap<CommentId, Redactable<Comment>>,
     /// Reactions to changes.
     reactions: GMap<CommentId, LWWSet<(ActorId, Reaction), Lamport>>,
-    /// Comment timeline.
-    timeline: GSet<(Lamport, EntryId)>,
+    /// Comment timeline, ordered by time.
+    timeline: GSet<(clock::Hybrid, EntryId)>,
 }
 
 impl Semilattice for Thread {
@@ -267,7 +268,7 @@ impl cob::store::FromHistory for Thread {
             let author = op.author;
             let timestamp = op.timestamp;
 
-            self.timeline.insert((op.clock, op.id));
+            self.timeline.insert((op.hybrid, op.id));
 
             match op.action {
                 Action::Comment { body, reply_to } => {
@@ -330,7 +331,9 @@ mod tests {
     use crate::cob::store::FromHistory;
     use crate::cob::test;
     use crate::crypto::test::signer::MockSigner;
-    use crate::crypto::Signer;
+    use crate::crypto::{Signer, Verified};
+    use crate::identity::Doc;
+    use crate::test::arbitrary;
 
     /// An object that can be used to create and sign changes.
     pub struct Actor<G> {
@@ -628,7 +631,8 @@ mod tests {
         a.merge(b);
         a.merge(e);
 
-        let (expected, _) = Thread::from_history(&a).unwrap();
+        let (expected, _, _) =
+            Thread::from_history(&a, &arbitrary::gen::<Doc<Verified>>(1)).unwrap();
         for permutation in a.permutations(2) {
             let actual = Thread::from_ops(permutation).unwrap();
             assert_eq!(actual, expected);
@@ -649,7 +653,7 @@ mod tests {
         b.append(&b0);
         a.merge(b);
 
-        let (thread, _) = Thread::from_history(&a).unwrap();
+        let (thread, _, _) = Thread::from_history(&a, &arbitrary::gen::<Doc<Verified>>(1)).unwrap();
 
         assert_eq!(thread.comments().count(), 2);
 
@@ -681,7 +685,8 @@ mod tests {
         h3.merge(h1);
         h3.merge(h2);
 
-        let (thread, _) = Thread::from_history(&h3).unwrap();
+        let (thread, _, _) =
+            Thread::from_history(&h3, &arbitrary::gen::<Doc<Verified>>(1)).unwrap();
 
         // The three comments, distinct yet identical in terms of content, are preserved.
         assert_eq!(thread.comments().count(), 3);
@@ -166,6 +166,14 @@ pub mod refs {
             Qualified::from_components(name::component!("rad"), name::component!("id"), None)
         });
 
+        /// Where a person's identity document is stored, under their namespace.
+        ///
+        /// `refs/rad/person`
+        ///
+        pub static PERSON_BRANCH: Lazy<Qualified> = Lazy::new(|| {
+            Qualified::from_components(name::component!("rad"), name::component!("person"), None)
+        });
+
         /// Where the project's signed references are stored.
         ///
         /// `refs/rad/sigrefs`
@@ -174,6 +182,19 @@ pub mod refs {
             Qualified::from_components(name::component!("rad"), name::component!("sigrefs"), None)
         });
 
+        /// Entry of the signed references holding the previous signed references commit.
+        /// It is also published as a reference, so that it can be verified like any other.
+        ///
+        /// `refs/rad/sigrefs-parent`
+        ///
+        pub static SIGREFS_PARENT: Lazy<Qualified> = Lazy::new(|| {
+            Qualified::from_components(
+                name::component!("rad"),
+                name::component!("sigrefs-parent"),
+                None,
+            )
+        });
+
         /// Create the [`Namespaced`] `branch` under the `remote` namespace, i.e.
         ///
         /// `refs/namespaces/<remote>/refs/heads/<branch>`
@@ -190,6 +211,14 @@ pub mod refs {
             IDENTITY_BRANCH.with_namespace(remote.into())
         }
 
+        /// Get the branch where the `remote`'s person document is stored.
+        ///
+        /// `refs/namespaces/<remote>/refs/rad/person`
+        ///
+        pub fn person(remote: &RemoteId) -> Namespaced {
+            PERSON_BRANCH.with_namespace(remote.into())
+        }
+
         /// The collaborative object reference, identified by `typename` and `object_id`, under the given `remote`.
         ///
         /// `refs/namespaces/<remote>/refs/cobs/<typename>/<object_id>`
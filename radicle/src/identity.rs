@@ -1,5 +1,6 @@
 pub mod did;
 pub mod doc;
+pub mod person;
 pub mod project;
 
 use std::collections::HashMap;
@@ -15,7 +16,10 @@ use crate::storage::{refs, ReadRepository, RemoteId};
 
 pub use crypto::PublicKey;
 pub use did::Did;
-pub use doc::{Doc, Id, IdError, PayloadError};
+pub use doc::{
+    Doc, DocAt, Fork, Id, IdError, PayloadError, Permission, Role, Rotation, Visibility,
+};
+pub use person::Person;
 pub use project::Project;
 
 /// Untrusted, well-formed input.
@@ -33,6 +37,8 @@ pub enum IdentityError {
     GitExt(#[from] git::Error),
     #[error("identity branches diverge from each other")]
     BranchesDiverge,
+    #[error("delegates don't reach a quorum on `{0}`")]
+    NoQuorum(git::RefString),
     #[error("root hash `{0}` does not match project")]
     MismatchedRoot(Oid),
     #[error("the identity branch is missing")]
@@ -113,59 +119,140 @@ impl Identity<Untrusted> {
     }
 
     pub fn load_at<R: ReadRepository>(head: Oid, repo: &R) -> Result<Identity<Oid>, IdentityError> {
-        let mut history = repo.revwalk(head)?.collect::<Vec<_>>();
-
-        // Retrieve root document.
-        let root_oid = history.pop().ok_or(IdentityError::MissingRoot)??.into();
-        let root = Doc::<Verified>::load_at(root_oid, repo)?;
-        let revision = history.len() as u32;
-
-        // Every identity founder must have signed the root document.
-        for founder in &root.doc.delegates {
-            if !root.sigs.iter().any(|(k, _)| k == &**founder) {
-                return Err(IdentityError::MissingRootSignatures);
-            }
-        }
-
-        let mut current = root.blob;
-        let mut trusted = root.doc;
-        let mut signatures = root.sigs;
+        let mut root = None;
+        let mut current = None;
 
         // Traverse the history chronologically.
-        for oid in history.into_iter().rev() {
-            let oid = oid?;
-            let untrusted = Doc::<Verified>::load_at(oid.into(), repo)?;
-
-            // Check that enough delegates signed this next version.
-            let quorum = untrusted
-                .sigs
-                .iter()
-                .filter(|(key, _)| trusted.delegates.iter().any(|d| **d == **key))
-                .count();
-            if quorum < trusted.threshold {
+        for version in Self::versions(head, repo)? {
+            let version = version?;
+
+            if !version.is_verified() {
+                if version.revision == 0 {
+                    // Every identity founder must have signed the root document.
+                    return Err(IdentityError::MissingRootSignatures);
+                }
+                // Enough delegates must have signed every next version.
                 return Err(IdentityError::ThresholdNotReached(
-                    quorum,
-                    trusted.threshold,
+                    version.quorum,
+                    version.threshold,
                 ));
             }
-
-            current = untrusted.blob;
-            trusted = untrusted.doc;
-            signatures = untrusted.sigs;
+            root.get_or_insert(version.at.blob);
+            current = Some(version);
         }
+        let root = root.ok_or(IdentityError::MissingRoot)?;
+        let current = current.ok_or(IdentityError::MissingRoot)?;
 
         Ok(Identity {
-            root: root.blob,
+            root,
             head,
-            current,
-            revision,
-            doc: trusted,
-            signatures: signatures.into_iter().collect(),
+            current: current.at.blob,
+            revision: current.revision,
+            doc: current.at.doc,
+            signatures: current.at.sigs.into_iter().collect(),
         })
     }
+
+    /// Iterate over all versions of an identity document, starting from the initial
+    /// document, up to and including the document at `head`.
+    ///
+    /// Unlike [`Identity::load_at`], versions that weren't signed by enough delegates
+    /// are returned, and can be told apart using [`Version::is_verified`].
+    pub fn versions<R: ReadRepository>(
+        head: Oid,
+        repo: &R,
+    ) -> Result<Versions<'_, R>, IdentityError> {
+        let mut commits = repo
+            .revwalk(head)?
+            .map(|oid| oid.map(Oid::from))
+            .collect::<Result<Vec<_>, _>>()?;
+        commits.reverse();
+
+        Ok(Versions {
+            repo,
+            commits: commits.into_iter(),
+            revision: 0,
+            previous: None,
+        })
+    }
+}
+
+/// A version of an identity document, as found on the identity branch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    /// Revision number. The initial document has a revision of `0`.
+    pub revision: u32,
+    /// The document, along with its commit and signatures.
+    pub at: DocAt,
+    /// Number of delegates of the previous version who signed this version, either with
+    /// their key or with the key it was rotated to in this version.
+    /// For the initial document, these are the signatures of its own delegates.
+    pub quorum: usize,
+    /// Number of signatures required for this version to be verified.
+    /// For the initial document, every delegate must have signed.
+    pub threshold: usize,
 }
+
+impl Version {
+    /// Whether this version was signed by enough delegates.
+    pub fn is_verified(&self) -> bool {
+        self.quorum >= self.threshold
+    }
+}
+
+/// Iterator over the versions of an identity document, in chronological order.
+/// See [`Identity::versions`].
+pub struct Versions<'a, R> {
+    repo: &'a R,
+    commits: std::vec::IntoIter<Oid>,
+    revision: u32,
+    previous: Option<Doc<Verified>>,
+}
+
+impl<'a, R: ReadRepository> Iterator for Versions<'a, R> {
+    type Item = Result<Version, IdentityError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let oid = self.commits.next()?;
+        let at = match Doc::<Verified>::load_at(oid, self.repo) {
+            Ok(at) => at,
+            Err(err) => return Some(Err(err.into())),
+        };
+        let signers = self.previous.as_ref().unwrap_or(&at.doc);
+        // A delegate's signature may have been made with a key it was rotated to
+        // in this version.
+        let quorum = signers
+            .delegates
+            .iter()
+            .filter(|did| {
+                at.sigs.contains_key(&**did) || at.sigs.contains_key(&at.doc.resolve(did))
+            })
+            .count();
+        let threshold = if self.previous.is_some() {
+            signers.threshold
+        } else {
+            signers.delegates.len()
+        };
+        let version = Version {
+            revision: self.revision,
+            at,
+            quorum,
+            threshold,
+        };
+        self.revision += 1;
+        // Unverified versions have no authority: the next version is checked against the
+        // last verified one.
+        if version.is_verified() {
+            self.previous = Some(version.at.doc.clone());
+        }
+
+        Some(Ok(version))
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use nonempty::NonEmpty;
     use qcheck_macros::quickcheck;
     use radicle_crypto::test::signer::MockSigner;
     use radicle_crypto::Signer as _;
@@ -294,4 +381,171 @@ mod test {
         let doc = storage.get(alice.public_key(), id).unwrap().unwrap();
         assert_eq!(doc.project().unwrap().description(), "Acme's repository!?");
     }
+
+    #[test]
+    fn test_versions() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let mut rng = fastrand::Rng::new();
+
+        let alice = MockSigner::new(&mut rng);
+        let bob = MockSigner::new(&mut rng);
+
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+        let (id, _, _, _) =
+            fixtures::project(tempdir.path().join("copy"), &storage, &alice).unwrap();
+        let mut doc = storage.get(alice.public_key(), id).unwrap().unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        // Add Bob as a delegate, and sign it.
+        doc.delegate(bob.public_key());
+        doc.threshold = 2;
+        doc.sign(&alice)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    alice.public_key(),
+                    "Add bob",
+                    &[(alice.public_key(), sig)],
+                    repo.raw(),
+                )
+            })
+            .unwrap();
+
+        // Lower the threshold, without Bob's signature.
+        doc.threshold = 1;
+        let head = doc
+            .sign(&alice)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    alice.public_key(),
+                    "Lower threshold",
+                    &[(alice.public_key(), sig)],
+                    repo.raw(),
+                )
+            })
+            .unwrap();
+
+        // Build on the unverified version. It doesn't lower the threshold for this one.
+        doc.payload.insert(
+            PayloadId::project(),
+            doc.project()
+                .unwrap()
+                .update(None, String::from("Unverified"), None)
+                .unwrap()
+                .into(),
+        );
+        let head = doc
+            .sign(&alice)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    alice.public_key(),
+                    "Update description",
+                    &[(alice.public_key(), sig)],
+                    repo.raw(),
+                )
+            })
+            .unwrap();
+
+        let versions = Identity::versions(head, &repo)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(versions.len(), 4);
+        assert_eq!(versions[0].at.doc.delegates.len(), 1);
+        assert_eq!(versions[3].at.commit, head);
+        assert_eq!(versions[3].at.doc, doc);
+        assert_eq!(
+            versions.iter().map(|v| v.revision).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(
+            versions.iter().map(|v| v.is_verified()).collect::<Vec<_>>(),
+            vec![true, true, false, false]
+        );
+        assert_eq!(versions[3].threshold, 2);
+        assert!(matches!(
+            Identity::load_at(head, &repo),
+            Err(IdentityError::ThresholdNotReached(1, 2))
+        ));
+    }
+
+    #[test]
+    fn test_rotation() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let mut rng = fastrand::Rng::new();
+
+        let alice = MockSigner::new(&mut rng);
+        let alice_new = MockSigner::new(&mut rng);
+        let eve = MockSigner::new(&mut rng);
+
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+        let (id, _, _, _) =
+            fixtures::project(tempdir.path().join("copy"), &storage, &alice).unwrap();
+        let mut doc = storage.get(alice.public_key(), id).unwrap().unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        // Eve can't take over by rotating Alice's key herself, or by swapping the
+        // delegate key.
+        let mut forged = doc.clone();
+        let rotation = Rotation {
+            old: *alice.public_key(),
+            new: *eve.public_key(),
+            signature: eve.sign(&Rotation::statement(alice.public_key(), eve.public_key())),
+        };
+        forged.rotate(rotation).unwrap_err();
+        forged.delegates = NonEmpty::new(eve.public_key().into());
+
+        let alice_head = repo
+            .raw()
+            .refname_to_id(&git::refs::storage::id(alice.public_key()))
+            .unwrap();
+        repo.raw()
+            .reference(
+                &git::refs::storage::id(eve.public_key()),
+                alice_head,
+                false,
+                "Fork identity branch",
+            )
+            .unwrap();
+        let head = forged
+            .sign(&eve)
+            .and_then(|(_, sig)| {
+                forged.update(
+                    eve.public_key(),
+                    "Forge delegate",
+                    &[(eve.public_key(), sig)],
+                    repo.raw(),
+                )
+            })
+            .unwrap();
+        assert!(matches!(
+            Identity::load_at(head, &repo),
+            Err(IdentityError::ThresholdNotReached(0, 1))
+        ));
+
+        // Alice rotates her key, and signs the new document with her new key only.
+        doc.rotate(Rotation::new(&alice, *alice_new.public_key()))
+            .unwrap();
+        assert!(doc.is_delegate(alice_new.public_key()));
+        assert!(!doc.is_delegate(alice.public_key()));
+        assert!(!doc.is_authorized(alice.public_key(), Permission::Identity));
+        assert_eq!(doc.resolve(alice.public_key()), *alice_new.public_key());
+
+        let head = doc
+            .sign(&alice_new)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    alice.public_key(),
+                    "Rotate key",
+                    &[(alice_new.public_key(), sig)],
+                    repo.raw(),
+                )
+            })
+            .unwrap();
+        let identity = Identity::load_at(head, &repo).unwrap();
+
+        assert_eq!(identity.revision, 1);
+        assert_eq!(identity.doc, doc);
+        assert_eq!(identity.doc.rotations.len(), 1);
+    }
 }
@@ -1,6 +1,6 @@
 mod id;
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::fmt::Write as _;
 use std::marker::PhantomData;
@@ -17,6 +17,7 @@ use crate::canonical::formatter::CanonicalFormatter;
 use crate::crypto;
 use crate::crypto::{Signature, Unverified, Verified};
 use crate::git;
+use crate::git::fmt::refspec::PatternString;
 use crate::identity::{project::Project, Did};
 use crate::storage;
 use crate::storage::git::trailers;
@@ -31,6 +32,14 @@ pub static PATH: Lazy<&Path> = Lazy::new(|| Path::new("radicle.json"));
 pub const MAX_STRING_LENGTH: usize = 255;
 /// Maximum number of a delegates in the identity document.
 pub const MAX_DELEGATES: usize = 255;
+/// Maximum number of role assignments in the identity document.
+pub const MAX_ROLES: usize = 255;
+/// Maximum number of key rotations in the identity document.
+pub const MAX_ROTATIONS: usize = 255;
+/// Maximum number of nodes in the allow-list of a private repository.
+pub const MAX_ALLOWED: usize = 255;
+/// Maximum number of canonical reference patterns in the identity document.
+pub const MAX_CANONICAL_REFS: usize = 255;
 
 #[derive(Error, Debug)]
 pub enum DocError {
@@ -48,6 +57,14 @@ pub enum DocError {
     Version(u32),
     #[error("invalid threshold `{0}`: {1}")]
     Threshold(usize, &'static str),
+    #[error("invalid roles: {0}")]
+    Roles(&'static str),
+    #[error("invalid key rotation: {0}")]
+    Rotation(&'static str),
+    #[error("invalid visibility: {0}")]
+    Visibility(&'static str),
+    #[error("invalid canonical references: {0}")]
+    CanonicalRefs(&'static str),
     #[error("git: {0}")]
     GitExt(#[from] git::Error),
     #[error("git: {0}")]
@@ -83,6 +100,11 @@ impl PayloadId {
     pub fn project() -> Self {
         Self(String::from("xyz.radicle.project"))
     }
+
+    /// Fork payload type.
+    pub fn fork() -> Self {
+        Self(String::from("xyz.radicle.fork"))
+    }
 }
 
 #[derive(Debug, Error)]
@@ -114,6 +136,210 @@ impl Deref for Payload {
     }
 }
 
+/// Fork payload. Records the project this project was forked from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fork {
+    /// The upstream project.
+    pub upstream: Id,
+    /// The canonical head of the upstream project at the time of the fork.
+    pub head: Oid,
+}
+
+impl From<Fork> for Payload {
+    fn from(fork: Fork) -> Self {
+        let value =
+            serde_json::to_value(fork).expect("Payload::from: could not convert fork into value");
+
+        Self::from(value)
+    }
+}
+
+/// A role that can be assigned to a [`Did`] in the identity document.
+///
+/// Roles grant powers over collaborative objects, such as issues and patches,
+/// that the actor didn't author. Delegates implicitly hold every role, and
+/// changing the identity document itself is reserved to delegates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    /// Can merge patches, review and triage.
+    Maintainer,
+    /// Can review patches and triage.
+    Reviewer,
+    /// Can triage issues and patches, eg. close, tag or assign them.
+    Triager,
+}
+
+impl Role {
+    /// Whether this role grants the given permission.
+    pub fn permits(&self, permission: Permission) -> bool {
+        match self {
+            Self::Maintainer => permission != Permission::Identity,
+            Self::Reviewer => matches!(permission, Permission::Review | Permission::Triage),
+            Self::Triager => permission == Permission::Triage,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Maintainer => write!(f, "maintainer"),
+            Self::Reviewer => write!(f, "reviewer"),
+            Self::Triager => write!(f, "triager"),
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = DocError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "maintainer" => Ok(Self::Maintainer),
+            "reviewer" => Ok(Self::Reviewer),
+            "triager" => Ok(Self::Triager),
+            _ => Err(DocError::Roles("unknown role")),
+        }
+    }
+}
+
+/// A permission needed to act on an object, or on the identity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    /// Accept, reject or commit changes to the identity document.
+    Identity,
+    /// Merge patches.
+    Merge,
+    /// Accept or reject patch revisions.
+    Review,
+    /// Change the state, title, tags or assignees of objects authored by others.
+    Triage,
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Identity => write!(f, "identity"),
+            Self::Merge => write!(f, "merge"),
+            Self::Review => write!(f, "review"),
+            Self::Triage => write!(f, "triage"),
+        }
+    }
+}
+
+/// Who a repository is visible to.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Visibility {
+    /// The repository is announced to the network, and served to anyone.
+    #[default]
+    Public,
+    /// The repository is never announced, and only served to the delegates and to
+    /// the nodes in the allow-list.
+    Private {
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        allow: BTreeSet<Did>,
+    },
+}
+
+impl Visibility {
+    /// Private visibility, with the given nodes allowed.
+    pub fn private(allow: impl IntoIterator<Item = Did>) -> Self {
+        Self::Private {
+            allow: allow.into_iter().collect(),
+        }
+    }
+
+    /// Whether this is public visibility.
+    pub fn is_public(&self) -> bool {
+        matches!(self, Self::Public)
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Public => write!(f, "public"),
+            Self::Private { .. } => write!(f, "private"),
+        }
+    }
+}
+
+/// Check that a canonical reference pattern is under `refs/heads/` or `refs/tags/`.
+pub fn is_canonical_pattern(pattern: &PatternString) -> bool {
+    let pattern = pattern.as_str();
+
+    ["refs/heads/", "refs/tags/"]
+        .iter()
+        .any(|prefix| pattern.len() > prefix.len() && pattern.starts_with(prefix))
+}
+
+/// Check whether two canonical reference patterns can match the same reference. Patterns
+/// have at most one `*`, which matches across `/`.
+fn is_overlapping(a: &PatternString, b: &PatternString) -> bool {
+    let is_match = |name: &str, prefix: &str, suffix: &str| {
+        name.len() >= prefix.len() + suffix.len()
+            && name.starts_with(prefix)
+            && name.ends_with(suffix)
+    };
+    let (a, b) = (a.as_str(), b.as_str());
+
+    match (a.split_once('*'), b.split_once('*')) {
+        (None, None) => a == b,
+        (Some((prefix, suffix)), None) => is_match(b, prefix, suffix),
+        (None, Some((prefix, suffix))) => is_match(a, prefix, suffix),
+        (Some((p, s)), Some((q, t))) => {
+            (p.starts_with(q) || q.starts_with(p)) && (s.ends_with(t) || t.ends_with(s))
+        }
+    }
+}
+
+/// A statement, signed by a delegate's old key, binding it to a new key.
+///
+/// Once a rotation is part of the identity document, signatures made with the new key
+/// count towards the authority of the old key, and objects authored with the old key
+/// are attributed to the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rotation {
+    /// The key being rotated out.
+    pub old: PublicKey,
+    /// The key replacing it.
+    pub new: PublicKey,
+    /// Signature of the old key over the rotation statement.
+    pub signature: Signature,
+}
+
+impl Rotation {
+    /// Create a rotation from the signer's key to a new key.
+    pub fn new<G: crypto::Signer>(signer: &G, new: PublicKey) -> Self {
+        let old = *signer.public_key();
+        let signature = signer.sign(&Self::statement(&old, &new));
+
+        Self {
+            old,
+            new,
+            signature,
+        }
+    }
+
+    /// The bytes signed by the old key.
+    pub fn statement(old: &PublicKey, new: &PublicKey) -> Vec<u8> {
+        format!("rad:rotate:{old}:{new}").into_bytes()
+    }
+
+    /// Verify the rotation statement signature.
+    pub fn verify(&self) -> Result<(), DocError> {
+        if self.old == self.new {
+            return Err(DocError::Rotation("cannot rotate a key to itself"));
+        }
+        self.old
+            .verify(&Self::statement(&self.old, &self.new), &self.signature)
+            .map_err(|err| DocError::Signature(self.old, err))
+    }
+}
+
 /// A verified identity document at a specific commit.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DocAt {
@@ -137,6 +363,19 @@ pub struct Doc<V> {
     pub delegates: NonEmpty<Did>,
     /// The signature threshold.
     pub threshold: usize,
+    /// The roles section. Assigns roles to [`Did`]s, who need not be delegates.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub roles: BTreeMap<Did, BTreeSet<Role>>,
+    /// The key rotations section, in the order they were made.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rotations: Vec<Rotation>,
+    /// The visibility of the repository. Repositories are public by default.
+    #[serde(default, skip_serializing_if = "Visibility::is_public")]
+    pub visibility: Visibility,
+    /// Patterns of references, other than the default branch, that have a canonical
+    /// target agreed upon by the delegates, eg. `refs/heads/release/*` or `refs/tags/*`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub canonical_refs: Vec<PatternString>,
 
     #[serde(skip)]
     verified: PhantomData<V>,
@@ -163,6 +402,57 @@ impl<V> Doc<V> {
     pub fn is_delegate(&self, key: &crypto::PublicKey) -> bool {
         self.delegates.contains(&key.into())
     }
+
+    /// Resolve a key through the key rotations of this document. Returns the key it was
+    /// last rotated to, or the key itself if it was never rotated.
+    ///
+    /// This is used to attribute what was done with a key to its current key. It doesn't
+    /// confer any authority: as of the document that records its rotation, a rotated-out
+    /// key is no longer a delegate, and has no roles. Changes made before the
+    /// rotation refer to an earlier document, under which the key keeps its authority.
+    pub fn resolve(&self, key: &crypto::PublicKey) -> crypto::PublicKey {
+        let mut key = *key;
+        // Rotations can't form cycles, but we bound the walk regardless.
+        for _ in 0..self.rotations.len() {
+            match self.rotations.iter().find(|r| r.old == key) {
+                Some(r) => key = r.new,
+                None => break,
+            }
+        }
+        key
+    }
+
+    /// Whether the repository is visible to the given node, ie. whether it may be announced
+    /// or served to it. Delegates can always see the repository.
+    pub fn is_visible_to(&self, key: &crypto::PublicKey) -> bool {
+        match &self.visibility {
+            Visibility::Public => true,
+            Visibility::Private { allow } => {
+                allow.contains(&Did::from(*key)) || self.is_delegate(key)
+            }
+        }
+    }
+
+    /// Get the roles held by the given key. Delegates hold every role.
+    pub fn roles_of(&self, key: &crypto::PublicKey) -> BTreeSet<Role> {
+        if self.is_delegate(key) {
+            return BTreeSet::from([Role::Maintainer, Role::Reviewer, Role::Triager]);
+        }
+        self.roles
+            .get(&Did::from(*key))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Check whether the given key has the given permission.
+    pub fn is_authorized(&self, key: &crypto::PublicKey, permission: Permission) -> bool {
+        if self.is_delegate(key) {
+            return true;
+        }
+        self.roles
+            .get(&Did::from(*key))
+            .map_or(false, |roles| roles.iter().any(|r| r.permits(permission)))
+    }
 }
 
 impl Doc<Verified> {
@@ -187,6 +477,62 @@ impl Doc<Verified> {
         false
     }
 
+    /// Assign a role to a key. Returns `true` if the key didn't already have that role.
+    pub fn assign(&mut self, key: &crypto::PublicKey, role: Role) -> bool {
+        self.roles.entry(Did::from(key)).or_default().insert(role)
+    }
+
+    /// Revoke a role from a key. Returns `true` if the key had that role.
+    pub fn revoke(&mut self, key: &crypto::PublicKey, role: Role) -> bool {
+        let did = Did::from(key);
+        let Some(roles) = self.roles.get_mut(&did) else {
+            return false;
+        };
+        let removed = roles.remove(&role);
+        if roles.is_empty() {
+            self.roles.remove(&did);
+        }
+        removed
+    }
+
+    /// Rotate a delegate's key, replacing the old key with the new one in the delegates
+    /// and roles sections. The rotation is recorded in the document, so that the old key's
+    /// signatures and authorship remain attributable.
+    pub fn rotate(&mut self, rotation: Rotation) -> Result<(), DocError> {
+        rotation.verify()?;
+
+        if self.rotations.iter().any(|r| r.old == rotation.old) {
+            return Err(DocError::Rotation("key was already rotated"));
+        }
+        if self.rotations.iter().any(|r| r.old == rotation.new) {
+            return Err(DocError::Rotation(
+                "cannot rotate to a key that was rotated out",
+            ));
+        }
+        if self.rotations.len() >= MAX_ROTATIONS {
+            return Err(DocError::Rotation("number of rotations cannot exceed 255"));
+        }
+        if !self.is_delegate(&rotation.old) {
+            return Err(DocError::Rotation("only delegate keys can be rotated"));
+        }
+        if self.is_delegate(&rotation.new) {
+            return Err(DocError::Rotation("new key is already a delegate"));
+        }
+        let (old, new) = (Did::from(rotation.old), Did::from(rotation.new));
+
+        for delegate in self.delegates.iter_mut() {
+            if *delegate == old {
+                *delegate = new;
+            }
+        }
+        if let Some(roles) = self.roles.remove(&old) {
+            self.roles.entry(new).or_default().extend(roles);
+        }
+        self.rotations.push(rotation);
+
+        Ok(())
+    }
+
     pub fn rescind(&mut self, key: &crypto::PublicKey) -> Result<Option<Did>, DocError> {
         let delegate = Did::from(key);
         let (matches, delegates) = self.delegates.iter().partition(|d| **d == delegate);
@@ -216,6 +562,28 @@ impl Doc<Verified> {
         Ok(proj)
     }
 
+    /// Get the fork payload, if this project was forked from another project.
+    pub fn fork(&self) -> Result<Fork, PayloadError> {
+        let value = self
+            .payload
+            .get(&PayloadId::fork())
+            .ok_or_else(|| PayloadError::NotFound(PayloadId::fork()))?;
+        let fork: Fork = serde_json::from_value((**value).clone())?;
+
+        Ok(fork)
+    }
+
+    /// When the project was archived, in seconds since the epoch, if it was.
+    pub fn archived(&self) -> Option<u64> {
+        self.project().ok().and_then(|p| p.archived())
+    }
+
+    /// Whether the given key can write to the project. Once a project is archived,
+    /// only delegates can.
+    pub fn is_writable(&self, key: &crypto::PublicKey) -> bool {
+        self.archived().is_none() || self.is_delegate(key)
+    }
+
     pub fn sign<G: crypto::Signer>(&self, signer: &G) -> Result<(git::Oid, Signature), DocError> {
         let (oid, _) = self.encode()?;
         let sig = signer.sign(oid.as_bytes());
@@ -255,9 +623,31 @@ impl Doc<Verified> {
         remote: &RemoteId,
         signatures: &[(&PublicKey, Signature)],
         repo: &git2::Repository,
+    ) -> Result<git::Oid, DocError> {
+        let id_ref = git::refs::storage::id(remote);
+
+        Self::init_at(doc, remote, signatures, Some(&id_ref), repo)
+    }
+
+    /// Like [`Doc::init`], but updates the given reference, if any, instead of the
+    /// identity branch of `remote`.
+    pub(crate) fn init_at(
+        doc: &[u8],
+        remote: &RemoteId,
+        signatures: &[(&PublicKey, Signature)],
+        refname: Option<&str>,
+        repo: &git2::Repository,
     ) -> Result<git::Oid, DocError> {
         let tree = git::write_tree(*PATH, doc, repo)?;
-        let oid = Doc::commit(remote, &tree, "Initialize Radicle\n", &[], signatures, repo)?;
+        let oid = Doc::commit(
+            remote,
+            &tree,
+            "Initialize Radicle\n",
+            &[],
+            signatures,
+            refname,
+            repo,
+        )?;
 
         Ok(oid)
     }
@@ -273,7 +663,15 @@ impl Doc<Verified> {
         let tree = git::write_tree(*PATH, doc.as_slice(), repo)?;
         let id_ref = git::refs::storage::id(remote);
         let head = repo.find_reference(&id_ref)?.peel_to_commit()?;
-        let oid = Doc::commit(remote, &tree, msg, &[&head], signatures, repo)?;
+        let oid = Doc::commit(
+            remote,
+            &tree,
+            msg,
+            &[&head],
+            signatures,
+            Some(&id_ref),
+            repo,
+        )?;
 
         Ok(oid)
     }
@@ -284,6 +682,7 @@ impl Doc<Verified> {
         msg: &str,
         parents: &[&git2::Commit],
         signatures: &[(&PublicKey, Signature)],
+        refname: Option<&str>,
         repo: &git2::Repository,
     ) -> Result<git::Oid, DocError> {
         let sig = repo
@@ -307,8 +706,7 @@ impl Doc<Verified> {
                 .expect("in-memory writes don't fail");
         }
 
-        let id_ref = git::refs::storage::id(remote);
-        let oid = repo.commit(Some(&id_ref), &sig, &sig, &msg, tree, parents)?;
+        let oid = repo.commit(refname, &sig, &sig, &msg, tree, parents)?;
 
         Ok(oid.into())
     }
@@ -319,6 +717,10 @@ impl Doc<Verified> {
             payload: self.payload,
             delegates: self.delegates,
             threshold: self.threshold,
+            roles: self.roles,
+            rotations: self.rotations,
+            visibility: self.visibility,
+            canonical_refs: self.canonical_refs,
             verified: PhantomData,
         }
     }
@@ -337,6 +739,10 @@ impl Doc<Unverified> {
             payload: BTreeMap::from_iter([(PayloadId::project(), Payload::from(project))]),
             delegates,
             threshold,
+            roles: BTreeMap::new(),
+            rotations: Vec::new(),
+            visibility: Visibility::default(),
+            canonical_refs: Vec::new(),
             verified: PhantomData,
         }
     }
@@ -364,11 +770,65 @@ impl Doc<Unverified> {
                 "threshold cannot be zero",
             ));
         }
+        if self.roles.len() > MAX_ROLES {
+            return Err(DocError::Roles(
+                "number of role assignments cannot exceed 255",
+            ));
+        }
+        if self.roles.values().any(|roles| roles.is_empty()) {
+            return Err(DocError::Roles("role assignments cannot be empty"));
+        }
+        if self.rotations.len() > MAX_ROTATIONS {
+            return Err(DocError::Rotation("number of rotations cannot exceed 255"));
+        }
+        for (i, rotation) in self.rotations.iter().enumerate() {
+            rotation.verify()?;
+
+            // Keys can only be rotated once, and never back to a key that was rotated out.
+            // This ensures rotations form chains without cycles.
+            if self.rotations[..i]
+                .iter()
+                .any(|r| r.old == rotation.old || r.old == rotation.new)
+            {
+                return Err(DocError::Rotation("key was already rotated"));
+            }
+        }
+        if let Visibility::Private { allow } = &self.visibility {
+            if allow.len() > MAX_ALLOWED {
+                return Err(DocError::Visibility(
+                    "number of allowed nodes cannot exceed 255",
+                ));
+            }
+        }
+        if self.canonical_refs.len() > MAX_CANONICAL_REFS {
+            return Err(DocError::CanonicalRefs(
+                "number of patterns cannot exceed 255",
+            ));
+        }
+        if !self.canonical_refs.iter().all(is_canonical_pattern) {
+            return Err(DocError::CanonicalRefs(
+                "patterns must be under `refs/heads/` or `refs/tags/`",
+            ));
+        }
+        for (i, pattern) in self.canonical_refs.iter().enumerate() {
+            if self.canonical_refs[i + 1..]
+                .iter()
+                .any(|other| is_overlapping(pattern, other))
+            {
+                return Err(DocError::CanonicalRefs(
+                    "patterns cannot overlap with each other",
+                ));
+            }
+        }
 
         Ok(Doc {
             payload: self.payload,
             delegates: self.delegates,
             threshold: self.threshold,
+            roles: self.roles,
+            rotations: self.rotations,
+            visibility: self.visibility,
+            canonical_refs: self.canonical_refs,
             verified: PhantomData,
         })
     }
@@ -471,6 +931,161 @@ mod test {
         assert_eq!(doc, Doc::canonical(&repo).unwrap().doc);
     }
 
+    #[test]
+    fn test_roles() {
+        let mut doc = arbitrary::gen::<Doc<Verified>>(1);
+        let (_, encoded) = doc.encode().unwrap();
+        let delegate = *doc.delegates.first().as_key();
+        let bob = arbitrary::gen::<PublicKey>(1);
+
+        assert!(!String::from_utf8(encoded).unwrap().contains("roles"));
+        assert!(doc.is_authorized(&delegate, Permission::Identity));
+        assert!(!doc.is_authorized(&bob, Permission::Triage));
+        assert!(doc.roles_of(&bob).is_empty());
+
+        assert!(doc.assign(&bob, Role::Triager));
+        assert!(!doc.assign(&bob, Role::Triager));
+        assert!(doc.is_authorized(&bob, Permission::Triage));
+        assert!(!doc.is_authorized(&bob, Permission::Review));
+
+        assert!(doc.assign(&bob, Role::Maintainer));
+        assert!(doc.is_authorized(&bob, Permission::Merge));
+        assert!(!doc.is_authorized(&bob, Permission::Identity));
+
+        let (_, bytes) = doc.encode().unwrap();
+        assert_eq!(Doc::from_json(&bytes).unwrap().verified().unwrap(), doc);
+
+        assert!(doc.revoke(&bob, Role::Triager));
+        assert!(doc.revoke(&bob, Role::Maintainer));
+        assert!(!doc.revoke(&bob, Role::Maintainer));
+        assert!(doc.roles.is_empty());
+    }
+
+    #[test]
+    fn test_rotations() {
+        let alice = MockSigner::from_seed([1; 32]);
+        let alice_new = MockSigner::from_seed([2; 32]);
+        let bob = MockSigner::from_seed([3; 32]);
+        let mut doc = Doc::new(
+            arbitrary::gen::<Project>(1),
+            NonEmpty::from((alice.public_key().into(), vec![bob.public_key().into()])),
+            2,
+        )
+        .verified()
+        .unwrap();
+        doc.assign(alice.public_key(), Role::Maintainer);
+
+        // Only the old key can sign a rotation.
+        let forged = Rotation {
+            signature: bob.sign(&Rotation::statement(alice.public_key(), bob.public_key())),
+            ..Rotation::new(&alice, *bob.public_key())
+        };
+        assert!(matches!(
+            doc.rotate(forged),
+            Err(DocError::Signature(key, _)) if key == *alice.public_key()
+        ));
+        assert!(matches!(
+            doc.rotate(Rotation::new(&alice, *bob.public_key())),
+            Err(DocError::Rotation(_))
+        ));
+
+        doc.rotate(Rotation::new(&alice, *alice_new.public_key()))
+            .unwrap();
+        assert_eq!(
+            doc.delegates,
+            NonEmpty::from((alice_new.public_key().into(), vec![bob.public_key().into()]))
+        );
+        assert_eq!(
+            doc.roles.keys().collect::<Vec<_>>(),
+            vec![&Did::from(alice_new.public_key())]
+        );
+        assert!(doc.is_authorized(alice_new.public_key(), Permission::Identity));
+        // The old key is attributed to the new one, but has no authority left.
+        assert_eq!(doc.resolve(alice.public_key()), *alice_new.public_key());
+        assert!(!doc.is_authorized(alice.public_key(), Permission::Identity));
+        assert!(!doc.is_authorized(alice.public_key(), Permission::Triage));
+        assert!(doc.roles_of(alice.public_key()).is_empty());
+
+        // Rotating back to a key that was rotated out is not allowed.
+        assert!(matches!(
+            doc.rotate(Rotation::new(&alice_new, *alice.public_key())),
+            Err(DocError::Rotation(_))
+        ));
+
+        let (_, bytes) = doc.encode().unwrap();
+        assert_eq!(Doc::from_json(&bytes).unwrap().verified().unwrap(), doc);
+
+        // Documents with cyclic rotations are rejected.
+        let mut cyclic = doc.clone().unverified();
+        cyclic
+            .rotations
+            .push(Rotation::new(&alice_new, *alice.public_key()));
+        assert!(matches!(cyclic.verified(), Err(DocError::Rotation(_))));
+    }
+
+    #[test]
+    fn test_visibility() {
+        let mut doc = arbitrary::gen::<Doc<Verified>>(1);
+        let (_, encoded) = doc.encode().unwrap();
+        let delegate = *doc.delegates.first().as_key();
+        let bob = arbitrary::gen::<PublicKey>(1);
+        let eve = arbitrary::gen::<PublicKey>(1);
+
+        assert!(!String::from_utf8(encoded).unwrap().contains("visibility"));
+        assert!(doc.is_visible_to(&eve));
+
+        doc.visibility = Visibility::private([Did::from(bob)]);
+        assert!(doc.is_visible_to(&delegate));
+        assert!(doc.is_visible_to(&bob));
+        assert!(!doc.is_visible_to(&eve));
+
+        let (_, bytes) = doc.encode().unwrap();
+        assert_eq!(Doc::from_json(&bytes).unwrap().verified().unwrap(), doc);
+    }
+
+    #[test]
+    fn test_canonical_refs() {
+        let mut doc = arbitrary::gen::<Doc<Verified>>(1).unverified();
+        assert!(doc.clone().verified().is_ok());
+
+        doc.canonical_refs = vec![git::fmt::refspec::pattern!("refs/heads/release/*")];
+        assert!(doc.clone().verified().is_ok());
+
+        doc.canonical_refs = vec![
+            git::fmt::refspec::pattern!("refs/heads/release/*"),
+            git::fmt::refspec::pattern!("refs/tags/*"),
+        ];
+        assert!(doc.clone().verified().is_ok());
+
+        doc.canonical_refs = vec![git::fmt::refspec::pattern!("refs/remotes/*")];
+        assert!(matches!(
+            doc.clone().verified(),
+            Err(DocError::CanonicalRefs(_))
+        ));
+
+        // Patterns can't match the same references.
+        for (a, b) in [
+            ("refs/heads/release/*", "refs/heads/release/*"),
+            ("refs/heads/*", "refs/heads/release/*"),
+            ("refs/heads/release/*", "refs/heads/release/v1"),
+            ("refs/heads/*/stable", "refs/heads/release/*"),
+        ] {
+            doc.canonical_refs = vec![
+                PatternString::try_from(a).unwrap(),
+                PatternString::try_from(b).unwrap(),
+            ];
+            assert!(matches!(
+                doc.clone().verified(),
+                Err(DocError::CanonicalRefs(_))
+            ));
+        }
+        doc.canonical_refs = vec![
+            git::fmt::refspec::pattern!("refs/heads/release/*"),
+            git::fmt::refspec::pattern!("refs/heads/stable/*"),
+        ];
+        assert!(doc.verified().is_ok());
+    }
+
     #[quickcheck]
     fn prop_encode_decode(doc: Doc<Verified>) {
         let (_, bytes) = doc.encode().unwrap();
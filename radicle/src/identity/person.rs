@@ -0,0 +1,313 @@
+//! Person identity documents.
+//!
+//! A person document describes the human behind a key, eg. their display name.
+//! It is stored on the `refs/rad/person` branch, under the key's namespace, and signed
+//! by the key. Since it lives in the key's namespace, it is covered by the signed refs
+//! and replicated along with the rest of the remote.
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use radicle_git_ext::Oid;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::canonical::formatter::CanonicalFormatter;
+use crate::crypto;
+use crate::crypto::{PublicKey, Signature, Signer};
+use crate::git;
+use crate::identity::doc::MAX_STRING_LENGTH;
+use crate::identity::Did;
+use crate::storage::git::trailers;
+use crate::storage::{ReadRepository, RemoteId};
+
+/// Path to the person document in the person branch.
+pub static PATH: Lazy<&Path> = Lazy::new(|| Path::new("person.json"));
+/// Maximum number of linked keys in a person document.
+pub const MAX_KEYS: usize = 255;
+
+#[derive(Error, Debug)]
+pub enum PersonError {
+    #[error("invalid person: {0}")]
+    Invalid(&'static str),
+    #[error("invalid commit: {0}")]
+    Commit(&'static str),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid signature for {0}: {1}")]
+    Signature(PublicKey, crypto::Error),
+    #[error("person document is not signed by {0}")]
+    MissingSignature(PublicKey),
+    #[error("invalid commit trailers: {0}")]
+    Trailers(#[from] trailers::Error),
+    #[error("git: {0}")]
+    GitExt(#[from] git::Error),
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+}
+
+impl PersonError {
+    /// Whether this error is caused by the document not being found.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Self::GitExt(git::Error::NotFound(_)) => true,
+            Self::GitExt(git::Error::Git(e)) if git::is_not_found_err(e) => true,
+            Self::Git(err) if git::is_not_found_err(err) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A person identity document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Person {
+    /// Display name.
+    pub name: String,
+    /// Email address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Git blob hash of the avatar image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Oid>,
+    /// Other keys belonging to this person, eg. on other devices.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<Did>,
+}
+
+impl Person {
+    /// Create a new person document with the given display name.
+    pub fn new(name: impl ToString) -> Result<Self, PersonError> {
+        let person = Self {
+            name: name.to_string(),
+            email: None,
+            avatar: None,
+            keys: Vec::new(),
+        };
+        person.validate()?;
+
+        Ok(person)
+    }
+
+    /// Check that the document is well-formed.
+    pub fn validate(&self) -> Result<(), PersonError> {
+        if self.name.trim().is_empty() {
+            return Err(PersonError::Invalid("name cannot be empty"));
+        }
+        if self.name.len() > MAX_STRING_LENGTH {
+            return Err(PersonError::Invalid("name cannot exceed 255 bytes"));
+        }
+        if let Some(email) = &self.email {
+            if email.len() > MAX_STRING_LENGTH {
+                return Err(PersonError::Invalid("email cannot exceed 255 bytes"));
+            }
+            if !email.contains('@') {
+                return Err(PersonError::Invalid("email is not valid"));
+            }
+        }
+        if self.keys.len() > MAX_KEYS {
+            return Err(PersonError::Invalid("number of keys cannot exceed 255"));
+        }
+        Ok(())
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, PersonError> {
+        let person: Self = serde_json::from_slice(bytes)?;
+        person.validate()?;
+
+        Ok(person)
+    }
+
+    pub fn encode(&self) -> Result<(git::Oid, Vec<u8>), PersonError> {
+        let mut buf = Vec::new();
+        let mut serializer =
+            serde_json::Serializer::with_formatter(&mut buf, CanonicalFormatter::new());
+
+        self.serialize(&mut serializer)?;
+        let oid = git2::Oid::hash_object(git2::ObjectType::Blob, &buf)?;
+
+        Ok((oid.into(), buf))
+    }
+
+    pub fn sign<G: Signer>(&self, signer: &G) -> Result<(git::Oid, Signature), PersonError> {
+        let (oid, _) = self.encode()?;
+        let sig = signer.sign(oid.as_bytes());
+
+        Ok((oid, sig))
+    }
+
+    /// Publish the document under the signer's namespace, on top of the existing
+    /// person branch, if any.
+    ///
+    /// Linked keys co-sign the document to show they belong to the same person: their
+    /// signatures, obtained with [`Person::sign`], are published along with the signer's.
+    pub fn publish<G: Signer>(
+        &self,
+        signer: &G,
+        cosignatures: &[(PublicKey, Signature)],
+        repo: &git2::Repository,
+    ) -> Result<git::Oid, PersonError> {
+        self.validate()?;
+
+        let remote = signer.public_key();
+        let (oid, doc) = self.encode()?;
+        for (key, sig) in cosignatures {
+            if !self.keys.contains(&Did::from(*key)) {
+                return Err(PersonError::Invalid("co-signing key is not linked"));
+            }
+            if let Err(err) = key.verify(oid.as_bytes(), sig) {
+                return Err(PersonError::Signature(*key, err));
+            }
+        }
+        let tree = git::write_tree(*PATH, doc.as_slice(), repo)?;
+        let person_ref = git::refs::storage::person(remote);
+        let parent = match repo.find_reference(&person_ref) {
+            Ok(r) => Some(r.peel_to_commit()?),
+            Err(e) if git::is_not_found_err(&e) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let sig = repo
+            .signature()
+            .or_else(|_| git2::Signature::now("radicle", remote.to_string().as_str()))?;
+
+        let mut msg = String::from("Update person\n\n");
+        writeln!(
+            &mut msg,
+            "{}: {remote} {}",
+            trailers::SIGNATURE_TRAILER,
+            signer.sign(oid.as_bytes())
+        )
+        .expect("in-memory writes don't fail");
+        for (key, sig) in cosignatures {
+            writeln!(&mut msg, "{}: {key} {sig}", trailers::SIGNATURE_TRAILER)
+                .expect("in-memory writes don't fail");
+        }
+
+        let commit = repo.commit(
+            Some(&person_ref),
+            &sig,
+            &sig,
+            &msg,
+            &tree,
+            parent.as_ref().into_iter().collect::<Vec<_>>().as_slice(),
+        )?;
+
+        Ok(commit.into())
+    }
+
+    /// Load and verify the person document of the given remote.
+    pub fn load<R: ReadRepository>(remote: &RemoteId, repo: &R) -> Result<PersonAt, PersonError> {
+        let commit = repo.reference_oid(remote, &git::refs::storage::PERSON_BRANCH)?;
+        let blob = repo.blob_at(commit, Path::new(&*PATH))?;
+        let person = Self::from_json(blob.content())?;
+        let msg = repo
+            .commit(commit)?
+            .message_raw()
+            .ok_or(PersonError::Commit("commit message is not UTF-8"))?
+            .to_owned();
+        let sigs = trailers::parse_signatures(&msg)?;
+
+        for (pk, sig) in &sigs {
+            if let Err(err) = pk.verify(blob.id().as_bytes(), sig) {
+                return Err(PersonError::Signature(*pk, err));
+            }
+        }
+        if !sigs.contains_key(remote) {
+            return Err(PersonError::MissingSignature(*remote));
+        }
+
+        Ok(PersonAt {
+            commit,
+            blob: blob.id().into(),
+            person,
+            sigs,
+        })
+    }
+}
+
+/// A verified person document at a specific commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersonAt {
+    /// The commit at which this document exists.
+    pub commit: Oid,
+    /// The document blob at this commit.
+    pub blob: Oid,
+    /// The parsed document.
+    pub person: Person,
+    /// The validated commit signatures.
+    pub sigs: HashMap<PublicKey, Signature>,
+}
+
+impl PersonAt {
+    /// Linked keys that also signed the document, and can thus be trusted to belong
+    /// to the same person.
+    pub fn linked(&self) -> impl Iterator<Item = &Did> {
+        self.person
+            .keys
+            .iter()
+            .filter(|key| self.sigs.contains_key(&***key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use radicle_crypto::test::signer::MockSigner;
+
+    use crate::storage::git::Storage;
+    use crate::storage::ReadStorage as _;
+    use crate::test::fixtures;
+
+    use super::*;
+
+    #[test]
+    fn test_publish_load() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rng = fastrand::Rng::new();
+        let alice = MockSigner::new(&mut rng);
+        let eve = MockSigner::new(&mut rng);
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        assert!(Person::load(alice.public_key(), &repo)
+            .unwrap_err()
+            .is_not_found());
+        assert!(Person::new(" ").is_err());
+
+        let mut person = Person::new("Alice").unwrap();
+        person.email = Some("alice@radicle.xyz".to_owned());
+        person.keys.push(Did::from(eve.public_key()));
+        person.publish(&alice, &[], repo.raw()).unwrap();
+
+        let at = Person::load(alice.public_key(), &repo).unwrap();
+        assert_eq!(at.person, person);
+        assert_eq!(at.linked().count(), 0);
+
+        // Eve's key co-signs the document, linking it to Alice's.
+        person.name = String::from("Alice Liddell");
+        let (_, sig) = person.sign(&eve).unwrap();
+        let head = person
+            .publish(&alice, &[(*eve.public_key(), sig)], repo.raw())
+            .unwrap();
+        let at = Person::load(alice.public_key(), &repo).unwrap();
+        assert_eq!(at.commit, head);
+        assert_eq!(at.person.name, "Alice Liddell");
+        assert_eq!(
+            at.linked().collect::<Vec<_>>(),
+            vec![&Did::from(eve.public_key())]
+        );
+        assert_eq!(repo.commit(head).unwrap().parent_count(), 1);
+
+        // Only linked keys can co-sign, and only the document itself.
+        let bob = MockSigner::new(&mut rng);
+        let (_, sig) = person.sign(&bob).unwrap();
+        assert!(person
+            .publish(&alice, &[(*bob.public_key(), sig)], repo.raw())
+            .is_err());
+        let sig = eve.sign(b"something else");
+        assert!(person
+            .publish(&alice, &[(*eve.public_key(), sig)], repo.raw())
+            .is_err());
+    }
+}
@@ -1,4 +1,6 @@
+use std::collections::BTreeMap;
 use std::fmt;
+use std::path::{Component, Path};
 
 use serde::{
     de::{self, MapAccess, Visitor},
@@ -22,6 +24,146 @@ pub enum ProjectError {
     Description(&'static str),
     #[error("invalid default branch: {0}")]
     DefaultBranch(&'static str),
+    #[error("invalid homepage: {0}")]
+    Homepage(&'static str),
+    #[error("invalid license: {0}")]
+    License(&'static str),
+    #[error("invalid keywords: {0}")]
+    Keywords(&'static str),
+    #[error("invalid logo: {0}")]
+    Logo(&'static str),
+    #[error("invalid mirrors: {0}")]
+    Mirrors(&'static str),
+}
+
+/// Maximum number of keywords of a project.
+pub const MAX_KEYWORDS: usize = 16;
+/// Maximum length of a project keyword.
+pub const MAX_KEYWORD_LENGTH: usize = 32;
+/// Maximum number of mirrors of a project.
+pub const MAX_MIRRORS: usize = 16;
+
+/// Who is allowed to open issues or patches on a project.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Policy {
+    /// Anyone can open them.
+    #[default]
+    Open,
+    /// Only delegates can open them.
+    Delegates,
+    /// They are disabled for this project.
+    Disabled,
+}
+
+impl Policy {
+    /// Whether this is the default policy.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open => write!(f, "open"),
+            Self::Delegates => write!(f, "delegates"),
+            Self::Disabled => write!(f, "disabled"),
+        }
+    }
+}
+
+/// Optional project metadata.
+///
+/// All fields are optional and omitted from the payload when unset, so that documents
+/// without metadata are encoded the same way as before.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    /// Project homepage URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// Project license, as an SPDX license expression, eg. `MIT OR Apache-2.0`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Project keywords.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Path to the project logo, relative to the repository root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo: Option<String>,
+    /// Who can open issues.
+    #[serde(skip_serializing_if = "Policy::is_default")]
+    pub issue_policy: Policy,
+    /// Who can open patches.
+    #[serde(skip_serializing_if = "Policy::is_default")]
+    pub patch_policy: Policy,
+    /// Additional URLs the project's repository is mirrored at.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+    /// When the project was archived by its delegates, in seconds since the epoch.
+    /// Archived projects are read-only: nodes don't accept new issues, patches or
+    /// pushes from non-delegates made under an identity that archived the project.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived: Option<u64>,
+}
+
+impl Metadata {
+    /// Validate the metadata, returning all errors found.
+    fn validate(&self) -> Vec<ProjectError> {
+        let mut errs = Vec::new();
+
+        if let Some(homepage) = &self.homepage {
+            if let Err(e) = validate_url(homepage, &["https", "http"]) {
+                errs.push(ProjectError::Homepage(e));
+            }
+        }
+        if let Some(license) = &self.license {
+            if let Err(e) = validate_license(license) {
+                errs.push(ProjectError::License(e));
+            }
+        }
+
+        if self.keywords.len() > MAX_KEYWORDS {
+            errs.push(ProjectError::Keywords("cannot have more than 16 keywords"));
+        }
+        if self.keywords.iter().any(|k| k.is_empty()) {
+            errs.push(ProjectError::Keywords("keyword cannot be empty"));
+        } else if self.keywords.iter().any(|k| k.len() > MAX_KEYWORD_LENGTH) {
+            errs.push(ProjectError::Keywords("keyword cannot exceed 32 bytes"));
+        } else if self
+            .keywords
+            .iter()
+            .any(|k| k.contains(char::is_whitespace))
+        {
+            errs.push(ProjectError::Keywords("keyword cannot contain whitespace"));
+        }
+
+        if let Some(logo) = &self.logo {
+            let path = Path::new(logo);
+
+            if logo.is_empty() {
+                errs.push(ProjectError::Logo("logo path cannot be empty"));
+            } else if logo.len() > doc::MAX_STRING_LENGTH {
+                errs.push(ProjectError::Logo("logo path cannot exceed 255 bytes"));
+            } else if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+                errs.push(ProjectError::Logo(
+                    "logo path must be relative to the repository root",
+                ));
+            }
+        }
+
+        if self.mirrors.len() > MAX_MIRRORS {
+            errs.push(ProjectError::Mirrors("cannot have more than 16 mirrors"));
+        }
+        for mirror in &self.mirrors {
+            if let Err(e) = validate_url(mirror, &["https", "http", "git", "ssh"]) {
+                errs.push(ProjectError::Mirrors(e));
+                break;
+            }
+        }
+        errs
+    }
 }
 
 /// A "project" payload in an identity document.
@@ -34,6 +176,13 @@ pub struct Project {
     description: String,
     /// Project default branch.
     default_branch: BranchName,
+    /// Optional project metadata.
+    #[serde(flatten)]
+    metadata: Metadata,
+    /// Fields unknown to this version, eg. added by future versions. They are kept as-is,
+    /// so that documents can be updated without losing them.
+    #[serde(flatten)]
+    unknown: BTreeMap<String, serde_json::Value>,
 }
 
 impl<'de> Deserialize<'de> for Project {
@@ -41,14 +190,6 @@ impl<'de> Deserialize<'de> for Project {
     where
         D: serde::Deserializer<'de>,
     {
-        #[derive(Deserialize)]
-        #[serde(field_identifier, rename_all = "camelCase")]
-        enum Field {
-            Name,
-            Description,
-            DefaultBranch,
-        }
-
         struct ProjectVisitor;
 
         impl<'de> Visitor<'de> for ProjectVisitor {
@@ -65,27 +206,91 @@ impl<'de> Deserialize<'de> for Project {
                 let mut name = None;
                 let mut description = None;
                 let mut default_branch = None;
+                let mut homepage = None;
+                let mut license = None;
+                let mut keywords = None;
+                let mut logo = None;
+                let mut issue_policy = None;
+                let mut patch_policy = None;
+                let mut mirrors = None;
+                let mut archived = None;
+                let mut unknown = BTreeMap::new();
 
-                while let Some(key) = map.next_key()? {
-                    match key {
-                        Field::Name => {
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "name" => {
                             if name.is_some() {
                                 return Err(de::Error::duplicate_field("name"));
                             }
                             name = Some(map.next_value()?);
                         }
-                        Field::Description => {
+                        "description" => {
                             if description.is_some() {
                                 return Err(de::Error::duplicate_field("description"));
                             }
                             description = Some(map.next_value()?);
                         }
-                        Field::DefaultBranch => {
+                        "defaultBranch" => {
                             if default_branch.is_some() {
                                 return Err(de::Error::duplicate_field("defaultBranch"));
                             }
                             default_branch = Some(map.next_value()?);
                         }
+                        "homepage" => {
+                            if homepage.is_some() {
+                                return Err(de::Error::duplicate_field("homepage"));
+                            }
+                            homepage = Some(map.next_value()?);
+                        }
+                        "license" => {
+                            if license.is_some() {
+                                return Err(de::Error::duplicate_field("license"));
+                            }
+                            license = Some(map.next_value()?);
+                        }
+                        "keywords" => {
+                            if keywords.is_some() {
+                                return Err(de::Error::duplicate_field("keywords"));
+                            }
+                            keywords = Some(map.next_value()?);
+                        }
+                        "logo" => {
+                            if logo.is_some() {
+                                return Err(de::Error::duplicate_field("logo"));
+                            }
+                            logo = Some(map.next_value()?);
+                        }
+                        "issuePolicy" => {
+                            if issue_policy.is_some() {
+                                return Err(de::Error::duplicate_field("issuePolicy"));
+                            }
+                            issue_policy = Some(map.next_value()?);
+                        }
+                        "patchPolicy" => {
+                            if patch_policy.is_some() {
+                                return Err(de::Error::duplicate_field("patchPolicy"));
+                            }
+                            patch_policy = Some(map.next_value()?);
+                        }
+                        "mirrors" => {
+                            if mirrors.is_some() {
+                                return Err(de::Error::duplicate_field("mirrors"));
+                            }
+                            mirrors = Some(map.next_value()?);
+                        }
+                        "archived" => {
+                            if archived.is_some() {
+                                return Err(de::Error::duplicate_field("archived"));
+                            }
+                            archived = Some(map.next_value()?);
+                        }
+                        _ => {
+                            if unknown.contains_key(&key) {
+                                return Err(de::Error::custom(format!("duplicate field `{key}`")));
+                            }
+                            let value = map.next_value()?;
+                            unknown.insert(key, value);
+                        }
                     }
                 }
                 let name = name.ok_or_else(|| de::Error::missing_field("name"))?;
@@ -93,17 +298,43 @@ impl<'de> Deserialize<'de> for Project {
                     description.ok_or_else(|| de::Error::missing_field("description"))?;
                 let default_branch =
                     default_branch.ok_or_else(|| de::Error::missing_field("defaultBranch"))?;
-                Project::new(name, description, default_branch).map_err(|errs| {
-                    de::Error::custom(
-                        errs.into_iter()
-                            .map(|err| err.to_string())
-                            .collect::<Vec<_>>()
-                            .join(", "),
-                    )
-                })
+                let metadata = Metadata {
+                    homepage,
+                    license,
+                    keywords: keywords.unwrap_or_default(),
+                    logo,
+                    issue_policy: issue_policy.unwrap_or_default(),
+                    patch_policy: patch_policy.unwrap_or_default(),
+                    mirrors: mirrors.unwrap_or_default(),
+                    archived,
+                };
+
+                Project::new(name, description, default_branch)
+                    .and_then(|p| p.with_metadata(metadata))
+                    .map(|p| Project { unknown, ..p })
+                    .map_err(|errs| {
+                        de::Error::custom(
+                            errs.into_iter()
+                                .map(|err| err.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        )
+                    })
             }
         }
-        const FIELDS: &[&str] = &["name", "descrption", "defaultBranch"];
+        const FIELDS: &[&str] = &[
+            "name",
+            "description",
+            "defaultBranch",
+            "homepage",
+            "license",
+            "keywords",
+            "logo",
+            "issuePolicy",
+            "patchPolicy",
+            "mirrors",
+            "archived",
+        ];
         deserializer.deserialize_struct("Project", FIELDS, ProjectVisitor)
     }
 }
@@ -152,6 +383,8 @@ impl Project {
                 name,
                 description,
                 default_branch,
+                metadata: Metadata::default(),
+                unknown: BTreeMap::new(),
             })
         } else {
             Err(errs)
@@ -174,7 +407,32 @@ impl Project {
         let name = name.into().unwrap_or(self.name);
         let description = description.into().unwrap_or(self.description);
         let default_branch = default_branch.into().unwrap_or(self.default_branch);
-        Self::new(name, description, default_branch)
+        let unknown = self.unknown;
+
+        Self::new(name, description, default_branch)?
+            .with_metadata(self.metadata)
+            .map(|p| Self { unknown, ..p })
+    }
+
+    /// Set the optional project metadata.
+    ///
+    /// # Validation Rules
+    ///
+    ///   * `homepage` must be an `http` or `https` URL, not exceeding 255 bytes.
+    ///   * `license` must be a valid SPDX license expression, not exceeding 255 bytes.
+    ///   * There can be at most 16 `keywords`, and they must not be empty, must not exceed
+    ///     32 bytes and must not contain whitespace.
+    ///   * `logo` must be a non-empty relative path, not exceeding 255 bytes.
+    ///   * There can be at most 16 `mirrors`, which must be `http`, `https`, `git` or `ssh` URLs.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Result<Self, Vec<ProjectError>> {
+        let errs = metadata.validate();
+
+        if errs.is_empty() {
+            self.metadata = metadata;
+            Ok(self)
+        } else {
+            Err(errs)
+        }
     }
 
     #[inline]
@@ -191,6 +449,97 @@ impl Project {
     pub fn default_branch(&self) -> &BranchName {
         &self.default_branch
     }
+
+    #[inline]
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Fields of the payload that are unknown to this version.
+    #[inline]
+    pub fn unknown(&self) -> &BTreeMap<String, serde_json::Value> {
+        &self.unknown
+    }
+
+    /// When the project was archived, if it was, in seconds since the epoch.
+    #[inline]
+    pub fn archived(&self) -> Option<u64> {
+        self.metadata.archived
+    }
+
+    /// Archive or unarchive the project. Pass the current time to archive it.
+    pub fn set_archived(&mut self, archived: Option<u64>) {
+        self.metadata.archived = archived;
+    }
+}
+
+/// Validate a URL, given the allowed schemes.
+fn validate_url(url: &str, schemes: &[&str]) -> Result<(), &'static str> {
+    if url.len() > doc::MAX_STRING_LENGTH {
+        return Err("URL cannot exceed 255 bytes");
+    }
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Err("URL must include a scheme");
+    };
+    if !schemes.contains(&scheme) {
+        return Err("URL scheme is not supported");
+    }
+    if rest.is_empty() || rest.starts_with('/') {
+        return Err("URL must include a host");
+    }
+    if url.contains(char::is_whitespace) {
+        return Err("URL cannot contain whitespace");
+    }
+    Ok(())
+}
+
+/// Validate an SPDX license expression, eg. `MIT`, `GPL-3.0-or-later`, `MIT OR Apache-2.0`
+/// or `(MIT AND BSD-2-Clause) OR GPL-2.0-only WITH Classpath-exception-2.0`.
+///
+/// Only the syntax is checked, not whether the license identifiers exist.
+fn validate_license(license: &str) -> Result<(), &'static str> {
+    if license.is_empty() {
+        return Err("license cannot be empty");
+    }
+    if license.len() > doc::MAX_STRING_LENGTH {
+        return Err("license cannot exceed 255 bytes");
+    }
+    let expr = license.replace('(', " ( ").replace(')', " ) ");
+    let mut depth = 0usize;
+    // Whether we expect a license identifier next, as opposed to an operator.
+    let mut operand = true;
+
+    for token in expr.split_whitespace() {
+        match token {
+            "(" if operand => depth += 1,
+            ")" if !operand => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or("unbalanced parentheses in license expression")?;
+            }
+            "AND" | "OR" | "WITH" if !operand => operand = true,
+            "AND" | "OR" | "WITH" | "(" | ")" => return Err("invalid license expression"),
+            id if operand => {
+                let id = id.strip_suffix('+').unwrap_or(id);
+                if id.is_empty()
+                    || !id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == ':')
+                {
+                    return Err("invalid license identifier");
+                }
+                operand = false;
+            }
+            _ => return Err("invalid license expression"),
+        }
+    }
+    if operand {
+        return Err("incomplete license expression");
+    }
+    if depth != 0 {
+        return Err("unbalanced parentheses in license expression");
+    }
+    Ok(())
 }
 
 impl From<Project> for Payload {
@@ -201,3 +550,144 @@ impl From<Project> for Payload {
         Self::from(value)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::git;
+
+    fn project() -> Project {
+        Project::new(
+            String::from("heartwood"),
+            String::from("Radicle Heartwood Protocol & Stack"),
+            git::refname!("master"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_license() {
+        for valid in [
+            "MIT",
+            "GPL-3.0-or-later",
+            "GPL-2.0+",
+            "MIT OR Apache-2.0",
+            "(MIT AND BSD-2-Clause) OR GPL-2.0-only WITH Classpath-exception-2.0",
+            "LicenseRef-Custom",
+        ] {
+            assert!(validate_license(valid).is_ok(), "{valid}");
+        }
+        for invalid in [
+            "",
+            "MIT OR",
+            "OR MIT",
+            "MIT Apache-2.0",
+            "(MIT",
+            "MIT)",
+            "()",
+            "M!T",
+            "MIT OR AND",
+        ] {
+            assert!(validate_license(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_metadata() {
+        let metadata = Metadata {
+            homepage: Some(String::from("https://radicle.xyz")),
+            license: Some(String::from("MIT OR Apache-2.0")),
+            keywords: vec![String::from("p2p"), String::from("git")],
+            logo: Some(String::from("assets/logo.svg")),
+            issue_policy: Policy::Open,
+            patch_policy: Policy::Delegates,
+            mirrors: vec![String::from("https://github.com/radicle-dev/heartwood")],
+            archived: Some(1671125284),
+        };
+        let proj = project().with_metadata(metadata.clone()).unwrap();
+        assert_eq!(proj.metadata(), &metadata);
+
+        // Metadata is kept on update.
+        let proj = proj.update(None, String::from("Heartwood"), None).unwrap();
+        assert_eq!(proj.metadata(), &metadata);
+
+        let errs = project()
+            .with_metadata(Metadata {
+                homepage: Some(String::from("radicle.xyz")),
+                keywords: vec![String::from("peer to peer")],
+                logo: Some(String::from("../logo.svg")),
+                mirrors: vec![String::from("ftp://example.com")],
+                ..Metadata::default()
+            })
+            .unwrap_err();
+        assert_eq!(errs.len(), 4);
+    }
+
+    #[test]
+    fn test_encoding() {
+        // Projects without metadata are encoded like before.
+        let proj = project();
+        assert_eq!(
+            serde_json::to_value(&proj).unwrap(),
+            serde_json::json!({
+                "name": "heartwood",
+                "description": "Radicle Heartwood Protocol & Stack",
+                "defaultBranch": "master",
+            })
+        );
+
+        let proj = proj
+            .with_metadata(Metadata {
+                license: Some(String::from("MIT")),
+                issue_policy: Policy::Disabled,
+                ..Metadata::default()
+            })
+            .unwrap();
+        let value = serde_json::to_value(&proj).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "name": "heartwood",
+                "description": "Radicle Heartwood Protocol & Stack",
+                "defaultBranch": "master",
+                "license": "MIT",
+                "issuePolicy": "disabled",
+            })
+        );
+        assert_eq!(serde_json::from_value::<Project>(value).unwrap(), proj);
+    }
+
+    #[test]
+    fn test_decoding() {
+        // Unknown fields are preserved.
+        let value = serde_json::json!({
+            "name": "heartwood",
+            "description": "Radicle Heartwood Protocol & Stack",
+            "defaultBranch": "master",
+            "unknown": { "field": true },
+        });
+        let proj: Project = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(
+            proj.unknown().get("unknown"),
+            Some(&serde_json::json!({ "field": true }))
+        );
+        assert_eq!(serde_json::to_value(&proj).unwrap(), value);
+
+        // And kept on update.
+        let proj = proj.update(None, String::from("Heartwood"), None).unwrap();
+        assert_eq!(
+            proj.unknown().get("unknown"),
+            Some(&serde_json::json!({ "field": true }))
+        );
+
+        // Metadata is validated.
+        let err = serde_json::from_value::<Project>(serde_json::json!({
+            "name": "heartwood",
+            "description": "Radicle Heartwood Protocol & Stack",
+            "defaultBranch": "master",
+            "license": "MIT OR",
+        }))
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid license"), "{err}");
+    }
+}
@@ -16,6 +16,7 @@ pub mod identity;
 pub mod node;
 pub mod profile;
 pub mod rad;
+pub mod release;
 pub mod serde_ext;
 pub mod sql;
 pub mod storage;
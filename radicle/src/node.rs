@@ -138,6 +138,10 @@ pub enum CommandName {
     TrackNode,
     /// Untrack the given node.
     UntrackNode,
+    /// Get the fetches rejected for exceeding a storage quota.
+    Rejections,
+    /// Subscribe to the node events.
+    Subscribe,
     /// Get the node's status.
     Status,
     /// Shutdown the node.
@@ -248,6 +252,58 @@ impl Seeds {
     }
 }
 
+/// A fetch that was rejected because it exceeded a storage quota.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rejection {
+    /// The repository that was fetched.
+    pub rid: Id,
+    /// The node it was fetched from.
+    pub from: NodeId,
+    /// Why the fetch was rejected.
+    pub reason: String,
+}
+
+/// A node event, streamed to subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    /// A peer connected.
+    PeerConnected { nid: NodeId },
+    /// A peer disconnected.
+    PeerDisconnected { nid: NodeId, reason: String },
+    /// Refs were fetched from a remote.
+    RefsFetched {
+        remote: NodeId,
+        rid: Id,
+        updated: Vec<RefUpdate>,
+    },
+    /// A fetch from a remote failed.
+    FetchFailed {
+        remote: NodeId,
+        rid: Id,
+        reason: String,
+    },
+    /// A refs announcement was received.
+    RefsAnnounced {
+        nid: NodeId,
+        rid: Id,
+        timestamp: Timestamp,
+    },
+    /// An inventory announcement was received.
+    InventoryAnnounced {
+        nid: NodeId,
+        inventory: Vec<Id>,
+        timestamp: Timestamp,
+    },
+    /// A node announcement was received.
+    NodeAnnounced {
+        nid: NodeId,
+        alias: String,
+        timestamp: Timestamp,
+    },
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "kebab-case")]
 pub enum FetchResult {
@@ -388,6 +444,8 @@ pub trait Handle {
     fn seeds(&mut self, id: Id) -> Result<Seeds, Self::Error>;
     /// Fetch a repository from the network.
     fn fetch(&mut self, id: Id, from: NodeId) -> Result<FetchResult, Self::Error>;
+    /// Get the latest fetches that were rejected for exceeding a storage quota.
+    fn rejections(&mut self) -> Result<Vec<Rejection>, Self::Error>;
     /// Start tracking the given project. Doesn't do anything if the project is already
     /// tracked.
     fn track_repo(&mut self, id: Id, scope: tracking::Scope) -> Result<bool, Self::Error>;
@@ -407,8 +465,14 @@ pub trait Handle {
     fn shutdown(self) -> Result<(), Self::Error>;
     /// Query the peer session state.
     fn sessions(&self) -> Result<Self::Sessions, Self::Error>;
+    /// Subscribe to the node events. The events are streamed until the returned iterator is
+    /// dropped, or until the subscriber falls too far behind the node.
+    fn subscribe(&self) -> Result<Events<Self::Error>, Self::Error>;
 }
 
+/// Stream of node events.
+pub type Events<E> = Box<dyn Iterator<Item = Result<Event, E>> + Send>;
+
 /// Public node & device identifier.
 pub type NodeId = PublicKey;
 
@@ -495,6 +559,17 @@ impl Handle for Node {
         Ok(result)
     }
 
+    fn rejections(&mut self) -> Result<Vec<Rejection>, Error> {
+        let rejections = self
+            .call::<&str, _>(CommandName::Rejections, [])?
+            .next()
+            .ok_or(Error::EmptyResponse {
+                cmd: CommandName::Rejections,
+            })??;
+
+        Ok(rejections)
+    }
+
     fn track_node(&mut self, id: NodeId, alias: Option<String>) -> Result<bool, Error> {
         let id = id.to_human();
         let args = if let Some(alias) = alias.as_deref() {
@@ -565,6 +640,12 @@ impl Handle for Node {
         todo!();
     }
 
+    fn subscribe(&self) -> Result<Events<Error>, Error> {
+        let events = self.call::<&str, Event>(CommandName::Subscribe, [])?;
+
+        Ok(Box::new(events.map(|e| e.map_err(Error::from))))
+    }
+
     fn shutdown(self) -> Result<(), Error> {
         todo!();
     }
@@ -30,3 +30,12 @@ create table if not exists "repo-policies" (
   "policy"             text      default 'track'
   --
 ) strict;
+
+-- Large object storage policy. See `radicle::storage::git::lfs`.
+create table if not exists "large-object-policies" (
+  -- Repository ID.
+  "id"                 text      primary key not null,
+  -- Whether the large objects of this repository are stored.
+  "policy"             text      default 'block'
+  --
+) strict;
@@ -123,6 +123,22 @@ impl Config {
         Ok(self.db.change_count() > 0)
     }
 
+    /// Set a repository's large object storage policy.
+    pub fn set_large_objects_policy(&mut self, id: &Id, policy: Policy) -> Result<bool, Error> {
+        let mut stmt = self.db.prepare(
+            "INSERT INTO `large-object-policies` (id, policy)
+             VALUES (?1, ?2)
+             ON CONFLICT DO UPDATE
+             SET policy = ?2 WHERE policy != ?2",
+        )?;
+
+        stmt.bind((1, id))?;
+        stmt.bind((2, policy))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count() > 0)
+    }
+
     /// Untrack a node.
     pub fn untrack_node(&mut self, id: &NodeId) -> Result<bool, Error> {
         let mut stmt = self
@@ -209,6 +225,20 @@ impl Config {
         Ok(None)
     }
 
+    /// Get a repository's large object storage policy. Large objects aren't stored by default.
+    pub fn large_objects_policy(&self, id: &Id) -> Result<Policy, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT policy FROM `large-object-policies` WHERE id = ?")?;
+
+        stmt.bind((1, id))?;
+
+        if let Some(Ok(row)) = stmt.into_iter().next() {
+            return Ok(row.read::<Policy, _>("policy"));
+        }
+        Ok(Policy::Block)
+    }
+
     /// Get node tracking policies.
     pub fn node_policies(&self) -> Result<Box<dyn Iterator<Item = Node>>, Error> {
         let mut stmt = self
@@ -349,6 +379,17 @@ mod test {
         assert_eq!(db.repo_policy(&id).unwrap().unwrap().policy, Policy::Block);
     }
 
+    #[test]
+    fn test_large_objects_policy() {
+        let id = arbitrary::gen::<Id>(1);
+        let mut db = Config::open(":memory:").unwrap();
+
+        assert_eq!(db.large_objects_policy(&id).unwrap(), Policy::Block);
+        assert!(db.set_large_objects_policy(&id, Policy::Track).unwrap());
+        assert!(!db.set_large_objects_policy(&id, Policy::Track).unwrap());
+        assert_eq!(db.large_objects_policy(&id).unwrap(), Policy::Track);
+    }
+
     #[test]
     fn test_node_policy() {
         let id = arbitrary::gen::<NodeId>(1);
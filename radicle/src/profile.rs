@@ -9,7 +9,18 @@
 //!       radicle.pub                            # Public key (PKCS 8)
 //!     node/
 //!       radicle.sock                           # Node control socket
+//!     config.json                              # Profile configuration
+//!     profile                                  # Name of the active profile
+//!     profiles/
+//!       work/                                  # Named profile, with the same layout
+//!         storage -> $RAD_HOME/storage         # Storage, if shared with the default profile
+//!         keys/
+//!         node/
 //!
+//! The default profile lives at the root of the radicle home. Named profiles each have their
+//! own keys and node, and either their own storage, or the storage of the default profile.
+pub mod config;
+
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 
@@ -23,6 +34,8 @@ use crate::prelude::Did;
 use crate::storage::git::transport;
 use crate::storage::git::Storage;
 
+pub use config::{Config, ConfigError};
+
 /// Environment variables used by radicle.
 pub mod env {
     pub use std::env::*;
@@ -33,6 +46,8 @@ pub mod env {
     pub const RAD_SOCKET: &str = "RAD_SOCKET";
     /// Passphrase for the encrypted radicle secret key.
     pub const RAD_PASSPHRASE: &str = "RAD_PASSPHRASE";
+    /// Name of the active profile. Takes precedence over the profile set with `rad self`.
+    pub const RAD_PROFILE: &str = "RAD_PROFILE";
 
     pub fn passphrase() -> Option<super::Passphrase> {
         let Ok(passphrase) = std::env::var(RAD_PASSPHRASE) else {
@@ -82,8 +97,13 @@ impl Profile {
         })
     }
 
+    /// Load the active profile.
     pub fn load() -> Result<Self, Error> {
-        let home = self::home()?;
+        Self::load_from(self::home()?)
+    }
+
+    /// Load the profile at the given home.
+    pub fn load_from(home: Home) -> Result<Self, Error> {
         let storage = Storage::open(home.storage())?;
         let keystore = Keystore::new(&home.keys());
         let public_key = keystore
@@ -141,14 +161,31 @@ impl Profile {
     pub fn socket(&self) -> PathBuf {
         self.home.socket()
     }
+
+    /// Load the profile configuration.
+    pub fn config(&self) -> Result<Config, ConfigError> {
+        Config::load(&self.home.config())
+    }
 }
 
-/// Get the path to the radicle home folder.
+/// Name of the default profile, which lives at the root of the radicle home.
+pub const DEFAULT_PROFILE: &str = "default";
+/// File holding the name of the active profile, under the radicle home root.
+const ACTIVE_PROFILE_FILE: &str = "profile";
+/// Directory holding the named profiles, under the radicle home root.
+const PROFILES_DIR: &str = "profiles";
+
+/// Get the path to the radicle home folder of the active profile.
 pub fn home() -> Result<Home, io::Error> {
+    Home::new(self::path(&active()?)?)
+}
+
+/// Get the path to the radicle home root, which holds the default and named profiles.
+pub fn root() -> Result<PathBuf, io::Error> {
     if let Some(home) = env::var_os(env::RAD_HOME) {
-        Ok(Home::new(PathBuf::from(home))?)
+        Ok(PathBuf::from(home))
     } else if let Some(home) = env::var_os("HOME") {
-        Ok(Home::new(PathBuf::from(home).join(".radicle"))?)
+        Ok(PathBuf::from(home).join(".radicle"))
     } else {
         Err(io::Error::new(
             io::ErrorKind::NotFound,
@@ -157,6 +194,133 @@ pub fn home() -> Result<Home, io::Error> {
     }
 }
 
+/// Get the path to the radicle home folder of the given profile.
+pub fn path(name: &str) -> Result<PathBuf, io::Error> {
+    Root::new(root()?).profile(name)
+}
+
+/// Get the name of the active profile.
+pub fn active() -> Result<String, io::Error> {
+    if let Ok(name) = env::var(env::RAD_PROFILE) {
+        return Ok(name);
+    }
+    Root::new(root()?).active()
+}
+
+/// Set the active profile. The profile must exist.
+pub fn set_active(name: &str) -> Result<(), Error> {
+    Root::new(root()?).set_active(name)
+}
+
+/// Get the names of the existing profiles, starting with the default one.
+pub fn profiles() -> Result<Vec<String>, io::Error> {
+    Root::new(root()?).profiles()
+}
+
+/// Create the radicle home folder of the given profile, if it doesn't exist. See
+/// [`Root::create`].
+pub fn create(name: &str, share_storage: bool) -> Result<Home, io::Error> {
+    Root::new(root()?).create(name, share_storage)
+}
+
+/// Radicle home root, which holds the default and named profiles.
+#[derive(Debug, Clone)]
+pub struct Root {
+    path: PathBuf,
+}
+
+impl Root {
+    /// Use the given path as radicle home root. See [`root`] for the root of the environment.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Get the path to the radicle home folder of the given profile.
+    pub fn profile(&self, name: &str) -> Result<PathBuf, io::Error> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid profile name '{name}'"),
+            ));
+        }
+        if name == DEFAULT_PROFILE {
+            Ok(self.path.clone())
+        } else {
+            Ok(self.path.join(PROFILES_DIR).join(name))
+        }
+    }
+
+    /// Get the name of the active profile, as set with [`Root::set_active`].
+    pub fn active(&self) -> Result<String, io::Error> {
+        match fs::read_to_string(self.path.join(ACTIVE_PROFILE_FILE)) {
+            Ok(name) => Ok(name.trim().to_owned()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(DEFAULT_PROFILE.to_owned()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Set the active profile. The profile must exist.
+    pub fn set_active(&self, name: &str) -> Result<(), Error> {
+        let path = self.profile(name)?;
+        if !path.join("keys").exists() {
+            return Err(Error::NotFound(path));
+        }
+        fs::write(self.path.join(ACTIVE_PROFILE_FILE), name)?;
+
+        Ok(())
+    }
+
+    /// Get the names of the existing profiles, starting with the default one.
+    pub fn profiles(&self) -> Result<Vec<String>, io::Error> {
+        let mut profiles = vec![DEFAULT_PROFILE.to_owned()];
+
+        match fs::read_dir(self.path.join(PROFILES_DIR)) {
+            Ok(entries) => {
+                for entry in entries {
+                    let entry = entry?;
+                    if entry.path().join("keys").exists() {
+                        profiles.push(entry.file_name().to_string_lossy().into_owned());
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        profiles[1..].sort();
+
+        Ok(profiles)
+    }
+
+    /// Create the radicle home folder of the given profile, if it doesn't exist. When
+    /// `share_storage` is set, a named profile uses the storage of the default profile, and
+    /// it is an error for it to already have its own storage.
+    pub fn create(&self, name: &str, share_storage: bool) -> Result<Home, io::Error> {
+        let path = self.profile(name)?;
+
+        if share_storage && name != DEFAULT_PROFILE {
+            let default = Home::new(&self.path)?;
+            let storage = path.join("storage");
+
+            if fs::symlink_metadata(&storage).is_ok() {
+                if fs::read_link(&storage).ok() != Some(default.storage()) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("profile '{name}' already has its own storage"),
+                    ));
+                }
+            } else {
+                fs::create_dir_all(&path)?;
+                std::os::unix::fs::symlink(default.storage(), storage)?;
+            }
+        }
+        Home::new(path)
+    }
+}
+
 /// Radicle home.
 #[derive(Debug, Clone)]
 pub struct Home {
@@ -217,19 +381,46 @@ impl Home {
         self.path.join("node")
     }
 
+    pub fn config(&self) -> PathBuf {
+        self.path.join(config::FILE)
+    }
+
     pub fn socket(&self) -> PathBuf {
         env::var_os(env::RAD_SOCKET)
             .map(PathBuf::from)
             .unwrap_or_else(|| self.node().join(node::DEFAULT_SOCKET_NAME))
     }
+
+    /// Whether the storage is shared between profiles, ie. this is a named profile using the
+    /// storage of the default profile, or the default profile whose storage named profiles
+    /// use. Shared storage holds the namespaces of more than one local key.
+    pub fn is_storage_shared(&self) -> Result<bool, io::Error> {
+        let storage = self.storage();
+
+        if fs::symlink_metadata(&storage)?.file_type().is_symlink() {
+            return Ok(true);
+        }
+        let entries = match fs::read_dir(self.path.join(PROFILES_DIR)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            if fs::read_link(entry?.path().join("storage")).ok().as_ref() == Some(&storage) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use std::fs;
+    use std::io;
     use std::path::Path;
 
-    use super::Home;
+    use super::*;
 
     // Checks that if we have:
     // '/run/user/1000/.tmpqfK6ih/../.tmpqfK6ih/Radicle/Home'
@@ -258,4 +449,43 @@ mod test {
 
         assert_eq!(home.path, path);
     }
+
+    #[test]
+    fn test_profiles() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = Root::new(tmp.path().canonicalize().unwrap());
+        let default = Home::new(&root.path).unwrap();
+
+        assert_eq!(root.active().unwrap(), DEFAULT_PROFILE);
+        assert_eq!(root.profile(DEFAULT_PROFILE).unwrap(), root.path);
+        assert!(root.profile("../work").is_err());
+        assert!(matches!(root.set_active("work"), Err(Error::NotFound(_))));
+        assert!(!default.is_storage_shared().unwrap());
+
+        // Named profiles can share the storage of the default profile.
+        let work = root.create("work", true).unwrap();
+        assert_eq!(work.path(), root.path.join(PROFILES_DIR).join("work"));
+        assert_eq!(fs::read_link(work.storage()).unwrap(), default.storage());
+        assert_eq!(root.create("work", true).unwrap().path(), work.path());
+        assert!(work.is_storage_shared().unwrap());
+        assert!(default.is_storage_shared().unwrap());
+
+        // Or have their own.
+        let own = root.create("own", false).unwrap();
+        assert!(own.storage().is_dir());
+        assert!(fs::read_link(own.storage()).is_err());
+        assert!(!own.is_storage_shared().unwrap());
+        assert_eq!(
+            root.create("own", true).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(
+            root.profiles().unwrap(),
+            vec![DEFAULT_PROFILE, "own", "work"]
+        );
+
+        root.set_active("work").unwrap();
+        assert_eq!(root.active().unwrap(), "work");
+        assert_eq!(root.profile(&root.active().unwrap()).unwrap(), work.path());
+    }
 }
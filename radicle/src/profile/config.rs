@@ -0,0 +1,266 @@
+//! Profile configuration.
+//!
+//! The configuration is stored as JSON in the profile home, and is shared by the node, the
+//! CLI and the HTTP daemon. All settings are optional: missing settings take their default
+//! values, and command-line flags take precedence over the configuration file.
+//!
+//! Settings are addressed by dotted keys, eg. `node.trackingPolicy` or `cli.color`.
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::{fs, io};
+
+use cyphernet::addr::PeerAddr;
+use serde::{Deserialize, Serialize};
+use serde_json as json;
+use thiserror::Error;
+
+use crate::identity::Id;
+use crate::node::tracking::{Policy, Scope};
+use crate::node::{Address, NodeId};
+
+/// Name of the configuration file, under the profile home.
+pub const FILE: &str = "config.json";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("i/o error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid configuration: {0}")]
+    Json(#[from] json::Error),
+    #[error("unknown configuration key '{0}'")]
+    UnknownKey(String),
+    #[error("invalid value for '{key}': {err}")]
+    InvalidValue { key: String, err: json::Error },
+}
+
+/// Profile configuration.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+    /// Node settings.
+    pub node: Node,
+    /// CLI settings.
+    pub cli: Cli,
+    /// HTTP daemon settings.
+    pub web: Web,
+}
+
+impl Config {
+    /// Load the configuration from the given path. If the file doesn't exist, the default
+    /// configuration is returned.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(json::from_slice(&bytes)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the configuration to the given path.
+    pub fn write(&self, path: &Path) -> Result<(), ConfigError> {
+        let mut json = json::to_string_pretty(self)?;
+        json.push('\n');
+        fs::write(path, json)?;
+
+        Ok(())
+    }
+
+    /// Get the value of the given key.
+    pub fn get(&self, key: &str) -> Result<json::Value, ConfigError> {
+        let mut value = json::to_value(self)?;
+
+        for field in key.split('.') {
+            value = match value {
+                json::Value::Object(mut map) => map
+                    .remove(field)
+                    .ok_or_else(|| ConfigError::UnknownKey(key.to_owned()))?,
+                _ => return Err(ConfigError::UnknownKey(key.to_owned())),
+            };
+        }
+        Ok(value)
+    }
+
+    /// Set the value of the given key. Setting a key to `null` resets it to its default.
+    pub fn set(&mut self, key: &str, value: json::Value) -> Result<(), ConfigError> {
+        let mut root = json::to_value(&*self)?;
+        let (path, field) = match key.rsplit_once('.') {
+            Some((path, field)) => (Some(path), field),
+            None => (None, key),
+        };
+        let mut parent = &mut root;
+
+        for name in path.into_iter().flat_map(|p| p.split('.')) {
+            parent = parent
+                .get_mut(name)
+                .ok_or_else(|| ConfigError::UnknownKey(key.to_owned()))?;
+        }
+        let Some(map) = parent.as_object_mut() else {
+            return Err(ConfigError::UnknownKey(key.to_owned()));
+        };
+        if !map.contains_key(field) {
+            return Err(ConfigError::UnknownKey(key.to_owned()));
+        }
+        if value.is_null() {
+            map.remove(field);
+        } else {
+            map.insert(field.to_owned(), value);
+        }
+        *self = json::from_value(root).map_err(|err| ConfigError::InvalidValue {
+            key: key.to_owned(),
+            err,
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Node settings.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Node {
+    /// Addresses to listen on.
+    pub listen: Vec<SocketAddr>,
+    /// Peers to connect to on start.
+    pub connect: Vec<ConnectAddress>,
+    /// Address to bind git-daemon to.
+    pub git_daemon: Option<SocketAddr>,
+    /// Default tracking policy.
+    #[serde(with = "crate::serde_ext::string")]
+    pub tracking_policy: Policy,
+    /// Default scope for tracking policies.
+    #[serde(with = "crate::serde_ext::string")]
+    pub tracking_scope: Scope,
+    /// Service limits.
+    pub limits: Limits,
+    /// Whether to accept signed refs updates that roll back the refs of a remote.
+    pub allow_rollbacks: bool,
+}
+
+/// Service limits. Unset values take the node defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Limits {
+    /// Number of routing table entries before we start pruning.
+    pub routing_max_size: Option<usize>,
+    /// How long to keep a routing table entry before being pruned, in seconds.
+    pub routing_max_age: Option<u64>,
+    /// Storage quota of each repository.
+    pub repo_quota: Quota,
+    /// Storage quota of each remote of a repository.
+    pub remote_quota: Quota,
+}
+
+/// Storage quota, enforced when fetching. Unset values are unlimited.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Quota {
+    /// Maximum size of the git objects, in bytes.
+    pub max_bytes: Option<u64>,
+    /// Maximum number of refs.
+    pub max_refs: Option<usize>,
+    /// Maximum size of a single git object, in bytes.
+    pub max_object_size: Option<u64>,
+}
+
+impl Quota {
+    /// Check whether the quota doesn't limit anything.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_bytes.is_none() && self.max_refs.is_none() && self.max_object_size.is_none()
+    }
+}
+
+/// Address of a peer, in the form `<nid>@<addr>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConnectAddress(
+    #[serde(with = "crate::serde_ext::string")] pub PeerAddr<NodeId, Address>,
+);
+
+impl From<ConnectAddress> for (NodeId, Address) {
+    fn from(ConnectAddress(peer): ConnectAddress) -> Self {
+        (peer.id, peer.addr)
+    }
+}
+
+/// CLI settings.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Cli {
+    /// Editor used to write messages, when neither `VISUAL` nor `EDITOR` are set.
+    pub editor: Option<String>,
+    /// Pager used by the commands that page their output, when `PAGER` isn't set.
+    pub pager: Option<String>,
+    /// When to color the output.
+    pub color: Color,
+}
+
+/// When to color the output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
+    /// Color the output when it is a terminal.
+    #[default]
+    Auto,
+    /// Always color the output.
+    Always,
+    /// Never color the output.
+    Never,
+}
+
+/// HTTP daemon settings.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Web {
+    /// Address to listen on.
+    pub listen: Option<SocketAddr>,
+    /// Repository aliases, by name.
+    pub aliases: BTreeMap<String, Id>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_set() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(FILE);
+        let mut config = Config::load(&path).unwrap();
+
+        assert_eq!(config, Config::default());
+        assert_eq!(config.get("cli.color").unwrap(), json::json!("auto"));
+
+        config.set("cli.color", json::json!("never")).unwrap();
+        config
+            .set("node.trackingPolicy", json::json!("track"))
+            .unwrap();
+        config
+            .set("node.limits.repoQuota.maxRefs", json::json!(128))
+            .unwrap();
+        assert_eq!(config.cli.color, Color::Never);
+        assert_eq!(config.node.tracking_policy, Policy::Track);
+        assert_eq!(config.node.limits.repo_quota.max_refs, Some(128));
+
+        config.write(&path).unwrap();
+        assert_eq!(Config::load(&path).unwrap(), config);
+
+        config
+            .set("node.trackingPolicy", json::Value::Null)
+            .unwrap();
+        assert_eq!(config.node.tracking_policy, Policy::Block);
+
+        assert!(matches!(
+            config.set("node.unknown", json::json!(1)),
+            Err(ConfigError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            config.set("cli.color", json::json!("sometimes")),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            config.get("cli.color.shade"),
+            Err(ConfigError::UnknownKey(_))
+        ));
+    }
+}
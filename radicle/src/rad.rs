@@ -6,17 +6,18 @@ use std::str::FromStr;
 use once_cell::sync::Lazy;
 use thiserror::Error;
 
+use crate::cob::identity::Proposals;
 use crate::crypto::{Signer, Verified};
 use crate::git;
-use crate::identity::doc::{DocError, Id};
-use crate::identity::project::Project;
-use crate::identity::{doc, IdentityError};
+use crate::identity::doc::{DocError, Id, Rotation};
+use crate::identity::project::{Project, ProjectError};
+use crate::identity::{doc, IdentityError, Visibility};
 use crate::storage::git::transport;
 use crate::storage::git::{Repository, Storage};
 use crate::storage::refs::SignedRefs;
 use crate::storage::WriteRepository;
-use crate::storage::{BranchName, ReadRepository as _, RemoteId};
-use crate::{identity, storage};
+use crate::storage::{BranchName, ReadRepository as _, ReadStorage as _, RemoteId};
+use crate::{cob, identity, storage};
 
 /// Name of the radicle storage remote.
 pub static REMOTE_NAME: Lazy<git::RefString> = Lazy::new(|| git::refname!("rad"));
@@ -51,6 +52,29 @@ pub fn init<G: Signer>(
     default_branch: BranchName,
     signer: &G,
     storage: &Storage,
+) -> Result<(Id, identity::Doc<Verified>, SignedRefs<Verified>), InitError> {
+    init_with_visibility(
+        repo,
+        name,
+        description,
+        default_branch,
+        Visibility::default(),
+        signer,
+        storage,
+    )
+}
+
+/// Initialize a new radicle project from a git repository, with the given visibility.
+/// Since the visibility is set in the initial identity document, private projects are
+/// never announced to the network.
+pub fn init_with_visibility<G: Signer>(
+    repo: &git2::Repository,
+    name: &str,
+    description: &str,
+    default_branch: BranchName,
+    visibility: Visibility,
+    signer: &G,
+    storage: &Storage,
 ) -> Result<(Id, identity::Doc<Verified>, SignedRefs<Verified>), InitError> {
     // TODO: Better error when project id already exists in storage, but remote doesn't.
     let pk = signer.public_key();
@@ -68,7 +92,10 @@ pub fn init<G: Signer>(
                 .join(", "),
         )
     })?;
-    let doc = identity::Doc::initial(proj, delegate).verified()?;
+    let mut doc = identity::Doc::initial(proj, delegate);
+    doc.visibility = visibility;
+
+    let doc = doc.verified()?;
     let (project, _) = Repository::init(&doc, pk, storage, signer)?;
     let url = git::Url::from(project.id).with_namespace(*pk);
 
@@ -108,6 +135,11 @@ pub enum ForkError {
     Doc(#[from] DocError),
     #[error("git: invalid reference")]
     InvalidReference,
+    #[error(
+        "project payload: {}",
+        .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    Project(Vec<ProjectError>),
 }
 
 /// Create a local tree for an existing project, from an existing remote.
@@ -187,10 +219,159 @@ pub fn fork<G: Signer, S: storage::WriteStorage>(
     Ok(())
 }
 
+/// Create a new project from an existing one, ie. a "hard fork".
+///
+/// Unlike [`fork`], the new project has its own identity, with the signer as its only
+/// delegate. The upstream project is recorded in the identity document's fork payload,
+/// and the upstream's canonical default branch is copied into the signer's namespace.
+///
+/// The new project isn't archived, even if the upstream project is, and it has the
+/// same visibility as the upstream project, so that forks of private projects stay
+/// private.
+pub fn hard_fork<G: Signer>(
+    upstream: Id,
+    name: Option<String>,
+    signer: &G,
+    storage: &Storage,
+) -> Result<(Id, identity::Doc<Verified>, SignedRefs<Verified>), ForkError> {
+    let me = signer.public_key();
+    let upstream_repo = storage.repository(upstream)?;
+    let (_, upstream_doc) = upstream_repo.identity_doc()?;
+    let upstream_doc = upstream_doc.verified()?;
+    let mut project = upstream_doc
+        .project()?
+        .update(name, None, None)
+        .map_err(ForkError::Project)?;
+    project.set_archived(None);
+    let (branch, head) = upstream_repo.canonical_head()?;
+
+    let mut doc = identity::Doc::initial(project, identity::Did::from(*me));
+    doc.visibility = upstream_doc.visibility;
+
+    let mut doc = doc.verified()?;
+    doc.payload
+        .insert(doc::PayloadId::fork(), doc::Fork { upstream, head }.into());
+
+    let (repo, _) = Repository::init(&doc, me, storage, signer)?;
+    repo.raw()
+        .remote_anonymous(&upstream_repo.path().to_string_lossy())?
+        .fetch(
+            &[format!(
+                "{}:{}",
+                branch.as_str(),
+                branch.with_namespace(me.into()).as_str()
+            )],
+            None,
+            None,
+        )?;
+
+    let signed = repo.sign_refs(signer)?;
+    let _head = repo.set_head()?;
+    let _head = repo.set_identity_head()?;
+
+    Ok((repo.id, doc, signed))
+}
+
+#[derive(Error, Debug)]
+pub enum RotateError {
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("identity document: {0}")]
+    Doc(#[from] DocError),
+    #[error("identity: {0}")]
+    Identity(#[from] IdentityError),
+    #[error("identity proposal: {0}")]
+    Proposal(#[from] cob::identity::Error),
+    #[error("identity proposal: {0}")]
+    Store(#[from] cob::store::Error),
+}
+
+/// Outcome of a key rotation in a repository. See [`rotate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotated {
+    /// The old key isn't a delegate: only its references were copied to the new key.
+    Migrated,
+    /// The identity of the repository was updated with the rotation.
+    Updated(git::Oid),
+    /// The identity requires the signatures of other delegates: a proposal accepted by
+    /// the old key was opened for them to accept and commit the rotation.
+    Proposed(cob::ObjectId),
+}
+
+/// Rotate a key in a repository, given the signers of the old and new keys.
+///
+/// The references of the old key are copied to the new key's namespace. If the old key
+/// is a delegate, the rotation is recorded in the identity document: directly if the
+/// document's threshold is one, otherwise through an identity proposal. Running it again
+/// after an interruption resumes the rotation.
+pub fn rotate<G: Signer, H: Signer>(
+    repo: &Repository,
+    rotation: Rotation,
+    signer: &G,
+    new_signer: &H,
+) -> Result<Rotated, RotateError> {
+    let (old, new) = (rotation.old, rotation.new);
+    repo.migrate(&old, &new)?;
+
+    let mut doc = repo.identity_doc_of(&new)?;
+    let rotated = if !doc.is_delegate(&old) {
+        Rotated::Migrated
+    } else if doc.threshold > 1 {
+        let current = identity::Doc::<Verified>::head(&new, repo)?;
+        doc.rotate(rotation)?;
+
+        let mut proposals = Proposals::open(repo)?;
+        let mut existing = None;
+        for result in proposals.all()? {
+            let Ok((id, proposal, _)) = result else {
+                continue;
+            };
+            if *proposal.state() == cob::identity::State::Open
+                && proposal.latest().map_or(false, |(_, r)| r.proposed == doc)
+            {
+                existing = Some(id);
+                break;
+            }
+        }
+        match existing {
+            Some(id) => Rotated::Proposed(id),
+            None => {
+                let (_, signature) = doc.sign(signer)?;
+                let mut proposal = proposals.create(
+                    "Rotate delegate key",
+                    format!("Rotate {old} to {new}."),
+                    current,
+                    doc,
+                    signer,
+                )?;
+                let revision = proposal
+                    .latest()
+                    .map(|(id, _)| *id)
+                    .expect("rotate: proposal has a revision");
+                proposal.accept(revision, signature, signer)?;
+
+                Rotated::Proposed(proposal.id)
+            }
+        }
+    } else {
+        doc.rotate(rotation)?;
+
+        let (_, sig) = doc.sign(new_signer)?;
+        let head = doc.update(&new, "Rotate delegate key", &[(&new, sig)], repo.raw())?;
+
+        Rotated::Updated(head)
+    };
+    repo.sign_refs(new_signer)?;
+
+    Ok(rotated)
+}
+
 #[derive(Error, Debug)]
 pub enum CheckoutError {
     #[error("failed to fetch to working copy")]
     Fetch(#[source] git2::Error),
+    #[error("failed to fetch to partial working copy: {0}")]
+    PartialFetch(#[source] io::Error),
     #[error("git: {0}")]
     Git(#[from] git2::Error),
     #[error("storage: {0}")]
@@ -226,7 +407,17 @@ pub fn checkout<P: AsRef<Path>, S: storage::ReadStorage>(
 
     // Configure and fetch all refs from remote.
     git::configure_remote(&repo, &REMOTE_NAME, &url)?;
-    git::fetch(&repo, &REMOTE_NAME).map_err(CheckoutError::Fetch)?;
+
+    // Partial repositories are checked out as partial clones, which libgit2 doesn't support.
+    let filter = storage.repository(proj)?.fetch_limits()?.filter;
+    if let Some(filter) = filter {
+        let filter = format!("--filter={filter}");
+
+        git::run::<_, _, &str, &str>(path.as_ref(), ["fetch", &filter, REMOTE_NAME.as_str()], [])
+            .map_err(CheckoutError::PartialFetch)?;
+    } else {
+        git::fetch(&repo, &REMOTE_NAME).map_err(CheckoutError::Fetch)?;
+    }
 
     {
         // Setup default branch.
@@ -242,7 +433,14 @@ pub fn checkout<P: AsRef<Path>, S: storage::ReadStorage>(
             .expect("checkout: default branch name is valid UTF-8");
 
         repo.set_head(branch_ref)?;
-        repo.checkout_head(None)?;
+
+        if filter.is_some() {
+            // Let git fetch the blobs missing from the partial clone.
+            git::run::<_, _, &str, &str>(path.as_ref(), ["reset", "--hard"], [])
+                .map_err(CheckoutError::PartialFetch)?;
+        } else {
+            repo.checkout_head(None)?;
+        }
 
         // Setup remote tracking for default branch.
         git::set_upstream(&repo, &REMOTE_NAME, project.default_branch(), branch_ref)?;
@@ -298,10 +496,12 @@ pub fn repo(path: impl AsRef<Path>) -> Result<(git2::Repository, Id), RemoteErro
 mod tests {
     use std::collections::HashMap;
 
+    use nonempty::NonEmpty;
     use radicle_crypto::test::signer::MockSigner;
 
+    use crate::cob::identity::Proposal;
     use crate::git::{name::component, qualified};
-    use crate::identity::Did;
+    use crate::identity::{Did, Identity};
     use crate::storage::git::transport;
     use crate::storage::git::Storage;
     use crate::storage::ReadStorage;
@@ -398,6 +598,210 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_init_private() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let bob = MockSigner::default();
+        let eve = MockSigner::default();
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (repo, _) = fixtures::repository(tempdir.path().join("working"));
+        let (id, doc, _) = init_with_visibility(
+            &repo,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            Visibility::private([Did::from(bob.public_key())]),
+            &signer,
+            &storage,
+        )
+        .unwrap();
+        let project = storage.repository(id).unwrap();
+
+        assert!(!doc.visibility.is_public());
+        assert_eq!(storage.inventory().unwrap(), vec![id]);
+        assert!(storage.public_inventory().unwrap().is_empty());
+        assert!(project.is_visible_to(signer.public_key()).unwrap());
+        assert!(project.is_visible_to(bob.public_key()).unwrap());
+        assert!(!project.is_visible_to(eve.public_key()).unwrap());
+    }
+
+    #[test]
+    fn test_hard_fork() {
+        let mut rng = fastrand::Rng::new();
+        let tempdir = tempfile::tempdir().unwrap();
+        let alice = MockSigner::new(&mut rng);
+        let bob = MockSigner::new(&mut rng);
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (original, _) = fixtures::repository(tempdir.path().join("original"));
+        let (upstream, _, alice_refs) = init(
+            &original,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            &alice,
+            &storage,
+        )
+        .unwrap();
+        let master = alice_refs.get(&qualified!("refs/heads/master")).unwrap();
+
+        // Bob creates his own project from Alice's.
+        let (id, doc, bob_refs) =
+            hard_fork(upstream, Some(String::from("acme-ng")), &bob, &storage).unwrap();
+
+        assert_ne!(id, upstream);
+        assert_eq!(doc.delegates, NonEmpty::new(Did::from(bob.public_key())));
+        assert_eq!(doc.project().unwrap().name(), "acme-ng");
+        assert_eq!(
+            doc.fork().unwrap(),
+            doc::Fork {
+                upstream,
+                head: *master
+            }
+        );
+        assert_eq!(
+            bob_refs.get(&qualified!("refs/heads/master")).unwrap(),
+            master
+        );
+
+        let repo = storage.repository(id).unwrap();
+        assert_eq!(repo.identity_doc_of(bob.public_key()).unwrap(), doc);
+        assert_eq!(repo.head().unwrap().1, *master);
+        assert!(storage
+            .repository(upstream)
+            .unwrap()
+            .identity_doc_of(alice.public_key())
+            .unwrap()
+            .fork()
+            .is_err());
+    }
+
+    #[test]
+    fn test_hard_fork_private_archived() {
+        let mut rng = fastrand::Rng::new();
+        let tempdir = tempfile::tempdir().unwrap();
+        let alice = MockSigner::new(&mut rng);
+        let bob = MockSigner::new(&mut rng);
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (original, _) = fixtures::repository(tempdir.path().join("original"));
+        let (upstream, mut doc, _) = init_with_visibility(
+            &original,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            Visibility::private([Did::from(bob.public_key())]),
+            &alice,
+            &storage,
+        )
+        .unwrap();
+        let upstream_repo = storage.repository(upstream).unwrap();
+
+        // Alice archives her project.
+        let mut project = doc.project().unwrap();
+        project.set_archived(Some(1));
+        doc.payload
+            .insert(doc::PayloadId::project(), project.into());
+        doc.sign(&alice)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    alice.public_key(),
+                    "Archive project",
+                    &[(alice.public_key(), sig)],
+                    upstream_repo.raw(),
+                )
+            })
+            .unwrap();
+        upstream_repo.set_identity_head().unwrap();
+
+        let (_, fork, _) = hard_fork(upstream, None, &bob, &storage).unwrap();
+        assert_eq!(fork.visibility, doc.visibility);
+        assert_eq!(fork.archived(), None);
+
+        // All validation errors are returned.
+        assert!(matches!(
+            hard_fork(upstream, Some(String::new()), &bob, &storage),
+            Err(ForkError::Project(errs)) if errs.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_rotate_proposal() {
+        let mut rng = fastrand::Rng::new();
+        let tempdir = tempfile::tempdir().unwrap();
+        let alice = MockSigner::new(&mut rng);
+        let alice_new = MockSigner::new(&mut rng);
+        let bob = MockSigner::new(&mut rng);
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (working, _) = fixtures::repository(tempdir.path().join("working"));
+        let (id, mut doc, _) = init(
+            &working,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            &alice,
+            &storage,
+        )
+        .unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        // Bob becomes a delegate, and identity updates now need both signatures.
+        doc.delegates.push(Did::from(bob.public_key()));
+        doc.threshold = 2;
+        doc.sign(&alice)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    alice.public_key(),
+                    "Add Bob",
+                    &[(alice.public_key(), sig)],
+                    repo.raw(),
+                )
+            })
+            .unwrap();
+
+        let rotation = Rotation::new(&alice, *alice_new.public_key());
+        let Rotated::Proposed(id) = rotate(&repo, rotation, &alice, &alice_new).unwrap() else {
+            panic!("the rotation should be proposed");
+        };
+        // The identity is unchanged until Bob accepts, and resuming doesn't propose again.
+        assert!(repo
+            .identity_doc_of(alice_new.public_key())
+            .unwrap()
+            .is_delegate(alice.public_key()));
+        assert_eq!(
+            rotate(&repo, rotation, &alice, &alice_new).unwrap(),
+            Rotated::Proposed(id)
+        );
+
+        let previous = Identity::load(alice_new.public_key(), &repo).unwrap();
+        let mut proposals = Proposals::open(&repo).unwrap();
+        let mut proposal = proposals.get_mut(&id).unwrap();
+        let (rid, revision) = proposal
+            .latest()
+            .map(|(rid, revision)| (*rid, revision.clone()))
+            .unwrap();
+        assert!(!revision.is_quorum_reached(&previous));
+
+        let (_, sig) = revision.proposed.sign(&bob).unwrap();
+        proposal.accept(rid, sig, &bob).unwrap();
+
+        let identity =
+            Proposal::commit(&proposal, &rid, alice_new.public_key(), &repo, &alice_new).unwrap();
+        assert!(identity.doc.is_delegate(alice_new.public_key()));
+        assert!(!identity.doc.is_delegate(alice.public_key()));
+    }
+
     #[test]
     fn test_checkout() {
         let tempdir = tempfile::tempdir().unwrap();
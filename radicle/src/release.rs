@@ -0,0 +1,364 @@
+//! Releases.
+//!
+//! A release is a tag signed by the repository delegates. Each delegate publishes an
+//! annotated tag under their namespace, eg. `refs/namespaces/<key>/refs/tags/v1.0`, whose
+//! message ends with their signature over the repository, tag name and target, in OpenSSH
+//! format. Since it lives in the delegate's namespace, the tag is covered by the signed refs
+//! and replicated along with the rest of the remote.
+//!
+//! A release is canonical once enough delegates to reach the identity document threshold
+//! have signed the same target. The top-level `refs/tags/<name>` is then set to that target,
+//! see [`set_canonical`].
+use thiserror::Error;
+
+use crate::crypto::ssh::{ExtendedSignature, ExtendedSignatureError};
+use crate::crypto::{PublicKey, Signer};
+use crate::git;
+use crate::git::{Oid, RefStr, RefString};
+use crate::identity::{Id, IdentityError};
+use crate::storage;
+use crate::storage::{ReadRepository, RemoteId, WriteRepository};
+
+/// Marks the beginning of the signature in a release tag message.
+pub const SIGNATURE_BEGIN: &str = "-----BEGIN SSH SIGNATURE-----";
+
+#[derive(Error, Debug)]
+pub enum ReleaseError {
+    #[error("release `{0}` already exists with a different target")]
+    Exists(RefString),
+    #[error("release tag of {0} is not signed")]
+    MissingSignature(PublicKey),
+    #[error("invalid release signature for {0}")]
+    InvalidSignature(PublicKey),
+    #[error("signature: {0}")]
+    Signature(#[from] ExtendedSignatureError),
+    #[error("identity: {0}")]
+    Identity(#[from] IdentityError),
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("git: {0}")]
+    GitExt(#[from] git::Error),
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+}
+
+/// A release tag, signed by a single delegate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signed {
+    /// The tag object.
+    pub tag: Oid,
+    /// The released object.
+    pub target: Oid,
+    /// Release notes.
+    pub notes: String,
+    /// The delegate's signature.
+    pub signature: ExtendedSignature,
+}
+
+/// A release, ie. a tag signed by one or more delegates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// Tag name, eg. `v1.0`.
+    pub name: RefString,
+    /// The released object. If delegates signed different targets, this is the one
+    /// signed by the most delegates.
+    pub target: Oid,
+    /// Release notes of the first delegate who signed the target.
+    pub notes: String,
+    /// Valid delegate signatures over the target.
+    pub signatures: Vec<ExtendedSignature>,
+    /// Number of signatures needed for the release to be canonical.
+    pub threshold: usize,
+    /// Whether enough delegates signed the target to reach the threshold.
+    pub canonical: bool,
+}
+
+/// The bytes signed by delegates when releasing `target` under the given name.
+pub fn payload(rid: &Id, name: &RefStr, target: &Oid) -> Vec<u8> {
+    format!("rad:release:{rid}:{name}:{target}").into_bytes()
+}
+
+/// Sign a release of `target` and publish it under the signer's namespace.
+///
+/// Returns the tag object. The caller is responsible for signing the refs afterwards.
+pub fn create<R: WriteRepository, G: Signer>(
+    repo: &R,
+    name: &RefStr,
+    target: Oid,
+    notes: &str,
+    signer: &G,
+) -> Result<Oid, ReleaseError> {
+    let remote = signer.public_key();
+    let raw = repo.raw();
+
+    if let Some(existing) = signed(repo, remote, name)? {
+        if existing.target != target {
+            return Err(ReleaseError::Exists(name.to_owned()));
+        }
+    }
+
+    let signature =
+        ExtendedSignature::new(*remote, signer.sign(&payload(&repo.id(), name, &target)));
+    let notes = notes.trim();
+    let msg = if notes.is_empty() {
+        signature.to_pem()?
+    } else {
+        format!("{notes}\n\n{}", signature.to_pem()?)
+    };
+    let tagger = raw
+        .signature()
+        .or_else(|_| git2::Signature::now("radicle", remote.to_string().as_str()))?;
+    let object = raw.find_object(*target, None)?;
+    let tag = raw.tag_annotation_create(name.as_str(), &object, &tagger, &msg)?;
+    let refname = tag_ref(name).with_namespace(remote.into());
+
+    raw.reference(&refname, tag, true, "release (radicle)")?;
+
+    Ok(tag.into())
+}
+
+/// Load and verify the release tag signed by the given remote, if any.
+///
+/// Returns `None` if the remote has no tag under that name, or if the tag is a
+/// lightweight tag, which can't be signed.
+pub fn signed<R: ReadRepository>(
+    repo: &R,
+    remote: &RemoteId,
+    name: &RefStr,
+) -> Result<Option<Signed>, ReleaseError> {
+    let r = match repo.reference(remote, &tag_ref(name)) {
+        Ok(r) => r,
+        Err(git::Error::NotFound(_)) => return Ok(None),
+        Err(git::Error::Git(e)) if git::is_not_found_err(&e) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let Ok(tag) = r.peel_to_tag() else {
+        return Ok(None);
+    };
+    let msg = tag.message().unwrap_or_default();
+    // The signature is appended to the notes, which may themselves quote a signature.
+    let Some(i) = msg.rfind(SIGNATURE_BEGIN) else {
+        return Err(ReleaseError::MissingSignature(*remote));
+    };
+    let signature = ExtendedSignature::from_pem(&msg[i..])?;
+    let target = Oid::from(tag.target_id());
+
+    if signature.key != *remote || !signature.verify(&payload(&repo.id(), name, &target)) {
+        return Err(ReleaseError::InvalidSignature(*remote));
+    }
+
+    Ok(Some(Signed {
+        tag: tag.id().into(),
+        target,
+        notes: msg[..i].trim().to_owned(),
+        signature,
+    }))
+}
+
+/// Get a release, given its tag name. Returns `None` if no delegate signed it.
+pub fn get<R: ReadRepository>(repo: &R, name: &RefStr) -> Result<Option<Release>, ReleaseError> {
+    let (_, doc) = repo.identity_doc()?;
+    let doc = doc.verified().map_err(IdentityError::from)?;
+    let mut targets: Vec<(Oid, String, Vec<ExtendedSignature>)> = Vec::new();
+
+    for delegate in doc.delegates.iter() {
+        let release = match signed(repo, delegate.as_key(), name) {
+            Ok(Some(release)) => release,
+            Ok(None) => continue,
+            Err(e) => {
+                log::warn!(target: "release", "Ignoring release `{name}` of {delegate}: {e}");
+                continue;
+            }
+        };
+        match targets.iter_mut().find(|(t, _, _)| *t == release.target) {
+            Some((_, _, sigs)) => sigs.push(release.signature),
+            None => targets.push((release.target, release.notes, vec![release.signature])),
+        }
+    }
+
+    // Pick the target signed by the most delegates, or the first one in case of a tie.
+    let mut best: Option<(Oid, String, Vec<ExtendedSignature>)> = None;
+    for candidate in targets {
+        if best
+            .as_ref()
+            .map_or(true, |(_, _, sigs)| candidate.2.len() > sigs.len())
+        {
+            best = Some(candidate);
+        }
+    }
+
+    Ok(best.map(|(target, notes, signatures)| Release {
+        name: name.to_owned(),
+        target,
+        notes,
+        threshold: doc.threshold,
+        canonical: signatures.len() >= doc.threshold,
+        signatures,
+    }))
+}
+
+/// List all releases signed by at least one delegate.
+pub fn list<R: ReadRepository>(repo: &R) -> Result<Vec<Release>, ReleaseError> {
+    let mut names = Vec::<RefString>::new();
+
+    for delegate in repo.delegates()?.iter() {
+        for (refname, _) in repo.references_of(delegate.as_key())?.iter() {
+            if let Some(name) = refname.as_str().strip_prefix("refs/tags/") {
+                let name = RefString::try_from(name).expect("tag names are valid refnames");
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+    names.sort();
+
+    let mut releases = Vec::new();
+    for name in names {
+        if let Some(release) = get(repo, &name)? {
+            releases.push(release);
+        }
+    }
+    Ok(releases)
+}
+
+/// Point the top-level tag of every canonical release to its target.
+///
+/// Returns the canonical releases.
+pub fn set_canonical<R: WriteRepository>(repo: &R) -> Result<Vec<Release>, ReleaseError> {
+    let releases = list(repo)?
+        .into_iter()
+        .filter(|r| r.canonical)
+        .collect::<Vec<_>>();
+
+    for release in &releases {
+        let refname = tag_ref(&release.name);
+
+        log::debug!(target: "release", "Setting ref: {} -> {}", refname, release.target);
+        repo.raw().reference(
+            refname.as_str(),
+            *release.target,
+            true,
+            "set-canonical-release (radicle)",
+        )?;
+    }
+    Ok(releases)
+}
+
+/// The qualified reference of a tag.
+fn tag_ref(name: &RefStr) -> git::Qualified<'static> {
+    git::Qualified::from(git::lit::refs_tags(&name.to_ref_string())).to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use radicle_crypto::test::signer::MockSigner;
+
+    use crate::storage::git::Storage;
+    use crate::storage::ReadStorage as _;
+    use crate::test::fixtures;
+
+    use super::*;
+
+    #[test]
+    fn test_create_verify() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let eve = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, head) =
+            fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let name = git::refname!("v1.0");
+        let head = Oid::from(head);
+
+        assert!(get(&repo, &name).unwrap().is_none());
+
+        let tag = create(&repo, &name, head, "First release.", &alice).unwrap();
+        let at = signed(&repo, alice.public_key(), &name).unwrap().unwrap();
+        assert_eq!(at.tag, tag);
+        assert_eq!(at.target, head);
+        assert_eq!(at.notes, "First release.");
+
+        let release = get(&repo, &name).unwrap().unwrap();
+        assert_eq!(release.target, head);
+        assert_eq!(release.signatures.len(), 1);
+        assert!(release.canonical);
+        assert_eq!(list(&repo).unwrap(), vec![release]);
+
+        // Signatures by non-delegates don't count.
+        create(&repo, &git::refname!("v2.0"), head, "", &eve).unwrap();
+        assert!(get(&repo, &git::refname!("v2.0")).unwrap().is_none());
+
+        // The same release can't point to another target.
+        let other = Oid::from(repo.raw().find_commit(*head).unwrap().parent_id(0).unwrap());
+        assert!(matches!(
+            create(&repo, &name, other, "", &alice),
+            Err(ReleaseError::Exists(_))
+        ));
+    }
+
+    #[test]
+    fn test_canonical() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, head) =
+            fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let name = git::refname!("v1.0");
+        let head = Oid::from(head);
+
+        // Add Bob as a delegate, and require both signatures.
+        let (_, doc) = repo.identity_doc().unwrap();
+        let mut doc = doc.verified().unwrap();
+        doc.delegates.push(bob.public_key().into());
+        doc.threshold = 2;
+        doc.sign(&alice)
+            .and_then(|(_, sig)| {
+                doc.update(
+                    alice.public_key(),
+                    "Add Bob",
+                    &[(alice.public_key(), sig)],
+                    repo.raw(),
+                )
+            })
+            .unwrap();
+        repo.set_identity_head().unwrap();
+
+        // Notes quoting a signature don't get in the way.
+        let notes = format!("Quoting:\n{SIGNATURE_BEGIN}");
+        create(&repo, &name, head, &notes, &alice).unwrap();
+        assert_eq!(
+            signed(&repo, alice.public_key(), &name)
+                .unwrap()
+                .unwrap()
+                .notes,
+            notes
+        );
+
+        let release = get(&repo, &name).unwrap().unwrap();
+        assert_eq!(release.signatures.len(), 1);
+        assert_eq!(release.threshold, 2);
+        assert!(!release.canonical);
+        assert!(set_canonical(&repo).unwrap().is_empty());
+        assert!(repo.raw().find_reference("refs/tags/v1.0").is_err());
+
+        // Each delegate has its own tag object for the same target.
+        create(&repo, &name, head, "", &bob).unwrap();
+        assert_ne!(
+            signed(&repo, alice.public_key(), &name)
+                .unwrap()
+                .unwrap()
+                .tag,
+            signed(&repo, bob.public_key(), &name).unwrap().unwrap().tag
+        );
+
+        let release = get(&repo, &name).unwrap().unwrap();
+        assert_eq!(release.signatures.len(), 2);
+        assert!(release.canonical);
+        assert_eq!(set_canonical(&repo).unwrap(), vec![release]);
+        assert_eq!(repo.raw().refname_to_id("refs/tags/v1.0").unwrap(), *head);
+    }
+}
@@ -1,9 +1,12 @@
 pub mod git;
+pub mod memory;
 pub mod refs;
 
 use std::collections::hash_map;
+use std::num::NonZeroU32;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::{fmt, io};
 
 use nonempty::NonEmpty;
@@ -67,6 +70,31 @@ impl Namespaces {
                 .collect(),
         }
     }
+
+    /// Fetchspecs of the identity, signed refs and collaborative objects of each namespace.
+    /// These are fetched in full, even when the repository has [`FetchLimits`].
+    ///
+    /// Since fetchspecs can't match more than one pattern, only the identity and signed refs
+    /// are fetched in the case of [`Namespaces::All`].
+    pub fn as_metadata_fetchspecs(&self) -> Vec<String> {
+        match self {
+            Self::All => [
+                refs::IDENTITY_BRANCH.as_str(),
+                refs::SIGREFS_BRANCH.as_str(),
+            ]
+            .iter()
+            .map(|r| format!("refs/namespaces/*/{r}:refs/namespaces/*/{r}"))
+            .collect(),
+            Self::One(pk) => Self::metadata_fetchspecs(pk).collect(),
+            Self::Many(pks) => pks.iter().flat_map(Self::metadata_fetchspecs).collect(),
+        }
+    }
+
+    fn metadata_fetchspecs(pk: &PublicKey) -> impl Iterator<Item = String> + '_ {
+        ["refs/rad", "refs/cobs"]
+            .into_iter()
+            .map(move |r| format!("refs/namespaces/{pk}/{r}/*:refs/namespaces/{pk}/{r}/*"))
+    }
 }
 
 impl From<PublicKey> for Namespaces {
@@ -81,6 +109,77 @@ impl From<NonEmpty<PublicKey>> for Namespaces {
     }
 }
 
+/// Object filter of a partial fetch, as understood by `git fetch --filter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// Omit all blobs.
+    BlobNone,
+    /// Omit blobs larger than the given size, in bytes.
+    BlobLimit(u64),
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BlobNone => write!(f, "blob:none"),
+            Self::BlobLimit(size) => write!(f, "blob:limit={size}"),
+        }
+    }
+}
+
+/// Error parsing a [`Filter`].
+#[derive(Error, Debug)]
+#[error("invalid filter '{0}', expected 'blob:none' or 'blob:limit=<bytes>'")]
+pub struct FilterError(String);
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "blob:none" {
+            return Ok(Self::BlobNone);
+        }
+        s.strip_prefix("blob:limit=")
+            .and_then(|size| size.parse().ok())
+            .map(Self::BlobLimit)
+            .ok_or_else(|| FilterError(s.to_owned()))
+    }
+}
+
+/// Limits on the history and objects fetched for a repository, for shallow and partial
+/// clones of large repositories.
+///
+/// Limits only apply to branches, tags and other code refs: the identity, signed refs and
+/// collaborative objects are always fetched in full, since they can't be verified or
+/// loaded otherwise. See [`Namespaces::as_metadata_fetchspecs`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FetchLimits {
+    /// Number of commits of history to fetch for each ref.
+    pub depth: Option<NonZeroU32>,
+    /// Objects to omit.
+    pub filter: Option<Filter>,
+}
+
+impl FetchLimits {
+    /// Whether there are no limits, ie. everything is fetched.
+    pub fn is_empty(&self) -> bool {
+        self.depth.is_none() && self.filter.is_none()
+    }
+
+    /// Arguments to pass to `git fetch` to apply these limits.
+    pub fn as_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(depth) = self.depth {
+            args.push(format!("--depth={depth}"));
+        }
+        if let Some(filter) = self.filter {
+            args.push(format!("--filter={filter}"));
+        }
+        args
+    }
+}
+
 /// Storage error.
 #[derive(Error, Debug)]
 pub enum Error {
@@ -98,6 +197,8 @@ pub enum Error {
     Id(std::ffi::OsString),
     #[error("i/o: {0}")]
     Io(#[from] io::Error),
+    #[error(transparent)]
+    Filter(#[from] FilterError),
 }
 
 impl Error {
@@ -286,6 +387,24 @@ impl Remote<Verified> {
     }
 }
 
+/// A reference matching one of the canonical reference patterns of a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalRef {
+    /// Name of the reference, eg. `refs/heads/release/1.0`.
+    pub name: RefString,
+    /// Target agreed upon by a quorum of delegates, if any.
+    pub target: Option<Oid>,
+    /// Target of the reference in the namespace of each delegate who has it.
+    pub heads: Vec<(PublicKey, Oid)>,
+}
+
+impl CanonicalRef {
+    /// Whether the reference is a tag.
+    pub fn is_tag(&self) -> bool {
+        self.name.as_str().starts_with("refs/tags/")
+    }
+}
+
 /// Read-only operations on a storage instance.
 pub trait ReadStorage {
     type Repository: ReadRepository;
@@ -306,6 +425,24 @@ pub trait ReadStorage {
     fn inventory(&self) -> Result<Inventory, Error>;
     /// Open or create a read-only repository.
     fn repository(&self, rid: Id) -> Result<Self::Repository, Error>;
+
+    /// Get the inventory of public repositories, ie. the repositories that can be announced
+    /// to the network. Repositories whose identity document can't be loaded are left out,
+    /// as are partial repositories, which aren't served.
+    fn public_inventory(&self) -> Result<Inventory, Error> {
+        let mut inventory = self.inventory()?;
+        inventory.retain(|rid| {
+            let Ok(repo) = self.repository(*rid) else {
+                return false;
+            };
+            if !repo.fetch_limits().map_or(false, |l| l.is_empty()) {
+                return false;
+            }
+            repo.identity_doc()
+                .map_or(false, |(_, doc)| doc.visibility.is_public())
+        });
+        Ok(inventory)
+    }
 }
 
 /// Allows access to individual storage repositories.
@@ -353,6 +490,14 @@ pub trait ReadRepository {
     /// Returns the [`Oid`] as well as the qualified reference name.
     fn canonical_head(&self) -> Result<(Qualified, Oid), IdentityError>;
 
+    /// Compute the canonical references of this repository, ie. the delegate references
+    /// matching the canonical reference patterns of the identity document.
+    ///
+    /// A tag is canonical if enough delegates to reach the document threshold point it to
+    /// the same object. A branch is canonical at the latest commit that enough delegates
+    /// have in their history. If delegates diverge, the reference has no target.
+    fn canonical_refs(&self) -> Result<Vec<CanonicalRef>, IdentityError>;
+
     /// Get the head of the `rad/id` reference in this repository.
     ///
     /// Returns the reference pointed to by `rad/id` if it is set. Otherwise, computes the canonical
@@ -407,6 +552,25 @@ pub trait ReadRepository {
 
     /// Get the repository's identity document.
     fn identity_doc(&self) -> Result<(Oid, identity::Doc<Unverified>), IdentityError>;
+
+    /// Get the limits that apply when fetching this repository.
+    fn fetch_limits(&self) -> Result<FetchLimits, Error>;
+
+    /// Check whether the repository is visible to the given node, according to its
+    /// identity document.
+    fn is_visible_to(&self, node: &PublicKey) -> Result<bool, IdentityError> {
+        let (_, doc) = self.identity_doc()?;
+        let doc = doc.verified()?;
+
+        Ok(doc.is_visible_to(node))
+    }
+
+    /// Check whether the repository is public, ie. visible to everyone.
+    fn is_public(&self) -> Result<bool, IdentityError> {
+        let (_, doc) = self.identity_doc()?;
+
+        Ok(doc.visibility.is_public())
+    }
 }
 
 /// Allows read-write access to a repository.
@@ -414,6 +578,9 @@ pub trait WriteRepository: ReadRepository {
     /// Set the repository head to the canonical branch.
     /// This computes the head based on the delegate set.
     fn set_head(&self) -> Result<Oid, IdentityError>;
+    /// Set the canonical references of the repository, as computed by
+    /// [`ReadRepository::canonical_refs`]. References without a target are left as-is.
+    fn set_canonical_refs(&self) -> Result<Vec<CanonicalRef>, IdentityError>;
     /// Set the repository 'rad/id' to the canonical commit, agreed by quorum.
     fn set_identity_head(&self) -> Result<Oid, IdentityError>;
     /// Sign the repository's refs under the `refs/rad/sigrefs` branch.
@@ -1,14 +1,20 @@
 pub mod cob;
+pub mod divergence;
+pub mod lfs;
+pub mod maintenance;
 pub mod transport;
+pub mod verify;
 
 use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
-use std::{fs, io};
+use std::{fs, io, thread, time};
 
 use crypto::{Signer, Unverified, Verified};
 use git_ref_format::refspec;
 use once_cell::sync::Lazy;
 
+use crate::cob;
 use crate::git;
 use crate::identity;
 use crate::identity::{Doc, Id};
@@ -16,7 +22,8 @@ use crate::identity::{Identity, IdentityError, Project};
 use crate::storage::refs;
 use crate::storage::refs::{Refs, SignedRefs};
 use crate::storage::{
-    Inventory, ReadRepository, ReadStorage, Remote, Remotes, WriteRepository, WriteStorage,
+    CanonicalRef, FetchLimits, Inventory, ReadRepository, ReadStorage, Remote, Remotes,
+    WriteRepository, WriteStorage,
 };
 
 pub use crate::git::*;
@@ -36,6 +43,20 @@ pub static CANONICAL_IDENTITY: Lazy<git::Qualified> = Lazy::new(|| {
     )
 });
 
+/// Git configuration key of the fetch depth of a repository. See [`FetchLimits`].
+const CONFIG_DEPTH: &str = "radicle.depth";
+/// Git configuration key of the fetch filter of a repository. See [`FetchLimits`].
+const CONFIG_FILTER: &str = "radicle.filter";
+/// Git configuration key allowing working copies to clone a partial repository.
+const CONFIG_ALLOW_FILTER: &str = "uploadpack.allowFilter";
+/// Git configuration key allowing working copies to fetch the objects they miss.
+const CONFIG_ALLOW_ANY_WANT: &str = "uploadpack.allowAnySHA1InWant";
+/// Name of the file locking a repository. See [`Repository::lock`].
+const LOCK_FILE: &str = "rad.lock";
+/// Age after which a lock file is considered to be left over by a process that didn't
+/// release it, eg. because it crashed.
+const LOCK_STALE: time::Duration = time::Duration::from_secs(60 * 60);
+
 /// A parsed Git reference.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Ref {
@@ -190,6 +211,20 @@ pub struct Repository {
     pub backend: git2::Repository,
 }
 
+/// Exclusive lock on a repository, released when dropped. See [`Repository::lock`].
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            log::warn!(target: "storage", "Failed to release lock {}: {e}", self.path.display());
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum VerifyError {
     #[error("invalid remote `{0}`")]
@@ -254,6 +289,47 @@ impl Repository {
         Ok((repo, oid))
     }
 
+    /// Lock the repository against operations that can't run concurrently, such as fetching
+    /// and pruning objects. Waits for the lock to be released for up to `timeout`, after
+    /// which an error of kind [`io::ErrorKind::WouldBlock`] is returned.
+    ///
+    /// The lock is a file in the repository, so it is held across processes.
+    pub fn lock(&self, timeout: time::Duration) -> io::Result<Lock> {
+        let path = self.path().join(LOCK_FILE);
+        let start = time::Instant::now();
+
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(Lock { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|t| t.elapsed().ok())
+                        .map_or(false, |age| age >= LOCK_STALE);
+
+                    if stale {
+                        log::warn!(target: "storage", "Removing stale lock of {}", self.id);
+                        fs::remove_file(&path).ok();
+                        continue;
+                    }
+                    if start.elapsed() >= timeout {
+                        return Err(io::Error::new(
+                            io::ErrorKind::WouldBlock,
+                            format!("repository {} is locked", self.id),
+                        ));
+                    }
+                    thread::sleep(time::Duration::from_millis(100));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
     pub fn inspect(&self) -> Result<(), Error> {
         for r in self.backend.references()? {
             let r = r?;
@@ -286,6 +362,155 @@ impl Repository {
         Ok(refs)
     }
 
+    /// Copy the references of one remote into another remote's namespace, eg. after the
+    /// remote's key was rotated. References that already exist under the new namespace are
+    /// left untouched, as are the signed refs branch and its parent, which must be signed by
+    /// the new key.
+    ///
+    /// Returns the number of references copied.
+    pub fn migrate(&self, old: &RemoteId, new: &RemoteId) -> Result<usize, Error> {
+        let mut copied = 0;
+
+        for r in self
+            .backend
+            .references_glob(format!("refs/namespaces/{old}/*").as_str())?
+        {
+            let r = r?;
+            let name = r.name().ok_or(Error::InvalidRef)?;
+            let (_, refname) = git::parse_ref::<RemoteId>(name)?;
+            let Some(oid) = r.target() else {
+                // Ignore symbolic refs, eg. `HEAD`.
+                continue;
+            };
+            if refname == *refs::SIGREFS_BRANCH || refname == *refs::SIGREFS_PARENT {
+                continue;
+            }
+            let target = refname.with_namespace(new.into());
+
+            if self.backend.find_reference(target.as_str()).is_ok() {
+                continue;
+            }
+            log::debug!(target: "storage", "Migrating ref: {} -> {}", name, target);
+
+            self.backend.reference(
+                target.as_str(),
+                oid,
+                false,
+                &format!("migrating reference from {old}"),
+            )?;
+            copied += 1;
+        }
+        Ok(copied)
+    }
+
+    /// Set the fetch limits of this repository, stored in its git configuration. They apply
+    /// to all subsequent fetches.
+    pub fn set_fetch_limits(&self, limits: &FetchLimits) -> Result<(), Error> {
+        let mut config = self.backend.config()?;
+
+        for key in [
+            CONFIG_DEPTH,
+            CONFIG_FILTER,
+            CONFIG_ALLOW_FILTER,
+            CONFIG_ALLOW_ANY_WANT,
+        ] {
+            match config.remove(key) {
+                Ok(()) => {}
+                Err(e) if git::is_not_found_err(&e) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if let Some(depth) = limits.depth {
+            config.set_i64(CONFIG_DEPTH, depth.get() as i64)?;
+        }
+        if let Some(filter) = limits.filter {
+            config.set_str(CONFIG_FILTER, &filter.to_string())?;
+            // Working copies can't be cloned in full from a partial repository. Instead, they
+            // are cloned with the same filter, and fetch the objects they miss on demand.
+            config.set_bool(CONFIG_ALLOW_FILTER, true)?;
+            config.set_bool(CONFIG_ALLOW_ANY_WANT, true)?;
+        }
+        Ok(())
+    }
+
+    /// Get the signed refs head of every remote.
+    pub fn sigrefs(&self) -> Result<HashMap<RemoteId, Oid>, Error> {
+        let mut heads = HashMap::new();
+
+        for r in self.backend.references_glob(SIGREFS_GLOB.as_str())? {
+            let r = r?;
+            let name = r.name().ok_or(Error::InvalidRef)?;
+            let (remote, _) = git::parse_ref_namespaced::<RemoteId>(name)?;
+            let oid = r.target().ok_or(Error::InvalidRef)?;
+
+            heads.insert(remote, oid.into());
+        }
+        Ok(heads)
+    }
+
+    /// Check updates of the signed refs of remotes against their current signed refs, and
+    /// return the ones that would roll back the refs of a remote. Updates of remotes we don't
+    /// have yet are always accepted.
+    pub fn check_sigrefs(
+        &self,
+        updates: &HashMap<RemoteId, Oid>,
+    ) -> Result<Vec<(RemoteId, refs::Error)>, Error> {
+        let known = self.sigrefs()?;
+        let mut rollbacks = Vec::new();
+
+        for (remote, new) in updates {
+            let Some(known) = known.get(remote) else {
+                continue;
+            };
+            match refs::check_update(remote, *known, *new, self) {
+                Ok(()) => {}
+                Err(e @ refs::Error::Rollback { .. }) => rollbacks.push((*remote, e)),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(rollbacks)
+    }
+
+    /// Get the collaborative object changes reachable from the given tips, but not from the
+    /// refs of any remote, whose clock is too far ahead of `now`. See
+    /// [`radicle_cob::history::HybridClock::is_ahead`].
+    pub fn changes_ahead(
+        &self,
+        tips: impl IntoIterator<Item = Oid>,
+        now: cob::Timestamp,
+    ) -> Result<Vec<Oid>, Error> {
+        use radicle_cob::change::Storage as _;
+        use radicle_cob::history::HybridClock;
+
+        let mut walk = self.backend.revwalk()?;
+        let mut ahead = Vec::new();
+
+        for tip in tips {
+            walk.push(*tip)?;
+        }
+        // Changes we already have were checked when we got them.
+        walk.hide_glob("refs/namespaces")?;
+
+        for oid in walk {
+            let commit = self.backend.find_commit(oid?)?;
+            // Changes refer to the identity they were made under, which isn't a change.
+            if commit.tree()?.get_name("manifest").is_none() {
+                continue;
+            }
+            let Ok(change) = self.backend.load(commit.id().into()) else {
+                continue;
+            };
+            let clock = change
+                .clock
+                .unwrap_or_else(|| HybridClock::new(change.timestamp, 0));
+
+            if clock.is_ahead(now) {
+                ahead.push(commit.id().into());
+            }
+        }
+        Ok(ahead)
+    }
+
     pub fn identity_of(&self, remote: &RemoteId) -> Result<Identity<Oid>, IdentityError> {
         Identity::load(remote, self)
     }
@@ -329,43 +554,7 @@ impl Repository {
 
             heads.push(oid.into());
         }
-        // Keep track of the longest identity branch.
-        let mut longest = heads.pop().ok_or(IdentityError::MissingBranch)?;
-
-        for head in &heads {
-            let base = self.raw().merge_base(*head, longest)?;
-
-            if base == longest {
-                // `head` is a successor of `longest`. Update `longest`.
-                //
-                //   o head
-                //   |
-                //   o longest (base)
-                //   |
-                //
-                longest = *head;
-            } else if base == *head || *head == longest {
-                // `head` is an ancestor of `longest`, or equal to it. Do nothing.
-                //
-                //   o longest             o longest, head (base)
-                //   |                     |
-                //   o head (base)   OR    o
-                //   |                     |
-                //
-            } else {
-                // The merge base between `head` and `longest` (`base`)
-                // is neither `head` nor `longest`. Therefore, the branches have
-                // diverged.
-                //
-                //    longest   head
-                //           \ /
-                //            o (base)
-                //            |
-                //
-                return Err(IdentityError::BranchesDiverge);
-            }
-        }
-        Ok(longest.into())
+        canonical_identity_head(&self.backend, heads)
     }
 
     pub fn remote_ids(
@@ -557,6 +746,21 @@ impl ReadRepository for Repository {
         Repository::identity_doc(self)
     }
 
+    fn fetch_limits(&self) -> Result<FetchLimits, Error> {
+        let config = self.backend.config()?;
+        let depth = match config.get_i64(CONFIG_DEPTH) {
+            Ok(depth) => u32::try_from(depth).ok().and_then(NonZeroU32::new),
+            Err(e) if git::is_not_found_err(&e) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let filter = match config.get_string(CONFIG_FILTER) {
+            Ok(filter) => Some(filter.parse()?),
+            Err(e) if git::is_not_found_err(&e) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(FetchLimits { depth, filter })
+    }
+
     fn head(&self) -> Result<(Qualified, Oid), IdentityError> {
         // If `HEAD` is already set locally, just return that.
         if let Ok(head) = self.backend.head() {
@@ -568,28 +772,51 @@ impl ReadRepository for Repository {
     }
 
     fn canonical_head(&self) -> Result<(Qualified, Oid), IdentityError> {
-        // TODO: In the `fork` function for example, we call Repository::project_identity again,
-        // This should only be necessary once.
+        canonical_head(self, &self.backend)
+    }
+
+    fn canonical_refs(&self) -> Result<Vec<CanonicalRef>, IdentityError> {
         let (_, doc) = self.identity_doc()?;
         let doc = doc.verified()?;
-        let project = doc.project()?;
-        let branch_ref = Qualified::from(lit::refs_heads(&project.default_branch()));
-        let raw = self.raw();
+        let mut heads: BTreeMap<RefString, Vec<(RemoteId, Oid)>> = BTreeMap::new();
 
-        let mut heads = Vec::new();
         for delegate in doc.delegates.iter() {
-            let r = self.reference_oid(delegate, &branch_ref)?.into();
-
-            heads.push(r);
+            let delegate = delegate.as_key();
+
+            for pattern in &doc.canonical_refs {
+                let glob = format!("refs/namespaces/{delegate}/{pattern}");
+
+                for r in self.backend.references_glob(&glob)? {
+                    let r = r?;
+                    let (Some(name), Some(oid)) = (r.name(), r.target()) else {
+                        // Ignore symbolic refs.
+                        continue;
+                    };
+                    let (_, refname) =
+                        git::parse_ref_namespaced::<RemoteId>(name).map_err(refs::Error::from)?;
+                    let heads = heads.entry(refname.to_ref_string()).or_default();
+
+                    // A reference may match more than one pattern, since `*` matches
+                    // across `/`, but each delegate only counts once.
+                    if !heads.iter().any(|(d, _)| d == delegate) {
+                        heads.push((*delegate, oid.into()));
+                    }
+                }
+            }
         }
 
-        let oid = match heads.as_slice() {
-            [head] => Ok(*head),
-            // FIXME: This branch is not tested.
-            heads => raw.merge_base_many(heads),
-        }?;
+        heads
+            .into_iter()
+            .map(|(name, heads)| {
+                let target = quorum(&self.backend, &name, &heads, doc.threshold)?;
 
-        Ok((branch_ref, oid.into()))
+                Ok(CanonicalRef {
+                    name,
+                    target,
+                    heads,
+                })
+            })
+            .collect()
     }
 
     fn identity_head(&self) -> Result<Oid, IdentityError> {
@@ -621,6 +848,23 @@ impl WriteRepository for Repository {
         Ok(head)
     }
 
+    fn set_canonical_refs(&self) -> Result<Vec<CanonicalRef>, IdentityError> {
+        let refs = self.canonical_refs()?;
+
+        for r in &refs {
+            if let Some(target) = r.target {
+                log::debug!(target: "storage", "Setting ref: {} -> {}", r.name, target);
+                self.raw().reference(
+                    r.name.as_str(),
+                    *target,
+                    true,
+                    "set-canonical-ref (radicle)",
+                )?;
+            }
+        }
+        Ok(refs)
+    }
+
     fn set_identity_head(&self) -> Result<Oid, IdentityError> {
         let head = self.canonical_identity_head()?;
 
@@ -638,11 +882,18 @@ impl WriteRepository for Repository {
     fn sign_refs<G: Signer>(&self, signer: &G) -> Result<SignedRefs<Verified>, Error> {
         let remote = signer.public_key();
         let refs = self.references_of(remote)?;
-        let signed = refs.signed(signer)?;
-
-        signed.save(remote, self)?;
+        let parent = match self.reference_oid(remote, &refs::SIGREFS_BRANCH) {
+            Ok(oid) => Some(oid),
+            Err(git::Error::Git(e)) if git::is_not_found_err(&e) => None,
+            Err(git::Error::NotFound(_)) => None,
+            Err(e) => return Err(refs::Error::from(e).into()),
+        };
+        let signed = refs.signed_after(parent, signer)?;
 
-        Ok(signed)
+        match signed.save(remote, self)? {
+            refs::Updated::Updated { .. } => Ok(signed),
+            refs::Updated::Unchanged { oid } => Ok(SignedRefs::load_at(oid, remote, self)?),
+        }
     }
 
     fn raw(&self) -> &git2::Repository {
@@ -650,6 +901,190 @@ impl WriteRepository for Repository {
     }
 }
 
+/// Compute the canonical head of a repository from the default branch of its delegates.
+pub(crate) fn canonical_head<R: ReadRepository>(
+    repo: &R,
+    raw: &git2::Repository,
+) -> Result<(Qualified<'static>, Oid), IdentityError> {
+    // TODO: In the `fork` function for example, we call Repository::project_identity again,
+    // This should only be necessary once.
+    let (_, doc) = repo.identity_doc()?;
+    let doc = doc.verified()?;
+    let project = doc.project()?;
+    let branch_ref = Qualified::from(lit::refs_heads(&project.default_branch()));
+    let (heads, _) = delegate_heads(repo, &doc, &branch_ref)?;
+
+    let Some(oid) = quorum(raw, &branch_ref, &heads, doc.threshold)? else {
+        return Err(IdentityError::NoQuorum(branch_ref.to_ref_string()));
+    };
+    Ok((branch_ref, oid))
+}
+
+/// Get the head of the given branch of every delegate. Delegates that don't have the
+/// branch are returned separately.
+pub(crate) fn delegate_heads<R: ReadRepository>(
+    repo: &R,
+    doc: &Doc<Verified>,
+    branch: &Qualified,
+) -> Result<(Vec<(RemoteId, Oid)>, Vec<RemoteId>), git::Error> {
+    let mut heads = Vec::new();
+    let mut missing = Vec::new();
+
+    for delegate in doc.delegates.iter() {
+        match repo.reference_oid(delegate, branch) {
+            Ok(oid) => heads.push((*delegate.as_key(), oid)),
+            Err(git::Error::Git(e)) if git::is_not_found_err(&e) => {
+                missing.push(*delegate.as_key())
+            }
+            Err(git::Error::NotFound(_)) => missing.push(*delegate.as_key()),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((heads, missing))
+}
+
+/// Compute the canonical target of a reference, given the delegate heads.
+pub(crate) fn quorum(
+    raw: &git2::Repository,
+    name: &RefStr,
+    heads: &[(RemoteId, Oid)],
+    threshold: usize,
+) -> Result<Option<Oid>, git2::Error> {
+    let oids = heads.iter().map(|(_, oid)| *oid).collect::<Vec<_>>();
+
+    if oids.len() < threshold {
+        return Ok(None);
+    }
+    // Tags are canonical if enough delegates point them to the same object. Annotated
+    // tags differ between delegates, since they carry the tagger and message, so they
+    // are peeled to the object they tag.
+    if name.as_str().starts_with("refs/tags/") {
+        let mut targets = Vec::with_capacity(oids.len());
+        for oid in &oids {
+            let object = raw.find_object(**oid, None)?;
+            let target = match object.as_tag() {
+                Some(tag) => tag.target_id(),
+                None => object.id(),
+            };
+            targets.push(Oid::from(target));
+        }
+        let target = targets
+            .iter()
+            .find(|a| targets.iter().filter(|b| b == a).count() >= threshold);
+
+        return Ok(target.copied());
+    }
+
+    // Branches are canonical at the latest commit found in the history of enough heads.
+    // Besides the heads themselves, the latest commit shared by any two heads is a
+    // candidate, since a quorum of heads may agree on a commit none of them points to.
+    let mut candidates = Vec::new();
+    for oid in &oids {
+        if !candidates.contains(oid) {
+            candidates.push(*oid);
+        }
+    }
+    for (i, a) in oids.iter().enumerate() {
+        for b in &oids[i + 1..] {
+            if a == b {
+                continue;
+            }
+            match raw.merge_base(**a, **b) {
+                Ok(base) if !candidates.contains(&base.into()) => candidates.push(base.into()),
+                Ok(_) => {}
+                Err(e) if git::is_not_found_err(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    let mut accepted = Vec::new();
+    for candidate in candidates {
+        let mut votes = 0;
+        for head in &oids {
+            if *head == candidate || is_descendant_of(raw, *head, candidate)? {
+                votes += 1;
+            }
+        }
+        if votes >= threshold {
+            accepted.push(candidate);
+        }
+    }
+
+    // The target must descend from every other accepted candidate, otherwise the
+    // delegates have diverged.
+    for candidate in &accepted {
+        let mut latest = true;
+        for other in &accepted {
+            if other != candidate && !is_descendant_of(raw, *candidate, *other)? {
+                latest = false;
+                break;
+            }
+        }
+        if latest {
+            return Ok(Some(*candidate));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether `head` descends from `ancestor`. In shallow repositories, history may be
+/// truncated before the ancestor is found, in which case `head` isn't considered to
+/// descend from it.
+fn is_descendant_of(raw: &git2::Repository, head: Oid, ancestor: Oid) -> Result<bool, git2::Error> {
+    match raw.graph_descendant_of(*head, *ancestor) {
+        Ok(descendant) => Ok(descendant),
+        Err(e) if git::is_not_found_err(&e) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Compute the canonical identity head, given the identity branch heads of all remotes.
+///
+/// This is the head of the longest branch, as long as the other branches are behind it.
+pub(crate) fn canonical_identity_head(
+    raw: &git2::Repository,
+    mut heads: Vec<git2::Oid>,
+) -> Result<Oid, IdentityError> {
+    // Keep track of the longest identity branch.
+    let mut longest = heads.pop().ok_or(IdentityError::MissingBranch)?;
+
+    for head in &heads {
+        let base = raw.merge_base(*head, longest)?;
+
+        if base == longest {
+            // `head` is a successor of `longest`. Update `longest`.
+            //
+            //   o head
+            //   |
+            //   o longest (base)
+            //   |
+            //
+            longest = *head;
+        } else if base == *head || *head == longest {
+            // `head` is an ancestor of `longest`, or equal to it. Do nothing.
+            //
+            //   o longest             o longest, head (base)
+            //   |                     |
+            //   o head (base)   OR    o
+            //   |                     |
+            //
+        } else {
+            // The merge base between `head` and `longest` (`base`)
+            // is neither `head` nor `longest`. Therefore, the branches have
+            // diverged.
+            //
+            //    longest   head
+            //           \ /
+            //            o (base)
+            //            |
+            //
+            return Err(IdentityError::BranchesDiverge);
+        }
+    }
+    Ok(longest.into())
+}
+
 pub mod trailers {
     use std::str::FromStr;
 
@@ -708,6 +1143,7 @@ mod tests {
     use crypto::test::signer::MockSigner;
 
     use super::*;
+    use crate::assert_matches;
     use crate::git;
     use crate::storage::refs::SIGREFS_BRANCH;
     use crate::storage::{ReadRepository, ReadStorage, WriteRepository};
@@ -763,6 +1199,197 @@ mod tests {
         assert_eq!(refs, vec!["refs/heads/master", "refs/rad/id"]);
     }
 
+    #[test]
+    fn test_sigrefs_rollback() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, head) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let proj = storage.repository(id).unwrap();
+        let alice = signer.public_key();
+        let old = proj.sigrefs().unwrap()[alice];
+
+        proj.raw()
+            .reference(
+                &format!("refs/namespaces/{alice}/refs/heads/feature"),
+                head,
+                false,
+                "",
+            )
+            .unwrap();
+        proj.sign_refs(&signer).unwrap();
+
+        let new = proj.sigrefs().unwrap()[alice];
+        let history = SignedRefs::history(alice, &proj).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].at, new);
+        assert_eq!(history[0].parent, Some(old));
+        assert_eq!(history[0].sigrefs.parent, Some(old));
+        assert_eq!(history[1].at, old);
+
+        // Signing the same refs again doesn't create a new commit.
+        proj.sign_refs(&signer).unwrap();
+        assert_eq!(proj.sigrefs().unwrap()[alice], new);
+
+        assert!(proj
+            .check_sigrefs(&[(*alice, new)].into_iter().collect())
+            .unwrap()
+            .is_empty());
+
+        // Replay the older signed refs.
+        let rollbacks = proj
+            .check_sigrefs(&[(*alice, old)].into_iter().collect())
+            .unwrap();
+        assert_eq!(rollbacks.len(), 1);
+        assert_eq!(&rollbacks[0].0, alice);
+        assert_matches!(rollbacks[0].1, refs::Error::Rollback { .. });
+
+        // Replay the older signed refs in a new commit, on top of the known ones.
+        let forged = {
+            let raw = proj.raw();
+            let tree = raw.find_commit(*old).unwrap().tree().unwrap();
+            let parent = raw.find_commit(*new).unwrap();
+            let author = raw.signature().unwrap();
+
+            raw.commit(None, &author, &author, "Forged", &tree, &[&parent])
+                .unwrap()
+        };
+        let rollbacks = proj
+            .check_sigrefs(&[(*alice, forged.into())].into_iter().collect())
+            .unwrap();
+        assert_eq!(rollbacks.len(), 1);
+        assert_matches!(rollbacks[0].1, refs::Error::Rollback { .. });
+    }
+
+    #[test]
+    fn test_sigrefs_legacy() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, head) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let proj = storage.repository(id).unwrap();
+        let alice = signer.public_key();
+        let root = proj.sigrefs().unwrap()[alice];
+        assert_eq!(
+            SignedRefs::load_at(root, alice, &proj).unwrap().version(),
+            refs::Version::V1
+        );
+
+        // Signed refs without a signed parent, on top of the given commit, like the ones
+        // published by older nodes.
+        let legacy = |branch: &str, parent: Oid| -> Oid {
+            proj.raw()
+                .reference(
+                    &format!("refs/namespaces/{alice}/refs/heads/{branch}"),
+                    head,
+                    true,
+                    "",
+                )
+                .unwrap();
+            let raw = proj.raw();
+            let parent = raw.find_commit(*parent).unwrap();
+            let author = raw.signature().unwrap();
+            let signed = proj.references_of(alice).unwrap().signed(&signer).unwrap();
+
+            match signed
+                .commit(alice, None, Some(&parent), &author, raw)
+                .unwrap()
+            {
+                refs::Updated::Updated { oid } => oid,
+                refs::Updated::Unchanged { .. } => panic!("signed refs are unchanged"),
+            }
+        };
+
+        // Legacy updates are accepted as long as the known signed refs are legacy too.
+        let update = legacy("legacy", root);
+        assert!(proj
+            .check_sigrefs(&[(*alice, update)].into_iter().collect())
+            .unwrap()
+            .is_empty());
+
+        // Once the parent is signed, it's also published as a reference.
+        proj.sign_refs(&signer).unwrap();
+        let v2 = proj.sigrefs().unwrap()[alice];
+        let sigrefs = SignedRefs::load_at(v2, alice, &proj).unwrap();
+        assert_eq!(sigrefs.version(), refs::Version::V2);
+        assert_eq!(sigrefs.parent, Some(root));
+        assert_eq!(
+            proj.reference_oid(alice, &refs::SIGREFS_PARENT).unwrap(),
+            root
+        );
+        proj.verify().unwrap();
+
+        // Legacy updates can't follow signed parents.
+        let update = legacy("downgrade", v2);
+        let rollbacks = proj
+            .check_sigrefs(&[(*alice, update)].into_iter().collect())
+            .unwrap();
+        assert_eq!(rollbacks.len(), 1);
+        assert_matches!(rollbacks[0].1, refs::Error::Rollback { .. });
+    }
+
+    #[test]
+    fn test_fetch_limits() {
+        let tmp = tempfile::tempdir().unwrap();
+        let storage = Storage::open(tmp.path()).unwrap();
+        let repo = storage.create(arbitrary::gen::<Id>(1)).unwrap();
+        assert!(repo.fetch_limits().unwrap().is_empty());
+
+        let limits = FetchLimits {
+            depth: NonZeroU32::new(1),
+            filter: Some("blob:limit=1024".parse().unwrap()),
+        };
+        repo.set_fetch_limits(&limits).unwrap();
+        assert_eq!(repo.fetch_limits().unwrap(), limits);
+        assert_eq!(
+            limits.as_args(),
+            vec!["--depth=1", "--filter=blob:limit=1024"]
+        );
+
+        repo.set_fetch_limits(&FetchLimits::default()).unwrap();
+        assert!(repo.fetch_limits().unwrap().is_empty());
+        assert!("blob:limit=1k".parse::<crate::storage::Filter>().is_err());
+    }
+
+    #[test]
+    fn test_migrate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut rng = fastrand::Rng::new();
+        let alice = MockSigner::new(&mut rng);
+        let alice_new = MockSigner::new(&mut rng);
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        let (id, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &alice).unwrap();
+        let proj = storage.repository(id).unwrap();
+
+        assert_eq!(
+            proj.migrate(alice.public_key(), alice_new.public_key())
+                .unwrap(),
+            2
+        );
+        // Nothing left to migrate.
+        assert_eq!(
+            proj.migrate(alice.public_key(), alice_new.public_key())
+                .unwrap(),
+            0
+        );
+        proj.sign_refs(&alice_new).unwrap();
+
+        let mut old = proj.references_of(alice.public_key()).unwrap();
+        let mut new = proj.references_of(alice_new.public_key()).unwrap();
+        let sigref = (*SIGREFS_BRANCH).to_ref_string();
+        old.remove(&sigref).unwrap();
+        new.remove(&sigref).unwrap();
+
+        assert_eq!(old, new);
+        assert!(proj.remote(alice_new.public_key()).is_ok());
+    }
+
     #[test]
     fn test_sign_refs() {
         let tmp = tempfile::tempdir().unwrap();
@@ -799,4 +1426,77 @@ mod tests {
         assert_eq!(remote.refs, signed);
         assert_eq!(*remote.refs, unsigned);
     }
+
+    #[test]
+    fn test_canonical_refs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let proj = storage.repository(id).unwrap();
+        let (_, head) = proj.head().unwrap();
+        let alice = signer.public_key();
+
+        // Declare release branches and tags as canonical.
+        let (_, doc) = proj.identity_doc().unwrap();
+        let mut doc = doc.verified().unwrap();
+        doc.canonical_refs = vec![
+            refspec::pattern!("refs/heads/release/*"),
+            refspec::pattern!("refs/tags/*"),
+        ];
+        doc.sign(&signer)
+            .and_then(|(_, sig)| {
+                doc.update(alice, "Add canonical refs", &[(alice, sig)], proj.raw())
+            })
+            .unwrap();
+        proj.set_identity_head().unwrap();
+
+        for name in ["heads/release/1.0", "heads/feature", "tags/v1.0"] {
+            proj.raw()
+                .reference(
+                    &format!("refs/namespaces/{alice}/refs/{name}"),
+                    *head,
+                    false,
+                    "",
+                )
+                .unwrap();
+        }
+
+        let refs = proj.set_canonical_refs().unwrap();
+        let names = refs.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["refs/heads/release/1.0", "refs/tags/v1.0"]);
+        assert!(refs.iter().all(|r| r.target == Some(head)));
+        assert!(refs.iter().all(|r| r.heads == vec![(*alice, head)]));
+        assert_eq!(
+            proj.raw().refname_to_id("refs/heads/release/1.0").unwrap(),
+            *head
+        );
+        assert!(proj.raw().find_reference("refs/heads/feature").is_err());
+    }
+
+    #[test]
+    fn test_quorum_pairwise() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (raw, root) = fixtures::repository(tmp.path());
+        let commit = |parent: git2::Oid, msg: &str| -> Oid {
+            let parent = raw.find_commit(parent).unwrap();
+            let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+
+            raw.commit(None, &sig, &sig, msg, &parent.tree().unwrap(), &[&parent])
+                .unwrap()
+                .into()
+        };
+        let base = commit(root, "Base");
+        let alice = (arbitrary::gen::<RemoteId>(1), commit(*base, "Alice"));
+        let bob = (arbitrary::gen::<RemoteId>(1), commit(*base, "Bob"));
+        let eve = (arbitrary::gen::<RemoteId>(1), commit(root, "Eve"));
+
+        // Alice and Bob agree on `base`, which isn't the merge base of all heads, nor any
+        // of their heads.
+        let name = git::refname!("refs/heads/master");
+        let target = quorum(&raw, &name, &[alice, bob, eve], 2).unwrap();
+        assert_eq!(target, Some(base));
+    }
 }
@@ -0,0 +1,335 @@
+//! Divergence between delegates.
+//!
+//! The canonical head of a repository is computed from the default branch of every
+//! delegate. When delegates diverge, or some of them haven't published the branch, the
+//! canonical head can't be computed or falls behind. This module reports where each
+//! delegate stands relative to the others and to the canonical head, and what is
+//! preventing a quorum, if anything.
+use std::fmt;
+
+use crate::git;
+use crate::git::{lit, Oid, Qualified};
+use crate::identity::IdentityError;
+use crate::storage::git::{delegate_heads, quorum, Repository};
+use crate::storage::{ReadRepository, RemoteId};
+
+/// Number of commits a head is ahead and behind of another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AheadBehind {
+    /// Commits in the head that aren't in the other.
+    pub ahead: usize,
+    /// Commits in the other that aren't in the head.
+    pub behind: usize,
+}
+
+impl fmt::Display for AheadBehind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} -{}", self.ahead, self.behind)
+    }
+}
+
+/// The default branch of a delegate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegateHead {
+    /// The delegate.
+    pub remote: RemoteId,
+    /// Head of the delegate's default branch, if they have one.
+    pub head: Option<Oid>,
+    /// Position relative to the canonical head, if both exist.
+    pub canonical: Option<AheadBehind>,
+    /// Position relative to the heads of the other delegates.
+    pub others: Vec<(RemoteId, AheadBehind)>,
+}
+
+/// What prevents delegates from reaching a quorum on the default branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Blocking {
+    /// These delegates haven't published the default branch.
+    MissingBranch(Vec<RemoteId>),
+    /// The delegate heads don't share any history.
+    NoCommonAncestor,
+    /// No single commit is the latest in the history of enough delegates.
+    Diverged {
+        /// Number of delegates that need to agree.
+        threshold: usize,
+    },
+}
+
+impl fmt::Display for Blocking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingBranch(remotes) => {
+                let remotes = remotes
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "the default branch is missing for {remotes}")
+            }
+            Self::NoCommonAncestor => {
+                write!(f, "the delegate heads don't have a common ancestor")
+            }
+            Self::Diverged { threshold } => {
+                write!(
+                    f,
+                    "the heads of {threshold} delegate(s) don't agree on a commit"
+                )
+            }
+        }
+    }
+}
+
+/// Divergence report of the default branch of a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DivergenceReport {
+    /// The default branch.
+    pub branch: Qualified<'static>,
+    /// The canonical head, if enough delegates agree on it.
+    pub canonical: Option<Oid>,
+    /// Number of delegates that need to agree on the head.
+    pub threshold: usize,
+    /// Default branch of each delegate.
+    pub delegates: Vec<DelegateHead>,
+    /// What is preventing a quorum, if anything.
+    pub blocking: Option<Blocking>,
+}
+
+impl DivergenceReport {
+    /// Whether all delegates point their default branch to the same commit.
+    pub fn is_converged(&self) -> bool {
+        let mut heads = self.delegates.iter().map(|d| d.head);
+
+        match heads.next() {
+            Some(Some(first)) => heads.all(|h| h == Some(first)),
+            _ => false,
+        }
+    }
+}
+
+/// Compute the divergence report of the default branch of a repository.
+pub fn report(repo: &Repository) -> Result<DivergenceReport, IdentityError> {
+    let (_, doc) = repo.identity_doc()?;
+    let doc = doc.verified()?;
+    let project = doc.project()?;
+    let branch = Qualified::from(lit::refs_heads(&project.default_branch())).to_owned();
+
+    let (heads, missing) = delegate_heads(repo, &doc, &branch)?;
+
+    // The canonical head and what prevents it from being found are both derived from
+    // the quorum of delegate heads, like in [`ReadRepository::canonical_head`].
+    let canonical = quorum(&repo.backend, &branch, &heads, doc.threshold)?;
+    let blocking = if canonical.is_some() {
+        None
+    } else if heads.len() < doc.threshold {
+        Some(Blocking::MissingBranch(missing.clone()))
+    } else if !has_common_ancestor(repo, &heads)? {
+        Some(Blocking::NoCommonAncestor)
+    } else {
+        Some(Blocking::Diverged {
+            threshold: doc.threshold,
+        })
+    };
+
+    let mut delegates = Vec::new();
+    for (remote, head) in &heads {
+        let canonical = canonical
+            .map(|c| ahead_behind(repo, *head, c))
+            .transpose()?;
+        let mut others = Vec::new();
+
+        for (other, oid) in &heads {
+            if other != remote {
+                others.push((*other, ahead_behind(repo, *head, *oid)?));
+            }
+        }
+        delegates.push(DelegateHead {
+            remote: *remote,
+            head: Some(*head),
+            canonical,
+            others,
+        });
+    }
+    delegates.extend(missing.into_iter().map(|remote| DelegateHead {
+        remote,
+        head: None,
+        canonical: None,
+        others: vec![],
+    }));
+
+    Ok(DivergenceReport {
+        branch,
+        canonical,
+        threshold: doc.threshold,
+        delegates,
+        blocking,
+    })
+}
+
+fn has_common_ancestor(repo: &Repository, heads: &[(RemoteId, Oid)]) -> Result<bool, git2::Error> {
+    if heads.len() < 2 {
+        return Ok(true);
+    }
+    let oids = heads.iter().map(|(_, oid)| **oid).collect::<Vec<_>>();
+
+    match repo.backend.merge_base_many(&oids) {
+        Ok(_) => Ok(true),
+        Err(e) if git::is_not_found_err(&e) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn ahead_behind(repo: &Repository, head: Oid, other: Oid) -> Result<AheadBehind, git2::Error> {
+    let (ahead, behind) = repo.backend.graph_ahead_behind(*head, *other)?;
+
+    Ok(AheadBehind { ahead, behind })
+}
+
+#[cfg(test)]
+mod tests {
+    use radicle_crypto::test::signer::MockSigner;
+
+    use super::*;
+    use crate::crypto::Signer as _;
+    use crate::storage::git::Storage;
+    use crate::storage::{ReadStorage as _, WriteRepository as _};
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_divergence() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, head) =
+            fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let head = Oid::from(head);
+
+        let divergence = report(&repo).unwrap();
+        assert_eq!(divergence.canonical, Some(head));
+        assert_eq!(divergence.blocking, None);
+        assert!(divergence.is_converged());
+        assert_eq!(
+            divergence.delegates,
+            vec![DelegateHead {
+                remote: *alice.public_key(),
+                head: Some(head),
+                canonical: Some(AheadBehind {
+                    ahead: 0,
+                    behind: 0
+                }),
+                others: vec![],
+            }]
+        );
+
+        // Remove the delegate's default branch.
+        repo.backend
+            .find_reference(&divergence.branch.with_namespace(alice.public_key().into()))
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        let divergence = report(&repo).unwrap();
+        assert_eq!(divergence.canonical, None);
+        assert!(!divergence.is_converged());
+        assert_eq!(
+            divergence.blocking,
+            Some(Blocking::MissingBranch(vec![*alice.public_key()]))
+        );
+    }
+
+    #[test]
+    fn test_divergence_two_delegates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, head) =
+            fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let branch = report(&repo).unwrap().branch;
+        let base = repo.backend.find_commit(head).unwrap();
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+
+        // Alice and Bob each commit on top of the same head.
+        let mut heads = Vec::new();
+        for (signer, msg) in [(&alice, "Alice"), (&bob, "Bob")] {
+            let oid = repo
+                .backend
+                .commit(
+                    Some(branch.with_namespace(signer.public_key().into()).as_str()),
+                    &sig,
+                    &sig,
+                    msg,
+                    &base.tree().unwrap(),
+                    &[&base],
+                )
+                .unwrap();
+            heads.push(Oid::from(oid));
+        }
+        let update = |threshold: usize| {
+            let (_, doc) = repo.identity_doc().unwrap();
+            let mut doc = doc.verified().unwrap();
+            if !doc.is_delegate(bob.public_key()) {
+                doc.delegates.push(bob.public_key().into());
+            }
+            doc.threshold = threshold;
+            doc.sign(&alice)
+                .and_then(|(_, sig)| {
+                    doc.update(
+                        alice.public_key(),
+                        "Update delegates",
+                        &[(alice.public_key(), sig)],
+                        repo.raw(),
+                    )
+                })
+                .unwrap();
+            repo.set_identity_head().unwrap();
+        };
+
+        // With a threshold of one, each head is canonical on its own, and neither
+        // descends from the other.
+        update(1);
+        let divergence = report(&repo).unwrap();
+        assert_eq!(divergence.canonical, None);
+        assert_eq!(
+            divergence.blocking,
+            Some(Blocking::Diverged { threshold: 1 })
+        );
+        assert!(!divergence.is_converged());
+        assert!(matches!(
+            repo.canonical_head(),
+            Err(IdentityError::NoQuorum(_))
+        ));
+
+        // With a threshold of two, the delegates agree on the commit they both build on.
+        update(2);
+        let divergence = report(&repo).unwrap();
+        assert_eq!(divergence.canonical, Some(Oid::from(head)));
+        assert_eq!(divergence.blocking, None);
+        assert_eq!(
+            repo.canonical_head().unwrap(),
+            (branch.clone(), head.into())
+        );
+        for delegate in &divergence.delegates {
+            assert!(heads.contains(&delegate.head.unwrap()));
+            assert_eq!(
+                delegate.canonical,
+                Some(AheadBehind {
+                    ahead: 1,
+                    behind: 0
+                })
+            );
+            assert_eq!(
+                delegate
+                    .others
+                    .iter()
+                    .map(|(_, ab)| *ab)
+                    .collect::<Vec<_>>(),
+                vec![AheadBehind {
+                    ahead: 1,
+                    behind: 1
+                }]
+            );
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! Large object storage.
+//!
+//! Large files, eg. binary assets, are kept out of the history of a repository: a small
+//! pointer file is committed in their place, while their content is stored as a blob of the
+//! storage repository, pinned by a `refs/rad/lfs/<oid>` reference. These references live
+//! outside of the remote namespaces, so large objects are neither signed nor replicated
+//! with the rest of the repository. Instead, they are fetched by seeds whose tracking policy
+//! stores them, or on demand, when a working copy needs them.
+use std::io;
+use std::str::FromStr;
+
+use crate::git;
+use crate::git::{Oid, RefString};
+use crate::storage::git::Repository;
+use crate::storage::Error;
+
+/// First line of a pointer file.
+pub const POINTER_VERSION: &str = "version https://radicle.xyz/lfs/v1";
+/// Git configuration key of the large objects wanted by a repository.
+const CONFIG_WANT: &str = "radicle.lfswant";
+/// Pointer files are always smaller than this.
+pub const POINTER_MAX_SIZE: usize = 256;
+
+/// Reference pinning the given large object.
+pub fn refname(oid: &Oid) -> RefString {
+    git::refname!("refs/rad/lfs")
+        .join(RefString::try_from(oid.to_string()).expect("oids are valid ref strings"))
+}
+
+/// Fetchspec of large objects. When `oid` is given, only matches that object.
+///
+/// The fetchspec is always a pattern, so that fetching an object the remote doesn't have
+/// doesn't fail the fetch. It is forced, so that a ref that doesn't point to the object it
+/// is named after can be replaced. Such refs are deleted after fetching, see
+/// [`Repository::prune_large_objects`].
+pub fn fetchspec(oid: Option<&Oid>) -> String {
+    match oid {
+        Some(oid) => format!("+refs/rad/lfs/{oid}*:refs/rad/lfs/{oid}*"),
+        None => String::from("+refs/rad/lfs/*:refs/rad/lfs/*"),
+    }
+}
+
+/// A pointer file, committed in place of a large object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pointer {
+    /// Object id of the large object.
+    pub oid: Oid,
+    /// Size of the large object, in bytes.
+    pub size: u64,
+}
+
+impl Pointer {
+    /// Encode the pointer file.
+    pub fn encode(&self) -> String {
+        format!("{POINTER_VERSION}\noid {}\nsize {}\n", self.oid, self.size)
+    }
+
+    /// Decode a pointer file. Returns `None` if the input isn't a pointer file.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() > POINTER_MAX_SIZE {
+            return None;
+        }
+        let text = std::str::from_utf8(bytes).ok()?;
+        let mut lines = text.lines();
+
+        if lines.next()? != POINTER_VERSION {
+            return None;
+        }
+        let oid = lines.next()?.strip_prefix("oid ")?;
+        let oid = Oid::from_str(oid).ok()?;
+        let size = lines.next()?.strip_prefix("size ")?.parse().ok()?;
+
+        if lines.next().is_some() {
+            return None;
+        }
+        Some(Self { oid, size })
+    }
+}
+
+impl Repository {
+    /// Store a large object read from the given reader, and return its pointer.
+    pub fn store_large_object(&self, mut reader: impl io::Read) -> Result<Pointer, Error> {
+        let mut writer = self.backend.blob_writer(None)?;
+        let size = io::copy(&mut reader, &mut writer)?;
+        let oid = Oid::from(writer.commit()?);
+
+        self.backend
+            .reference(&refname(&oid), *oid, true, "Store large object")?;
+
+        Ok(Pointer { oid, size })
+    }
+
+    /// Get a large object, if it is in storage.
+    pub fn large_object(&self, oid: &Oid) -> Result<Option<git2::Blob>, Error> {
+        if let Err(e) = self.backend.find_reference(&refname(oid)) {
+            if git::is_not_found_err(&e) {
+                return Ok(None);
+            }
+            return Err(e.into());
+        }
+        Ok(Some(self.backend.find_blob(**oid)?))
+    }
+
+    /// Get the ids of the large objects in storage.
+    pub fn large_objects(&self) -> Result<Vec<Oid>, Error> {
+        let mut oids = Vec::new();
+
+        for r in self.backend.references_glob("refs/rad/lfs/*")? {
+            let r = r?;
+            let oid = r.target().ok_or(Error::InvalidRef)?;
+
+            oids.push(oid.into());
+        }
+        Ok(oids)
+    }
+
+    /// Delete the large object refs that don't point to the object they are named after.
+    /// Since large objects aren't signed, this must be done after every fetch. Returns the
+    /// names of the deleted refs.
+    pub fn prune_large_objects(&self) -> Result<Vec<String>, Error> {
+        let mut mismatched = Vec::new();
+
+        for r in self.backend.references_glob("refs/rad/lfs/*")? {
+            let r = r?;
+            let name = r.name().ok_or(Error::InvalidRef)?;
+            let named = name
+                .strip_prefix("refs/rad/lfs/")
+                .and_then(|oid| git::raw::Oid::from_str(oid).ok());
+
+            if named.is_none() || named != r.target() {
+                mismatched.push(name.to_owned());
+            }
+        }
+        for name in &mismatched {
+            self.backend.find_reference(name)?.delete()?;
+        }
+        Ok(mismatched)
+    }
+
+    /// Mark a large object as wanted, so that it is fetched along with the repository.
+    pub fn want_large_object(&self, oid: &Oid) -> Result<(), Error> {
+        let mut config = self.backend.config()?;
+        config.set_multivar(CONFIG_WANT, &format!("^{oid}$"), &oid.to_string())?;
+
+        Ok(())
+    }
+
+    /// Unmark a large object as wanted.
+    pub fn unwant_large_object(&self, oid: &Oid) -> Result<(), Error> {
+        let mut config = self.backend.config()?;
+
+        match config.remove_multivar(CONFIG_WANT, &format!("^{oid}$")) {
+            Ok(()) => Ok(()),
+            Err(e) if git::is_not_found_err(&e) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get the wanted large objects that aren't in storage yet.
+    pub fn wanted_large_objects(&self) -> Result<Vec<Oid>, Error> {
+        let config = self.backend.config()?;
+        let entries = config.multivar(CONFIG_WANT, None)?;
+        let mut wanted = Vec::new();
+
+        for entry in &entries {
+            let entry = entry?;
+            let Some(oid) = entry.value().and_then(|v| Oid::from_str(v).ok()) else {
+                continue;
+            };
+            if self.large_object(&oid)?.is_none() {
+                wanted.push(oid);
+            }
+        }
+        Ok(wanted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use radicle_crypto::test::signer::MockSigner;
+
+    use super::*;
+    use crate::storage::git::Storage;
+    use crate::storage::ReadStorage as _;
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_pointer_roundtrip() {
+        let pointer = Pointer {
+            oid: Oid::from_str("8d5b3c4e5d6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b").unwrap(),
+            size: 4096,
+        };
+        assert_eq!(Pointer::decode(pointer.encode().as_bytes()), Some(pointer));
+        assert_eq!(Pointer::decode(b"not a pointer"), None);
+    }
+
+    #[test]
+    fn test_large_objects() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let content = vec![0xff; 1024 * 64];
+
+        let pointer = repo.store_large_object(content.as_slice()).unwrap();
+        assert_eq!(pointer.size, content.len() as u64);
+        assert_eq!(repo.large_objects().unwrap(), vec![pointer.oid]);
+        assert_eq!(
+            repo.large_object(&pointer.oid).unwrap().unwrap().content(),
+            content.as_slice()
+        );
+
+        let missing = Oid::from_str("8d5b3c4e5d6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b").unwrap();
+        assert!(repo.large_object(&missing).unwrap().is_none());
+
+        repo.want_large_object(&missing).unwrap();
+        repo.want_large_object(&missing).unwrap();
+        repo.want_large_object(&pointer.oid).unwrap();
+        assert_eq!(repo.wanted_large_objects().unwrap(), vec![missing]);
+
+        repo.unwant_large_object(&missing).unwrap();
+        assert!(repo.wanted_large_objects().unwrap().is_empty());
+
+        // Refs that don't point to the object they are named after are deleted.
+        repo.backend
+            .reference(&refname(&missing), *pointer.oid, false, "")
+            .unwrap();
+        assert_eq!(
+            repo.prune_large_objects().unwrap(),
+            vec![refname(&missing).to_string()]
+        );
+        assert!(repo.large_object(&missing).unwrap().is_none());
+        assert_eq!(repo.large_objects().unwrap(), vec![pointer.oid]);
+    }
+}
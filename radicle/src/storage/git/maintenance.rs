@@ -0,0 +1,427 @@
+//! Storage maintenance.
+//!
+//! Repositories in storage accumulate the objects of every remote, and keep objects around
+//! that are no longer reachable, eg. the changes of removed COBs. Maintenance removes the
+//! references of untracked remotes, repacks repositories and prunes unreachable objects.
+//!
+//! Maintenance is safe to run concurrently with fetches: unreachable objects are only
+//! pruned once they are older than a grace period, so objects written by an ongoing fetch,
+//! which aren't referenced yet, are kept. Packs being received are protected by git's own
+//! `.keep` files, and concurrent maintenance of the same repository is prevented by git's
+//! `gc.pid` lock.
+//!
+//! Steps that can't rely on the grace period hold the repository lock (see
+//! [`Repository::lock`]), which fetches also hold: removing the references of remotes, and
+//! pruning the objects of redacted patch revisions, which are expired early. A fetch could
+//! otherwise rely on a redacted object being present, and update a reference to it just
+//! as it is pruned. If the repository stays locked, these steps are left to the next run.
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::{fs, io, ops, time};
+
+use thiserror::Error;
+
+use crate::cob;
+use crate::cob::op::Ops;
+use crate::cob::patch::{self, Patches};
+use crate::cob::store;
+use crate::git;
+use crate::identity::{Id, IdentityError};
+use crate::storage;
+use crate::storage::git::{Repository, Storage};
+use crate::storage::refs;
+use crate::storage::{ReadRepository, ReadStorage, RemoteId};
+
+/// Default grace period before unreachable objects are pruned. Same as git's default.
+pub const DEFAULT_PRUNE_EXPIRY: time::Duration = time::Duration::from_secs(60 * 60 * 24 * 14);
+/// Default time to wait for a repository lock held by a fetch.
+pub const DEFAULT_LOCK_TIMEOUT: time::Duration = time::Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("i/o: {0}")]
+    Io(#[from] io::Error),
+    #[error("identity: {0}")]
+    Identity(#[from] IdentityError),
+    #[error("refs: {0}")]
+    Refs(#[from] refs::Error),
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("cob: {0}")]
+    Cob(#[from] store::Error),
+}
+
+/// Maintenance options.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Unreachable objects younger than this are not pruned.
+    pub prune_expiry: time::Duration,
+    /// Whether to remove the references of remotes that are not kept.
+    pub prune_remotes: bool,
+    /// How long to wait for the repository lock, before skipping the steps that need it.
+    pub lock_timeout: time::Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            prune_expiry: DEFAULT_PRUNE_EXPIRY,
+            prune_remotes: true,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+        }
+    }
+}
+
+/// Outcome of a maintenance run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    /// Number of repositories maintained.
+    pub repositories: usize,
+    /// Number of remotes whose references were removed.
+    pub remotes: usize,
+    /// Number of bytes reclaimed.
+    pub reclaimed: u64,
+}
+
+impl ops::AddAssign for Report {
+    fn add_assign(&mut self, other: Self) {
+        self.repositories += other.repositories;
+        self.remotes += other.remotes;
+        self.reclaimed += other.reclaimed;
+    }
+}
+
+/// Run maintenance on all repositories in storage. Remotes of a repository for which `keep`
+/// returns `false` are removed, unless they are delegates of the repository.
+///
+/// Repositories that fail maintenance are logged and skipped.
+pub fn run(
+    storage: &Storage,
+    keep: impl Fn(&Id, &RemoteId) -> bool,
+    opts: &Options,
+) -> Result<Report, Error> {
+    let mut report = Report::default();
+
+    for rid in storage.repositories()? {
+        let repo = storage.repository(rid)?;
+
+        match repository(&repo, |remote| keep(&rid, remote), opts) {
+            Ok(r) => report += r,
+            Err(e) => log::warn!(target: "storage", "Maintenance of {rid} failed: {e}"),
+        }
+    }
+    Ok(report)
+}
+
+/// Run maintenance on a repository. Remotes for which `keep` returns `false` are removed,
+/// unless they are delegates of the repository.
+pub fn repository(
+    repo: &Repository,
+    keep: impl Fn(&RemoteId) -> bool,
+    opts: &Options,
+) -> Result<Report, Error> {
+    let objects = repo.path().join("objects");
+    let before = disk_usage(&objects)?;
+    let mut report = Report {
+        repositories: 1,
+        ..Report::default()
+    };
+
+    if opts.prune_remotes {
+        let delegates = repo.delegates()?;
+
+        report.remotes = locked(repo, opts, || {
+            let mut removed = 0;
+
+            for remote in repo.remote_ids()?.collect::<Result<Vec<_>, _>>()? {
+                if keep(&remote) || delegates.iter().any(|d| d.as_key() == &remote) {
+                    continue;
+                }
+                log::debug!(
+                    target: "storage",
+                    "Removing untracked remote {remote} from {}", repo.id
+                );
+
+                for r in repo
+                    .backend
+                    .references_glob(format!("refs/namespaces/{remote}/*").as_str())?
+                {
+                    r?.delete()?;
+                }
+                removed += 1;
+            }
+            Ok(removed)
+        })?
+        .unwrap_or_default();
+    }
+
+    let redacted = redacted(repo)?;
+    let expiry = format!("{}.seconds.ago", opts.prune_expiry.as_secs());
+
+    git::run(
+        repo.path(),
+        ["gc", "--quiet", format!("--prune={expiry}").as_str()],
+        git::env::GIT_DEFAULT_CONFIG,
+    )?;
+    // Unreachable objects are left loose by `gc` until they expire. Redacted objects are
+    // expired early, which is only safe while no fetch is running.
+    if !redacted.is_empty() {
+        locked(repo, opts, || {
+            if expire(repo, redacted)? > 0 {
+                git::run(
+                    repo.path(),
+                    ["prune", format!("--expire={expiry}").as_str()],
+                    git::env::GIT_DEFAULT_CONFIG,
+                )?;
+            }
+            Ok(())
+        })?;
+    }
+    report.reclaimed = before.saturating_sub(disk_usage(&objects)?);
+
+    Ok(report)
+}
+
+/// Run `f` while holding the repository lock. Returns `None` if the lock couldn't be
+/// acquired in time, in which case `f` is left to the next maintenance run.
+fn locked<T>(
+    repo: &Repository,
+    opts: &Options,
+    f: impl FnOnce() -> Result<T, Error>,
+) -> Result<Option<T>, Error> {
+    let _lock = match repo.lock(opts.lock_timeout) {
+        Ok(lock) => lock,
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+            log::debug!(target: "storage", "Skipping locked maintenance of {}: {e}", repo.id);
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    f().map(Some)
+}
+
+/// Get the objects of patch revisions that were redacted: their commits and trees, up to
+/// the revision base. Objects shared with revisions that weren't redacted are left out.
+fn redacted(repo: &Repository) -> Result<HashSet<git::Oid>, Error> {
+    let patches = Patches::open(repo)?;
+    let mut present = Vec::new();
+    let mut revisions = Vec::new();
+
+    for result in patches.all()? {
+        // Objects that fail to load are left to the grace period.
+        let Ok((id, patch, _)) = result else {
+            continue;
+        };
+        let mut redacted = Vec::new();
+
+        for (rid, revision) in patch.revisions.iter() {
+            match revision.get() {
+                Some(revision) => present.push(revision.oid),
+                None => redacted.push(*rid),
+            }
+        }
+        if redacted.is_empty() {
+            continue;
+        }
+        let Some(cob) = cob::get(repo, &patch::TYPENAME, &id).map_err(store::Error::from)? else {
+            continue;
+        };
+        cob.history().traverse((), |_, entry| {
+            if !redacted.contains(entry.id()) {
+                return ControlFlow::Continue(());
+            }
+            if let Ok(Ops(ops)) = Ops::<patch::Action>::try_from(entry) {
+                for op in ops {
+                    if let patch::Action::Revision { base, oid, .. } = op.action {
+                        revisions.push((base, oid));
+                    }
+                }
+            }
+            ControlFlow::Continue(())
+        });
+    }
+
+    let mut objects = HashSet::new();
+    for (base, oid) in revisions {
+        let mut walk = repo.backend.revwalk()?;
+        // The revision may have never been fetched, or was already pruned.
+        if walk.push(*oid).is_err() {
+            continue;
+        }
+        for hide in present.iter().chain(Some(&base)) {
+            walk.hide(**hide).ok();
+        }
+        for commit in walk {
+            let Ok(commit) = commit.and_then(|oid| repo.backend.find_commit(oid)) else {
+                break;
+            };
+            let tree = commit.tree()?;
+
+            objects.insert(commit.id().into());
+            objects.insert(tree.id().into());
+            tree.walk(git2::TreeWalkMode::PreOrder, |_, entry| {
+                objects.insert(entry.id().into());
+                git2::TreeWalkResult::Ok
+            })?;
+        }
+    }
+    Ok(objects)
+}
+
+/// Expire the given objects, if they are loose, so that the next prune removes them
+/// if they are unreachable. Returns the number of objects expired.
+///
+/// This must only be called while holding the repository lock.
+fn expire(repo: &Repository, objects: impl IntoIterator<Item = git::Oid>) -> Result<usize, Error> {
+    let dir = repo.path().join("objects");
+    let mut expired = 0;
+
+    for oid in objects {
+        let hex = oid.to_string();
+        let path = dir.join(&hex[..2]).join(&hex[2..]);
+
+        match filetime::set_file_mtime(path, filetime::FileTime::zero()) {
+            Ok(()) => expired += 1,
+            // Unreachable objects are unpacked by `gc`, so packed objects are reachable.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(expired)
+}
+
+/// Get the disk usage of a directory, in bytes. Files that are removed while the
+/// directory is walked, eg. by a concurrent fetch, are ignored.
+fn disk_usage(path: &Path) -> io::Result<u64> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut size = 0;
+
+    for entry in entries {
+        let entry = entry?;
+        let meta = match entry.metadata() {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        if meta.is_dir() {
+            size += disk_usage(&entry.path())?;
+        } else {
+            size += meta.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use radicle_crypto::test::signer::MockSigner;
+
+    use super::*;
+    use crate::cob::issue::Issues;
+    use crate::crypto::Signer as _;
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_maintenance() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        // Bob creates an issue, and is then untracked.
+        let mut issues = Issues::open(&repo).unwrap();
+        issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &bob)
+            .unwrap();
+        assert_eq!(repo.remote_ids().unwrap().count(), 2);
+
+        // Nothing is removed when all remotes are kept.
+        let report = repository(&repo, |_| true, &Options::default()).unwrap();
+        assert_eq!(report.repositories, 1);
+        assert_eq!(report.remotes, 0);
+        assert_eq!(repo.remote_ids().unwrap().count(), 2);
+
+        // Delegates are always kept.
+        let opts = Options {
+            prune_expiry: time::Duration::ZERO,
+            ..Options::default()
+        };
+        let report = run(&storage, |_, _| false, &opts).unwrap();
+        assert_eq!(report.repositories, 1);
+        assert_eq!(report.remotes, 1);
+
+        let remotes = repo.remote_ids().unwrap().collect::<Result<Vec<_>, _>>();
+        assert_eq!(remotes.unwrap(), vec![*alice.public_key()]);
+        assert!(repo.head().is_ok());
+    }
+
+    #[test]
+    fn test_maintenance_redacted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let (_, head) = repo.head().unwrap();
+        let head = repo.backend.find_commit(*head).unwrap();
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+
+        // Two unreachable commits, proposed as patch revisions.
+        let commit = |msg: &str| -> git::Oid {
+            repo.backend
+                .commit(None, &sig, &sig, msg, &head.tree().unwrap(), &[&head])
+                .unwrap()
+                .into()
+        };
+        let kept = commit("Kept");
+        let redacted = commit("Redacted");
+
+        let mut patches = Patches::open(&repo).unwrap();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                patch::MergeTarget::Delegates,
+                head.id(),
+                kept,
+                &[],
+                &alice,
+            )
+            .unwrap();
+        let revision = patch
+            .transaction("Update", &alice, |tx| {
+                tx.revision("Second revision", head.id(), redacted)
+            })
+            .unwrap();
+        patch
+            .transaction("Redact", &alice, |tx| {
+                tx.push(patch::Action::Redact { revision })
+            })
+            .unwrap();
+
+        // Nothing is pruned early while a fetch holds the repository lock.
+        let opts = Options {
+            lock_timeout: time::Duration::ZERO,
+            ..Options::default()
+        };
+        let lock = repo.lock(time::Duration::ZERO).unwrap();
+        repository(&repo, |_| true, &opts).unwrap();
+        assert!(repo.backend.find_commit(*redacted).is_ok());
+        drop(lock);
+
+        // Only the objects of the redacted revision are pruned before the grace period.
+        repository(&repo, |_| true, &opts).unwrap();
+        assert!(repo.backend.find_commit(*kept).is_ok());
+        assert!(repo.backend.find_commit(*redacted).is_err());
+        assert!(repo.head().is_ok());
+    }
+}
@@ -0,0 +1,511 @@
+//! Storage verification.
+//!
+//! Checks that the contents of storage are what their owners signed: every remote's
+//! signed refs must match the refs present, identity branches must carry enough valid
+//! signatures, and collaborative object changes must be signed correctly. Objects that
+//! are referenced but missing from the object database are reported, as are dangling
+//! objects, which aren't a problem in themselves, but can be reclaimed by maintenance.
+use std::collections::HashSet;
+use std::io;
+use std::process::Command;
+
+use radicle_cob::change::Storage as _;
+use thiserror::Error;
+
+use crate::git;
+use crate::git::{Oid, RefString};
+use crate::identity::{Id, IdentityError};
+use crate::storage;
+use crate::storage::git::{Repository, Storage};
+use crate::storage::refs;
+use crate::storage::refs::Refs;
+use crate::storage::{ReadRepository, ReadStorage, RemoteId};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("i/o: {0}")]
+    Io(#[from] io::Error),
+    #[error("refs: {0}")]
+    Refs(#[from] refs::Error),
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+}
+
+/// A problem found while verifying a repository.
+#[derive(Debug, Error)]
+pub enum Problem {
+    #[error("signed refs of remote `{0}` are invalid: {1}")]
+    SignedRefs(RemoteId, refs::Error),
+    #[error("unknown reference `{1}` in remote `{0}`")]
+    UnknownRef(RemoteId, RefString),
+    #[error("missing reference `{1}` in remote `{0}`")]
+    MissingRef(RemoteId, RefString),
+    #[error("invalid target `{2}` for reference `{1}` of remote `{0}`")]
+    InvalidRefTarget(RemoteId, RefString, Oid),
+    #[error("invalid identity of remote `{0}`: {1}")]
+    Identity(RemoteId, IdentityError),
+    #[error("invalid change `{2}` of `{1}` in remote `{0}`: {3}")]
+    Change(
+        RemoteId,
+        RefString,
+        Oid,
+        radicle_cob::git::change::error::Load,
+    ),
+    #[error("invalid signature on change `{2}` of `{1}` in remote `{0}`")]
+    ChangeSignature(RemoteId, RefString, Oid),
+    #[error("missing {0} `{1}`")]
+    MissingObject(String, Oid),
+    #[error("verification failed: {0}")]
+    Failed(#[from] Error),
+}
+
+/// Verification outcome of a single repository.
+#[derive(Debug)]
+pub struct RepositoryReport {
+    /// The repository verified.
+    pub rid: Id,
+    /// Problems found.
+    pub problems: Vec<Problem>,
+    /// Objects that aren't reachable from any reference.
+    pub dangling: Vec<Oid>,
+}
+
+impl RepositoryReport {
+    /// Whether the repository is free of problems.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Verification outcome of storage.
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Reports of all repositories in storage.
+    pub repositories: Vec<RepositoryReport>,
+}
+
+impl VerifyReport {
+    /// Whether all repositories are free of problems.
+    pub fn is_ok(&self) -> bool {
+        self.repositories.iter().all(|r| r.is_ok())
+    }
+
+    /// Iterate over the repositories that have problems.
+    pub fn failed(&self) -> impl Iterator<Item = &RepositoryReport> {
+        self.repositories.iter().filter(|r| !r.is_ok())
+    }
+}
+
+/// Verify all repositories in storage.
+pub fn run(storage: &Storage) -> Result<VerifyReport, Error> {
+    let mut report = VerifyReport::default();
+
+    for rid in storage.repositories()? {
+        let r = storage
+            .repository(rid)
+            .map_err(Error::from)
+            .and_then(|repo| repository(&repo));
+
+        report.repositories.push(match r {
+            Ok(r) => r,
+            Err(e) => RepositoryReport {
+                rid,
+                problems: vec![Problem::Failed(e)],
+                dangling: vec![],
+            },
+        });
+    }
+    Ok(report)
+}
+
+/// Verify a repository.
+pub fn repository(repo: &Repository) -> Result<RepositoryReport, Error> {
+    let mut problems = Vec::new();
+    let mut signed = Vec::new();
+    let mut cobs = Vec::new();
+
+    for remote in repo.remote_ids()? {
+        let remote = remote?;
+
+        match repo.remote(&remote) {
+            Ok(r) => signed.push((remote, Refs::from(r.refs))),
+            Err(e) => problems.push(Problem::SignedRefs(remote, e)),
+        }
+    }
+
+    for entry in repo.namespaced_references()? {
+        let (remote, refname, oid) = entry?;
+        let refname = RefString::from(refname);
+        if refname.as_str().starts_with("refs/cobs/") {
+            cobs.push((remote, refname.clone(), oid));
+        }
+        let Some((_, refs)) = signed.iter_mut().find(|(id, _)| id == &remote) else {
+            // Remotes without valid signed refs have already been reported.
+            continue;
+        };
+
+        match refs.remove(&refname) {
+            Some(signed) if signed == oid => {}
+            Some(_) => problems.push(Problem::InvalidRefTarget(remote, refname, oid)),
+            None => problems.push(Problem::UnknownRef(remote, refname)),
+        }
+    }
+
+    for (remote, refs) in signed {
+        // The refs that are left are ones that were signed, but are not in the repository.
+        for (refname, _) in refs.iter() {
+            problems.push(Problem::MissingRef(remote, refname.clone()));
+        }
+        if let Err(e) = repo
+            .identity_of(&remote)
+            .and_then(|identity| identity.verified(repo.id))
+        {
+            problems.push(Problem::Identity(remote, e));
+        }
+    }
+    for (remote, refname, oid) in cobs {
+        changes(repo, &remote, &refname, oid, &mut problems)?;
+    }
+
+    let (missing, dangling) = fsck(repo)?;
+    problems.extend(
+        missing
+            .into_iter()
+            .map(|(kind, oid)| Problem::MissingObject(kind, oid)),
+    );
+
+    Ok(RepositoryReport {
+        rid: repo.id,
+        problems,
+        dangling,
+    })
+}
+
+/// Verify the signatures of all changes of a collaborative object.
+fn changes(
+    repo: &Repository,
+    remote: &RemoteId,
+    refname: &RefString,
+    head: Oid,
+    problems: &mut Vec<Problem>,
+) -> Result<(), Error> {
+    let mut visited = HashSet::new();
+    let mut queue = vec![head];
+
+    while let Some(oid) = queue.pop() {
+        if !visited.insert(oid) {
+            continue;
+        }
+        let change = match repo.backend.load(oid) {
+            Ok(change) => change,
+            Err(e) => {
+                problems.push(Problem::Change(*remote, refname.clone(), oid, e));
+                continue;
+            }
+        };
+        if !change.valid_signatures() {
+            problems.push(Problem::ChangeSignature(*remote, refname.clone(), oid));
+        }
+        // The resource, ie. the identity commit, is a parent of every change, but isn't
+        // a change itself.
+        let commit = repo.backend.find_commit(oid.into())?;
+        queue.extend(
+            commit
+                .parent_ids()
+                .map(Oid::from)
+                .filter(|p| *p != change.resource),
+        );
+    }
+    Ok(())
+}
+
+/// Check the connectivity of the object database, returning missing and dangling objects.
+fn fsck(repo: &Repository) -> Result<(Vec<(String, Oid)>, Vec<Oid>), Error> {
+    // Unlike `git::run`, we don't want to fail on a non-zero exit status, since that is
+    // how missing objects are signaled.
+    let output = Command::new("git")
+        .current_dir(repo.path())
+        .envs(git::env::GIT_DEFAULT_CONFIG)
+        .args(["fsck", "--connectivity-only", "--no-progress"])
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut missing = Vec::new();
+    let mut dangling = Vec::new();
+
+    for line in stdout.lines() {
+        let mut words = line.split_whitespace();
+        let (Some(status), Some(kind), Some(oid)) = (words.next(), words.next(), words.next())
+        else {
+            continue;
+        };
+        let Ok(oid) = oid.parse::<Oid>() else {
+            continue;
+        };
+        match status {
+            "missing" => missing.push((kind.to_owned(), oid)),
+            "dangling" => dangling.push(oid),
+            _ => {}
+        }
+    }
+    if missing.is_empty() && !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr),
+        )
+        .into());
+    }
+    Ok((missing, dangling))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use radicle_crypto::test::signer::MockSigner;
+
+    use super::*;
+    use crate::cob::issue::Issues;
+    use crate::storage::git::paths;
+    use crate::storage::WriteRepository;
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_verify() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        let report = run(&storage).unwrap();
+        assert!(report.is_ok(), "{report:?}");
+        assert_eq!(report.repositories.len(), 1);
+
+        // Add a reference that wasn't signed.
+        let (_, head) = repo.head().unwrap();
+        repo.backend
+            .reference(
+                &format!("refs/namespaces/{}/refs/heads/unsigned", alice.public_key()),
+                head.into(),
+                false,
+                "",
+            )
+            .unwrap();
+
+        let report = repository(&repo).unwrap();
+        assert!(!report.is_ok());
+        assert!(matches!(
+            report.problems.as_slice(),
+            [Problem::UnknownRef(remote, name)]
+            if remote == alice.public_key() && name.as_str() == "refs/heads/unsigned"
+        ));
+    }
+
+    #[test]
+    fn test_verify_refs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let master = format!("refs/namespaces/{}/refs/heads/master", alice.public_key());
+
+        // Move a signed reference without signing it.
+        let (_, head) = repo.head().unwrap();
+        let head = repo.backend.find_commit(*head).unwrap();
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let commit = repo
+            .backend
+            .commit(
+                None,
+                &sig,
+                &sig,
+                "Unsigned",
+                &head.tree().unwrap(),
+                &[&head],
+            )
+            .unwrap();
+        repo.backend.reference(&master, commit, true, "").unwrap();
+
+        let report = repository(&repo).unwrap();
+        assert!(matches!(
+            report.problems.as_slice(),
+            [Problem::InvalidRefTarget(remote, name, oid)]
+            if remote == alice.public_key()
+                && name.as_str() == "refs/heads/master"
+                && *oid == commit.into()
+        ));
+
+        // Remove a signed reference.
+        repo.backend
+            .find_reference(&master)
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        let report = repository(&repo).unwrap();
+        assert!(matches!(
+            report.problems.as_slice(),
+            [Problem::MissingRef(remote, name)]
+            if remote == alice.public_key() && name.as_str() == "refs/heads/master"
+        ));
+    }
+
+    #[test]
+    fn test_verify_change_signature() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        let mut issues = Issues::open(&repo).unwrap();
+        let issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &[], &alice)
+            .unwrap();
+        let name = format!("refs/cobs/xyz.radicle.issue/{}", issue.id());
+        let refname = format!("refs/namespaces/{}/{name}", alice.public_key());
+        let head = repo.backend.refname_to_id(&refname).unwrap();
+
+        // Forge a change with a different tree, keeping the original signature.
+        let commit = repo.backend.find_commit(head).unwrap();
+        let tree = {
+            let mut builder = repo
+                .backend
+                .treebuilder(Some(&commit.tree().unwrap()))
+                .unwrap();
+            let blob = repo.backend.blob(b"forged").unwrap();
+            builder.insert("forged", blob, 0o100644).unwrap();
+            builder.write().unwrap()
+        };
+        let odb = repo.backend.odb().unwrap();
+        let object = odb.read(head).unwrap();
+        let forged = String::from_utf8(object.data().to_vec()).unwrap().replacen(
+            &format!("tree {}", commit.tree_id()),
+            &format!("tree {tree}"),
+            1,
+        );
+        let forged = odb
+            .write(git2::ObjectType::Commit, forged.as_bytes())
+            .unwrap();
+        repo.backend.reference(&refname, forged, true, "").unwrap();
+        repo.sign_refs(&alice).unwrap();
+
+        let report = repository(&repo).unwrap();
+        assert!(matches!(
+            report.problems.as_slice(),
+            [Problem::ChangeSignature(remote, refname, oid)]
+            if remote == alice.public_key()
+                && refname.as_str() == name
+                && *oid == forged.into()
+        ));
+    }
+
+    #[test]
+    fn test_verify_missing_object() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        // Sign a branch, and then lose one of its objects.
+        let (_, head) = repo.head().unwrap();
+        let head = repo.backend.find_commit(*head).unwrap();
+        let blob = repo.backend.blob(b"Lost").unwrap();
+        let tree = {
+            let mut builder = repo
+                .backend
+                .treebuilder(Some(&head.tree().unwrap()))
+                .unwrap();
+            builder.insert("LOST", blob, 0o100644).unwrap();
+            repo.backend.find_tree(builder.write().unwrap()).unwrap()
+        };
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let commit = repo
+            .backend
+            .commit(None, &sig, &sig, "Lost", &tree, &[&head])
+            .unwrap();
+        repo.backend
+            .reference(
+                &format!("refs/namespaces/{}/refs/heads/lost", alice.public_key()),
+                commit,
+                false,
+                "",
+            )
+            .unwrap();
+        repo.sign_refs(&alice).unwrap();
+
+        let hex = blob.to_string();
+        fs::remove_file(repo.path().join("objects").join(&hex[..2]).join(&hex[2..])).unwrap();
+
+        let report = repository(&repo).unwrap();
+        assert!(matches!(
+            report.problems.as_slice(),
+            [Problem::MissingObject(kind, oid)] if kind == "blob" && *oid == blob.into()
+        ));
+    }
+
+    #[test]
+    fn test_verify_partial() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, _) = fixtures::project(tmp.path().join("copy"), &storage, &alice).unwrap();
+        let source = storage.repository(id).unwrap();
+        let (_, head) = source.head().unwrap();
+        let readme = source
+            .backend
+            .find_commit(*head)
+            .unwrap()
+            .tree()
+            .unwrap()
+            .get_name("README")
+            .unwrap()
+            .id();
+        source
+            .backend
+            .config()
+            .unwrap()
+            .set_bool("uploadpack.allowFilter", true)
+            .unwrap();
+
+        // Fetch the metadata in full, and the branches without their blobs, like a fetch
+        // limited by a filter does.
+        let partial = Storage::open(tmp.path().join("partial")).unwrap();
+        let repo = Repository::create(paths::repository(&partial, &id), id).unwrap();
+        let url = format!("file://{}", source.path().display());
+        let ns = format!("refs/namespaces/{}", alice.public_key());
+
+        git::run(
+            repo.path(),
+            [
+                "fetch",
+                url.as_str(),
+                format!("{ns}/refs/rad/*:{ns}/refs/rad/*").as_str(),
+            ],
+            git::env::GIT_DEFAULT_CONFIG,
+        )
+        .unwrap();
+        git::run(
+            repo.path(),
+            [
+                "-c",
+                format!("remote.rad.url={url}").as_str(),
+                "fetch",
+                "--filter=blob:none",
+                "rad",
+                format!("{ns}/refs/heads/*:{ns}/refs/heads/*").as_str(),
+            ],
+            git::env::GIT_DEFAULT_CONFIG,
+        )
+        .unwrap();
+        assert!(repo.backend.find_blob(readme).is_err());
+
+        // The blobs that are missing are promised by the remote, and aren't a problem.
+        let report = repository(&repo).unwrap();
+        assert!(report.is_ok(), "{report:?}");
+    }
+}
@@ -0,0 +1,635 @@
+//! In-memory storage.
+//!
+//! Repositories are kept in memory: objects are stored in an in-memory object database,
+//! and references in a map. This makes the storage fast and deterministic, which is useful
+//! for tests and simulations.
+//!
+//! References are read and written via the storage traits, and
+//! [`MemoryRepository::set_reference`]. Since the repositories have no reference database,
+//! [`ReadRepository::reference`] always fails, and [`ReadRepository::reference_oid`] should
+//! be used instead. Likewise, references can't be written via [`WriteRepository::raw`].
+//!
+//! Nothing is written to disk: the storage and repository paths are virtual, under [`PATH`].
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fmt, io};
+
+use crypto::{Signer, Unverified, Verified};
+
+use crate::git;
+use crate::git::{Oid, Qualified, RefStr, RefString};
+use crate::identity;
+use crate::identity::doc::{Doc, Id};
+use crate::identity::{Identity, IdentityError};
+use crate::storage::git::{canonical_head, canonical_identity_head, quorum, CANONICAL_IDENTITY};
+use crate::storage::refs;
+use crate::storage::refs::{Refs, SignedRefs, SIGREFS_BRANCH, SIGREFS_PARENT};
+use crate::storage::{
+    CanonicalRef, Error, FetchLimits, Inventory, ReadRepository, ReadStorage, Remote, RemoteId,
+    Remotes, VerifyError, WriteRepository, WriteStorage,
+};
+
+/// The symbolic `HEAD` reference.
+const HEAD: &str = "HEAD";
+/// Virtual path of the storage. Nothing exists under it on disk.
+pub const PATH: &str = ":memory:";
+
+/// Storage that keeps all repositories in memory.
+///
+/// Cloning the storage is cheap, and clones share the same repositories.
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    repos: Arc<Mutex<BTreeMap<Id, MemoryRepository>>>,
+}
+
+impl fmt::Debug for MemoryStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryStorage")
+            .field("repos", &*self.repos())
+            .finish()
+    }
+}
+
+impl MemoryStorage {
+    /// Create a new, empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a repository and the signer's identity branch from an identity document.
+    pub fn init<G: Signer>(
+        &self,
+        doc: &Doc<Verified>,
+        signer: &G,
+    ) -> Result<(MemoryRepository, git::Oid), Error> {
+        let remote = signer.public_key();
+        let (doc_oid, doc) = doc.encode()?;
+        let repo = self.create(Id::from(doc_oid))?;
+        let oid = Doc::init_at(
+            doc.as_slice(),
+            remote,
+            &[(remote, signer.sign(doc_oid.as_bytes()))],
+            None,
+            repo.raw(),
+        )?;
+        repo.set_reference(&git::refs::storage::id(remote).to_ref_string(), oid);
+
+        Ok((repo, oid))
+    }
+
+    fn repos(&self) -> std::sync::MutexGuard<BTreeMap<Id, MemoryRepository>> {
+        self.repos
+            .lock()
+            .expect("MemoryStorage::repos: lock is poisoned")
+    }
+}
+
+impl ReadStorage for MemoryStorage {
+    type Repository = MemoryRepository;
+
+    fn path(&self) -> &Path {
+        Path::new(PATH)
+    }
+
+    fn path_of(&self, rid: &Id) -> PathBuf {
+        self.path().join(rid.canonical())
+    }
+
+    fn contains(&self, rid: &Id) -> Result<bool, IdentityError> {
+        let Some(repo) = self.repos().get(rid).cloned() else {
+            return Ok(false);
+        };
+        let _ = repo.head()?;
+
+        Ok(true)
+    }
+
+    fn get(&self, remote: &RemoteId, rid: Id) -> Result<Option<Doc<Verified>>, IdentityError> {
+        let Some(repo) = self.repos().get(&rid).cloned() else {
+            return Ok(None);
+        };
+        match Doc::load(remote, &repo) {
+            Ok((doc, _)) => Ok(Some(doc.verified()?)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn inventory(&self) -> Result<Inventory, Error> {
+        let repos = self.repos().values().cloned().collect::<Vec<_>>();
+
+        // Like on-disk storage, leave out repositories that don't have a head.
+        Ok(repos
+            .into_iter()
+            .filter(|repo| repo.head().is_ok())
+            .map(|repo| repo.id)
+            .collect())
+    }
+
+    fn repository(&self, rid: Id) -> Result<Self::Repository, Error> {
+        self.repos()
+            .get(&rid)
+            .cloned()
+            .ok_or_else(|| Error::Io(io::Error::from(io::ErrorKind::NotFound)))
+    }
+}
+
+impl WriteStorage for MemoryStorage {
+    type RepositoryMut = MemoryRepository;
+
+    fn repository_mut(&self, rid: Id) -> Result<Self::RepositoryMut, Error> {
+        self.repository(rid)
+    }
+
+    fn create(&self, rid: Id) -> Result<Self::RepositoryMut, Error> {
+        let mut repos = self.repos();
+
+        if repos.contains_key(&rid) {
+            return Err(Error::Io(io::Error::from(io::ErrorKind::AlreadyExists)));
+        }
+        let repo = MemoryRepository::new(rid, self.path_of(&rid))?;
+        repos.insert(rid, repo.clone());
+
+        Ok(repo)
+    }
+}
+
+/// A repository kept in memory.
+///
+/// Cloning the repository is cheap, and clones share the same objects and references.
+#[derive(Clone)]
+pub struct MemoryRepository {
+    id: Id,
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// Repository wrapping an in-memory object database.
+    backend: git2::Repository,
+    /// Virtual path of the repository.
+    path: PathBuf,
+    /// References, by fully qualified name, including their namespace.
+    refs: Mutex<BTreeMap<RefString, Oid>>,
+    /// The reference pointed to by `HEAD`, if any.
+    head: Mutex<Option<RefString>>,
+}
+
+impl fmt::Debug for MemoryRepository {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryRepository")
+            .field("id", &self.id)
+            .field("refs", &*self.refs())
+            .finish()
+    }
+}
+
+impl MemoryRepository {
+    /// Create a new, empty repository, with the given virtual path.
+    pub fn new(id: Id, path: PathBuf) -> Result<Self, Error> {
+        let odb = git2::Odb::new()?;
+        odb.add_new_mempack_backend(1)?;
+        let backend = git2::Repository::from_odb(odb)?;
+
+        Ok(Self {
+            id,
+            inner: Arc::new(Inner {
+                backend,
+                path,
+                refs: Mutex::new(BTreeMap::new()),
+                head: Mutex::new(None),
+            }),
+        })
+    }
+
+    /// Point the fully qualified reference `name` to the given object.
+    pub fn set_reference(&self, name: &RefStr, oid: Oid) {
+        self.refs().insert(name.to_owned(), oid);
+    }
+
+    /// Delete the fully qualified reference `name`. Returns its target, if it existed.
+    pub fn remove_reference(&self, name: &RefStr) -> Option<Oid> {
+        self.refs().remove(name)
+    }
+
+    /// Get the target of the fully qualified reference `name`.
+    pub fn find_reference(&self, name: &RefStr) -> Option<Oid> {
+        self.refs().get(name).copied()
+    }
+
+    /// Get all references, by fully qualified name.
+    pub fn references(&self) -> Vec<(RefString, Oid)> {
+        self.refs()
+            .iter()
+            .map(|(name, oid)| (name.clone(), *oid))
+            .collect()
+    }
+
+    /// Get the identity of the given remote.
+    pub fn identity_of(&self, remote: &RemoteId) -> Result<Identity<Oid>, IdentityError> {
+        Identity::load(remote, self)
+    }
+
+    /// Get the remotes that have signed refs.
+    pub fn remote_ids(&self) -> Vec<RemoteId> {
+        self.namespaced()
+            .filter(|(_, refname, _)| refname.as_refstr() == SIGREFS_BRANCH.as_ref())
+            .map(|(remote, _, _)| remote)
+            .collect()
+    }
+
+    /// Iterate over the references under all namespaces, except for the signed refs branch.
+    fn namespaced_references(&self) -> impl Iterator<Item = (RemoteId, RefString, Oid)> {
+        self.namespaced()
+            .filter(|(_, refname, _)| refname.as_refstr() != SIGREFS_BRANCH.as_ref())
+    }
+
+    /// Iterate over the references under all namespaces.
+    fn namespaced(&self) -> impl Iterator<Item = (RemoteId, RefString, Oid)> {
+        self.references().into_iter().filter_map(|(name, oid)| {
+            git::parse_ref_namespaced::<RemoteId>(name.as_str())
+                .ok()
+                .map(|(remote, refname)| (remote, refname.to_ref_string(), oid))
+        })
+    }
+
+    fn refs(&self) -> std::sync::MutexGuard<BTreeMap<RefString, Oid>> {
+        self.inner
+            .refs
+            .lock()
+            .expect("MemoryRepository::refs: lock is poisoned")
+    }
+
+    fn symbolic_head(&self) -> std::sync::MutexGuard<Option<RefString>> {
+        self.inner
+            .head
+            .lock()
+            .expect("MemoryRepository::symbolic_head: lock is poisoned")
+    }
+
+    fn not_found(name: &str) -> git2::Error {
+        git2::Error::new(
+            git2::ErrorCode::NotFound,
+            git2::ErrorClass::Reference,
+            format!("reference '{name}' not found"),
+        )
+    }
+}
+
+impl ReadRepository for MemoryRepository {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn is_empty(&self) -> Result<bool, git2::Error> {
+        Ok(self.remote_ids().is_empty())
+    }
+
+    fn path(&self) -> &Path {
+        self.inner.path.as_path()
+    }
+
+    fn blob_at<'a>(&'a self, oid: Oid, path: &'a Path) -> Result<git2::Blob<'a>, git::Error> {
+        git::ext::Blob::At {
+            object: oid.into(),
+            path,
+        }
+        .get(&self.inner.backend)
+    }
+
+    fn verify(&self) -> Result<(), VerifyError> {
+        let mut remotes = HashMap::new();
+        for remote in self.remote_ids() {
+            remotes.insert(remote, Refs::from(self.remote(&remote)?.refs));
+        }
+
+        for (remote_id, refname, oid) in self.namespaced_references() {
+            let remote = remotes
+                .get_mut(&remote_id)
+                .ok_or(VerifyError::InvalidRemote(remote_id))?;
+            let signed_oid = remote
+                .remove(&refname)
+                .ok_or_else(|| VerifyError::UnknownRef(remote_id, refname.clone()))?;
+
+            if oid != signed_oid {
+                return Err(VerifyError::InvalidRefTarget(remote_id, refname, *oid));
+            }
+        }
+
+        for (remote, refs) in remotes.into_iter() {
+            // The refs that are left in the map, are ones that were signed, but are not
+            // in the repository.
+            if let Some((name, _)) = refs.into_iter().next() {
+                return Err(VerifyError::MissingRef(remote, name));
+            }
+            self.identity_of(&remote)?.verified(self.id)?;
+        }
+        Ok(())
+    }
+
+    fn head(&self) -> Result<(Qualified, Oid), IdentityError> {
+        let head = self.symbolic_head().clone();
+
+        if let Some(name) = head {
+            if let (Some(oid), Some(name)) =
+                (self.find_reference(&name), Qualified::from_refstr(name))
+            {
+                return Ok((name, oid));
+            }
+        }
+        self.canonical_head()
+    }
+
+    fn canonical_head(&self) -> Result<(Qualified, Oid), IdentityError> {
+        canonical_head(self, &self.inner.backend)
+    }
+
+    fn canonical_refs(&self) -> Result<Vec<CanonicalRef>, IdentityError> {
+        let (_, doc) = self.identity_doc()?;
+        let doc = doc.verified()?;
+        let mut heads: BTreeMap<RefString, Vec<(RemoteId, Oid)>> = BTreeMap::new();
+
+        for (remote, refname, oid) in self.namespaced_references() {
+            if !doc.is_delegate(&remote) {
+                continue;
+            }
+            if doc
+                .canonical_refs
+                .iter()
+                .any(|pattern| is_match(pattern.as_str(), refname.as_str()))
+            {
+                heads.entry(refname).or_default().push((remote, oid));
+            }
+        }
+
+        heads
+            .into_iter()
+            .map(|(name, heads)| {
+                let target = quorum(&self.inner.backend, &name, &heads, doc.threshold)?;
+
+                Ok(CanonicalRef {
+                    name,
+                    target,
+                    heads,
+                })
+            })
+            .collect()
+    }
+
+    fn identity_head(&self) -> Result<Oid, IdentityError> {
+        match self.find_reference(&CANONICAL_IDENTITY) {
+            Some(oid) => Ok(oid),
+            None => self.canonical_identity_head(),
+        }
+    }
+
+    fn canonical_identity_head(&self) -> Result<Oid, IdentityError> {
+        let mut heads = Vec::new();
+
+        for remote in self.remote_ids() {
+            let oid = Doc::<Unverified>::head(&remote, self)?;
+
+            heads.push(oid.into());
+        }
+        canonical_identity_head(&self.inner.backend, heads)
+    }
+
+    fn reference(
+        &self,
+        remote: &RemoteId,
+        reference: &Qualified,
+    ) -> Result<git2::Reference, git::Error> {
+        let name = reference.with_namespace(remote.into()).to_ref_string();
+
+        Err(git2::Error::new(
+            git2::ErrorCode::GenericError,
+            git2::ErrorClass::Reference,
+            format!("cannot borrow reference '{name}' of an in-memory repository"),
+        )
+        .into())
+    }
+
+    fn commit(&self, oid: Oid) -> Result<git2::Commit, git::Error> {
+        self.inner
+            .backend
+            .find_commit(oid.into())
+            .map_err(git::Error::from)
+    }
+
+    fn revwalk(&self, head: Oid) -> Result<git2::Revwalk, git2::Error> {
+        let mut revwalk = self.inner.backend.revwalk()?;
+        revwalk.push(head.into())?;
+
+        Ok(revwalk)
+    }
+
+    fn reference_oid(&self, remote: &RemoteId, reference: &Qualified) -> Result<Oid, git::Error> {
+        let name = reference.with_namespace(remote.into()).to_ref_string();
+
+        self.find_reference(&name)
+            .ok_or_else(|| Self::not_found(name.as_str()).into())
+    }
+
+    fn references_of(&self, remote: &RemoteId) -> Result<Refs, Error> {
+        let refs = self
+            .namespaced()
+            .filter(|(r, _, _)| r == remote)
+            .map(|(_, refname, oid)| (refname, oid))
+            .collect::<BTreeMap<_, _>>();
+
+        Ok(refs.into())
+    }
+
+    fn remote(&self, remote: &RemoteId) -> Result<Remote<Verified>, refs::Error> {
+        let refs = SignedRefs::load(remote, self)?;
+        Ok(Remote::new(*remote, refs))
+    }
+
+    fn remotes(&self) -> Result<Remotes<Verified>, refs::Error> {
+        let mut remotes = Vec::new();
+        for remote in self.remote_ids() {
+            remotes.push((remote, self.remote(&remote)?));
+        }
+        Ok(Remotes::from_iter(remotes))
+    }
+
+    fn identity_doc(&self) -> Result<(Oid, identity::Doc<Unverified>), IdentityError> {
+        let head = self.identity_head()?;
+
+        Doc::<Unverified>::load_at(head, self)
+            .map(|(doc, _)| (head, doc))
+            .map_err(IdentityError::from)
+    }
+
+    fn fetch_limits(&self) -> Result<FetchLimits, Error> {
+        // In-memory repositories aren't fetched over the network.
+        Ok(FetchLimits::default())
+    }
+}
+
+impl WriteRepository for MemoryRepository {
+    fn set_head(&self) -> Result<Oid, IdentityError> {
+        let (branch_ref, head) = self.canonical_head()?;
+        let branch_ref = branch_ref.to_ref_string();
+
+        log::debug!(target: "storage", "Setting ref: {} -> {}", &branch_ref, head);
+        self.set_reference(&branch_ref, head);
+
+        log::debug!(target: "storage", "Setting ref: {} -> {}", HEAD, branch_ref);
+        *self.symbolic_head() = Some(branch_ref);
+
+        Ok(head)
+    }
+
+    fn set_canonical_refs(&self) -> Result<Vec<CanonicalRef>, IdentityError> {
+        let refs = self.canonical_refs()?;
+
+        for r in &refs {
+            if let Some(target) = r.target {
+                log::debug!(target: "storage", "Setting ref: {} -> {}", r.name, target);
+                self.set_reference(&r.name, target);
+            }
+        }
+        Ok(refs)
+    }
+
+    fn set_identity_head(&self) -> Result<Oid, IdentityError> {
+        let head = self.canonical_identity_head()?;
+
+        log::debug!(target: "storage", "Setting ref: {} -> {}", *CANONICAL_IDENTITY, head);
+        self.set_reference(&CANONICAL_IDENTITY, head);
+
+        Ok(head)
+    }
+
+    fn sign_refs<G: Signer>(&self, signer: &G) -> Result<SignedRefs<Verified>, Error> {
+        let remote = signer.public_key();
+        let refs = self.references_of(remote)?;
+        let sigref = SIGREFS_BRANCH.with_namespace(remote.into()).to_ref_string();
+        let signed = refs.signed_after(self.find_reference(&sigref), signer)?;
+        let parent = signed
+            .parent
+            .map(|oid| self.inner.backend.find_commit(*oid))
+            .transpose()?;
+
+        // There is no git configuration to get the author from.
+        let author = git2::Signature::now("radicle", remote.to_string().as_str())?;
+
+        match signed.commit(remote, None, parent.as_ref(), &author, &self.inner.backend)? {
+            refs::Updated::Updated { oid } => {
+                self.set_reference(&sigref, oid);
+
+                if let Some(parent) = signed.parent {
+                    let name = SIGREFS_PARENT.with_namespace(remote.into()).to_ref_string();
+                    self.set_reference(&name, parent);
+                }
+
+                Ok(signed)
+            }
+            refs::Updated::Unchanged { oid } => Ok(SignedRefs::load_at(oid, remote, self)?),
+        }
+    }
+
+    fn raw(&self) -> &git2::Repository {
+        &self.inner.backend
+    }
+}
+
+/// Match a reference name against a pattern with at most one `*` wildcard, which, like in
+/// git, may match across path components.
+fn is_match(pattern: &str, refname: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            refname.len() >= prefix.len() + suffix.len()
+                && refname.starts_with(prefix)
+                && refname.ends_with(suffix)
+        }
+        None => pattern == refname,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use radicle_crypto::test::signer::MockSigner;
+
+    use super::*;
+    use crate::identity::Project;
+
+    #[test]
+    fn test_memory_storage() {
+        let signer = MockSigner::default();
+        let storage = MemoryStorage::new();
+        let project = Project::new(
+            String::from("acme"),
+            String::from("Acme's repository"),
+            git::refname!("master"),
+        )
+        .unwrap();
+        let doc = Doc::initial(project, signer.public_key().into())
+            .verified()
+            .unwrap();
+        let (repo, id) = storage.init(&doc, &signer).unwrap();
+
+        // Create a commit on the default branch.
+        let raw = repo.raw();
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let tree = raw
+            .find_tree(raw.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let head = raw
+            .commit(None, &sig, &sig, "Initial commit", &tree, &[])
+            .unwrap();
+        let branch = git::Qualified::from(git::lit::refs_heads(git::refname!("master")));
+
+        repo.set_reference(
+            &branch
+                .with_namespace(signer.public_key().into())
+                .to_ref_string(),
+            head.into(),
+        );
+        repo.sign_refs(&signer).unwrap();
+        repo.set_identity_head().unwrap();
+
+        assert_eq!(repo.set_head().unwrap(), Oid::from(head));
+        assert_eq!(repo.identity_head().unwrap(), id);
+        assert_eq!(repo.identity_doc().unwrap().1.verified().unwrap(), doc);
+        assert_eq!(
+            repo.reference_oid(signer.public_key(), &branch).unwrap(),
+            head.into()
+        );
+        assert!(repo.reference(signer.public_key(), &branch).is_err());
+        assert_eq!(
+            repo.path(),
+            Path::new(PATH).join(repo.id().canonical()).as_path()
+        );
+        assert!(!repo.path().exists());
+        assert_eq!(repo.remote_ids(), vec![*signer.public_key()]);
+        assert!(!repo.is_empty().unwrap());
+        repo.verify().unwrap();
+
+        assert_eq!(storage.inventory().unwrap(), vec![repo.id()]);
+        assert_eq!(
+            storage.get(signer.public_key(), repo.id()).unwrap(),
+            Some(doc)
+        );
+
+        // References that aren't signed fail verification.
+        repo.set_reference(
+            &git::refname!("refs/heads/unsigned")
+                .with_namespace(signer.public_key().into())
+                .to_ref_string(),
+            head.into(),
+        );
+        assert!(matches!(repo.verify(), Err(VerifyError::UnknownRef(_, _))));
+    }
+
+    #[test]
+    fn test_is_match() {
+        assert!(is_match("refs/heads/*", "refs/heads/master"));
+        assert!(is_match("refs/heads/*", "refs/heads/release/1.0"));
+        assert!(is_match("refs/tags/v*", "refs/tags/v1.0"));
+        assert!(!is_match("refs/tags/v*", "refs/heads/v1.0"));
+        assert!(is_match("refs/heads/master", "refs/heads/master"));
+        assert!(!is_match("refs/heads/master", "refs/heads/main"));
+    }
+}
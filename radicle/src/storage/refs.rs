@@ -24,6 +24,20 @@ pub const REFS_BLOB_PATH: &str = "refs";
 /// File in which the signature over the references is stored in the `refs/rad/sigrefs` branch.
 pub const SIGNATURE_BLOB_PATH: &str = "signature";
 
+/// Version of the signed refs payload.
+///
+/// The version isn't written down: it's given by the presence of the [`SIGREFS_PARENT`]
+/// entry in the payload. Since this entry is also published as a reference, nodes that only
+/// know the first version can still verify signed refs of the second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Version {
+    /// The payload only lists the refs. This is the version of signed refs published by
+    /// older nodes, and of the first signed refs of any remote, which have no parent.
+    V1,
+    /// The payload also lists the previous signed refs commit.
+    V2,
+}
+
 #[derive(Debug)]
 pub enum Updated {
     /// The computed [`Refs`] were stored as a new commit.
@@ -49,6 +63,14 @@ pub enum Error {
     Git(#[from] git2::Error),
     #[error(transparent)]
     GitExt(#[from] git_ext::Error),
+    #[error(
+        "signed refs of {remote} at {new} don't descend from the known signed refs at {known}"
+    )]
+    Rollback {
+        remote: RemoteId,
+        known: Oid,
+        new: Oid,
+    },
 }
 
 impl Error {
@@ -68,22 +90,15 @@ pub struct Refs(BTreeMap<git::RefString, Oid>);
 
 impl Refs {
     /// Verify the given signature on these refs, and return [`SignedRefs`] on success.
+    /// The refs are the signed payload, ie. they include the parent entry, if any.
     pub fn verified(
         self,
         signer: &PublicKey,
         signature: Signature,
     ) -> Result<SignedRefs<Verified>, Error> {
-        let refs = self;
-        let msg = refs.canonical();
-
-        match signer.verify(msg, &signature) {
-            Ok(()) => Ok(SignedRefs {
-                refs,
-                signature,
-                _verified: PhantomData,
-            }),
-            Err(e) => Err(e.into()),
-        }
+        SignedRefs::<Unverified>::new(self, signature)
+            .verified(signer)
+            .map_err(Error::from)
     }
 
     /// Sign these refs with the given signer and return [`SignedRefs`].
@@ -91,17 +106,55 @@ impl Refs {
     where
         G: Signer,
     {
-        let refs = self;
-        let msg = refs.canonical();
-        let signature = signer.try_sign(&msg)?;
+        self.signed_after(None, signer)
+    }
+
+    /// Sign these refs along with `parent`, the previous signed refs commit, if any.
+    /// Signing the parent prevents older signed refs from being replayed in a new commit.
+    /// Any parent entry of these refs is replaced.
+    pub fn signed_after<G>(
+        self,
+        parent: Option<Oid>,
+        signer: &G,
+    ) -> Result<SignedRefs<Verified>, Error>
+    where
+        G: Signer,
+    {
+        let (refs, _) = self.split_parent();
+        let refs = refs.with_parent(parent);
+        let signature = signer.try_sign(&refs.canonical())?;
 
         Ok(SignedRefs {
             refs,
+            parent,
             signature,
             _verified: PhantomData,
         })
     }
 
+    /// Get the parent entry, ie. the previous signed refs commit, if any.
+    pub fn parent(&self) -> Option<Oid> {
+        let name: &git::RefStr = SIGREFS_PARENT.as_ref();
+
+        self.0.get(name).copied()
+    }
+
+    /// Split the parent entry from these refs.
+    fn split_parent(mut self) -> (Self, Option<Oid>) {
+        let name: &git::RefStr = SIGREFS_PARENT.as_ref();
+        let parent = self.0.remove(name);
+
+        (self, parent)
+    }
+
+    /// Join the parent entry to these refs.
+    fn with_parent(mut self, parent: Option<Oid>) -> Self {
+        if let Some(parent) = parent {
+            self.0.insert(SIGREFS_PARENT.to_ref_string(), parent);
+        }
+        self
+    }
+
     /// Get a particular ref.
     pub fn get(&self, name: &git::Qualified) -> Option<Oid> {
         self.0.get(name.to_ref_string().as_refstr()).copied()
@@ -192,6 +245,65 @@ impl DerefMut for Refs {
     }
 }
 
+/// An entry in the history of a remote's signed refs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedRefsAt {
+    /// The signed refs commit.
+    pub at: Oid,
+    /// The previous signed refs commit, if any.
+    pub parent: Option<Oid>,
+    /// Commit time, in seconds since the epoch.
+    pub timestamp: i64,
+    /// The signed refs.
+    pub sigrefs: SignedRefs<Verified>,
+}
+
+/// Check that the signed refs at `new` descend from the `known` signed refs of `remote`, ie.
+/// that updating the remote from `known` to `new` doesn't roll back its refs.
+///
+/// The signed parents of [`Version::V2`] signed refs are followed. Since [`Version::V1`]
+/// signed refs have no signed parent, their commit parents are followed instead, as long as
+/// the known signed refs are also [`Version::V1`]. Commit parents can be forged, so this
+/// only protects against rollbacks once a remote publishes [`Version::V2`] signed refs,
+/// after which [`Version::V1`] signed refs are refused.
+///
+/// The walk stops at the first commit older than the known signed refs, so that only the
+/// history published since is verified.
+pub fn check_update<S: ReadRepository>(
+    remote: &RemoteId,
+    known: Oid,
+    new: Oid,
+    repo: &S,
+) -> Result<(), Error> {
+    let known_version = SignedRefs::load_at(known, remote, repo)?.version();
+    let known_time = repo.commit(known)?.time().seconds();
+    let mut next = Some(new);
+
+    while let Some(at) = next {
+        if at == known {
+            return Ok(());
+        }
+        let commit = repo.commit(at)?;
+        if commit.time().seconds() < known_time {
+            break;
+        }
+        let sigrefs = SignedRefs::load_at(at, remote, repo)?;
+
+        next = match sigrefs.version() {
+            Version::V2 => sigrefs.parent,
+            Version::V1 if known_version == Version::V1 => {
+                commit.parent_ids().next().map(Oid::from)
+            }
+            Version::V1 => None,
+        };
+    }
+    Err(Error::Rollback {
+        remote: *remote,
+        known,
+        new,
+    })
+}
+
 /// Combination of [`Refs`] and a [`Signature`]. The signature is a cryptographic
 /// signature over the refs. This allows us to easily verify if a set of refs
 /// came from a particular key.
@@ -200,17 +312,41 @@ impl DerefMut for Refs {
 /// [`Unverified`].
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct SignedRefs<V> {
+    /// The signed refs, including the parent entry, if any.
     pub refs: Refs,
+    /// The previous signed refs commit, which is part of the signed refs.
+    #[serde(skip)]
+    pub parent: Option<Oid>,
     #[serde(skip)]
     pub signature: Signature,
     #[serde(skip)]
     _verified: PhantomData<V>,
 }
 
+impl<V> SignedRefs<V> {
+    /// The canonical representation of the signed payload.
+    pub fn canonical(&self) -> Vec<u8> {
+        self.refs.canonical()
+    }
+
+    /// The version of the signed payload.
+    pub fn version(&self) -> Version {
+        if self.parent.is_some() {
+            Version::V2
+        } else {
+            Version::V1
+        }
+    }
+}
+
 impl SignedRefs<Unverified> {
+    /// Create signed refs from the signed payload, which may include the parent entry.
     pub fn new(refs: Refs, signature: Signature) -> Self {
+        let parent = refs.parent();
+
         Self {
             refs,
+            parent,
             signature,
             _verified: PhantomData,
         }
@@ -220,6 +356,7 @@ impl SignedRefs<Unverified> {
         match self.verify(signer) {
             Ok(()) => Ok(SignedRefs {
                 refs: self.refs,
+                parent: self.parent,
                 signature: self.signature,
                 _verified: PhantomData,
             }),
@@ -228,7 +365,7 @@ impl SignedRefs<Unverified> {
     }
 
     pub fn verify(&self, signer: &PublicKey) -> Result<(), crypto::Error> {
-        let canonical = self.refs.canonical();
+        let canonical = self.canonical();
 
         match signer.verify(canonical, &self.signature) {
             Ok(()) => Ok(()),
@@ -258,9 +395,11 @@ impl SignedRefs<Verified> {
         match remote.verify(refs.content(), &signature) {
             Ok(()) => {
                 let refs = Refs::from_canonical(refs.content())?;
+                let parent = refs.parent();
 
                 Ok(Self {
                     refs,
+                    parent,
                     signature,
                     _verified: PhantomData,
                 })
@@ -269,6 +408,30 @@ impl SignedRefs<Verified> {
         }
     }
 
+    /// Load the history of a remote's signed refs, from the latest to the first. Every
+    /// entry is verified.
+    pub fn history<S>(remote: &RemoteId, repo: &S) -> Result<Vec<SignedRefsAt>, Error>
+    where
+        S: ReadRepository,
+    {
+        let mut history = Vec::new();
+        let mut next = Some(repo.reference_oid(remote, &SIGREFS_BRANCH)?);
+
+        while let Some(at) = next {
+            let commit = repo.commit(at)?;
+            let parent = commit.parent_ids().next().map(Oid::from);
+
+            history.push(SignedRefsAt {
+                at,
+                parent,
+                timestamp: commit.time().seconds(),
+                sigrefs: SignedRefs::load_at(at, remote, repo)?,
+            });
+            next = parent;
+        }
+        Ok(history)
+    }
+
     /// Save the signed refs to disk.
     /// This creates a new commit on the signed refs branch, and updates the branch pointer.
     pub fn save<S: WriteRepository>(
@@ -285,8 +448,32 @@ impl SignedRefs<Verified> {
             Err(e) => return Err(e.into()),
         };
 
+        let sigref = sigref.with_namespace(remote.into());
+        let author = repo.raw().signature()?;
+        let updated = self.commit(remote, Some(&sigref), parent.as_ref(), &author, repo.raw())?;
+
+        if let (Updated::Updated { .. }, Some(parent)) = (&updated, self.parent) {
+            repo.raw().reference(
+                SIGREFS_PARENT.with_namespace(remote.into()).as_str(),
+                *parent,
+                true,
+                "sigrefs-parent (radicle)",
+            )?;
+        }
+        Ok(updated)
+    }
+
+    /// Write these refs as a commit on top of `parent`, the previous signed refs of
+    /// `remote`, if any. The reference named `refname` is updated if given.
+    pub(crate) fn commit(
+        &self,
+        remote: &RemoteId,
+        refname: Option<&str>,
+        parent: Option<&git2::Commit>,
+        author: &git2::Signature,
+        raw: &git2::Repository,
+    ) -> Result<Updated, Error> {
         let tree = {
-            let raw = repo.raw();
             let refs_blob_oid = raw.blob(&self.canonical())?;
             let sig_blob_oid = raw.blob(self.signature.as_ref())?;
 
@@ -299,23 +486,30 @@ impl SignedRefs<Verified> {
             raw.find_tree(oid)
         }?;
 
-        if let Some(ref parent) = parent {
-            if parent.tree()?.id() == tree.id() {
+        if let Some(parent) = parent {
+            // The signed parent always changes, so only the refs are compared.
+            let blob = parent
+                .tree()?
+                .get_path(Path::new(REFS_BLOB_PATH))?
+                .to_object(raw)?
+                .peel_to_blob()?;
+            let (refs, _) = Refs::from_canonical(blob.content())?.split_parent();
+            let (ours, _) = self.refs.clone().split_parent();
+
+            if refs.canonical() == ours.canonical() {
                 return Ok(Updated::Unchanged {
                     oid: parent.id().into(),
                 });
             }
         }
 
-        let sigref = sigref.with_namespace(remote.into());
-        let author = repo.raw().signature()?;
-        let commit = repo.raw().commit(
-            Some(&sigref),
-            &author,
-            &author,
+        let commit = raw.commit(
+            refname,
+            author,
+            author,
             &format!("Update signature for {remote}\n"),
             &tree,
-            &parent.iter().collect::<Vec<&git2::Commit>>(),
+            &parent.into_iter().collect::<Vec<&git2::Commit>>(),
         );
 
         match commit {
@@ -334,6 +528,7 @@ impl SignedRefs<Verified> {
     pub fn unverified(self) -> SignedRefs<Unverified> {
         SignedRefs {
             refs: self.refs,
+            parent: self.parent,
             signature: self.signature,
             _verified: PhantomData,
         }
@@ -20,6 +20,9 @@ pub struct MockStorage {
     /// All refs keyed by RID.
     /// Each value is a map of refs keyed by node Id (public key).
     pub remotes: HashMap<Id, HashMap<NodeId, refs::SignedRefs<Verified>>>,
+
+    /// Fetch limits keyed by RID. Repositories without an entry have no limits.
+    pub limits: HashMap<Id, FetchLimits>,
 }
 
 impl MockStorage {
@@ -28,6 +31,7 @@ impl MockStorage {
             path: PathBuf::default(),
             inventory: inventory.into_iter().collect(),
             remotes: HashMap::new(),
+            limits: HashMap::new(),
         }
     }
 
@@ -36,6 +40,7 @@ impl MockStorage {
             path: PathBuf::default(),
             inventory: HashMap::new(),
             remotes: HashMap::new(),
+            limits: HashMap::new(),
         }
     }
 
@@ -85,6 +90,7 @@ impl ReadStorage for MockStorage {
             id: rid,
             doc: doc.clone(),
             remotes: self.remotes.get(&rid).cloned().unwrap_or_default(),
+            limits: self.limits.get(&rid).cloned().unwrap_or_default(),
         })
     }
 }
@@ -98,6 +104,7 @@ impl WriteStorage for MockStorage {
             id: rid,
             doc: doc.clone(),
             remotes: self.remotes.get(&rid).cloned().unwrap_or_default(),
+            limits: self.limits.get(&rid).cloned().unwrap_or_default(),
         })
     }
 
@@ -111,6 +118,7 @@ pub struct MockRepository {
     id: Id,
     doc: Doc<Verified>,
     remotes: HashMap<NodeId, refs::SignedRefs<Verified>>,
+    limits: FetchLimits,
 }
 
 impl ReadRepository for MockRepository {
@@ -130,6 +138,10 @@ impl ReadRepository for MockRepository {
         todo!()
     }
 
+    fn canonical_refs(&self) -> Result<Vec<CanonicalRef>, IdentityError> {
+        Ok(vec![])
+    }
+
     fn verify(&self) -> Result<(), VerifyError> {
         Ok(())
     }
@@ -202,6 +214,10 @@ impl ReadRepository for MockRepository {
     fn canonical_identity_head(&self) -> Result<Oid, IdentityError> {
         todo!()
     }
+
+    fn fetch_limits(&self) -> Result<FetchLimits, Error> {
+        Ok(self.limits.clone())
+    }
 }
 
 impl WriteRepository for MockRepository {
@@ -213,6 +229,10 @@ impl WriteRepository for MockRepository {
         todo!()
     }
 
+    fn set_canonical_refs(&self) -> Result<Vec<CanonicalRef>, IdentityError> {
+        Ok(vec![])
+    }
+
     fn sign_refs<G: Signer>(
         &self,
         _signer: &G,